@@ -0,0 +1,134 @@
+use crate::{CrcParams, CrcWidth};
+
+/// The errors a CRC polynomial fails to detect in codewords of a given length.
+///
+/// Only the polynomial matters: `init`, `xor_out` and the reflections do not change which errors are detected.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PolyAnalysis {
+    pub width: u8,
+    /// In normal form, without the x^width term
+    pub poly: u64,
+    /// Number of message bits. The codeword is `width` bits longer
+    pub data_len: usize,
+    /// The number of undetected errors flipping 1, 2, 3 ... bits of the codeword, up to the maximum weight analysed
+    pub undetected: Vec<u64>,
+}
+
+impl PolyAnalysis {
+    /// Counts the undetected errors of up to `max_weight` bits, which takes about `(data_len + width)^(max_weight - 1)` steps
+    pub fn new(width: u8, poly: u64, data_len: usize, max_weight: usize) -> Self {
+        assert!((1..=64).contains(&width), "The width must be from 1 to 64.");
+        let columns = syndromes(width, poly, data_len + width as usize);
+        let mut sorted: Vec<(u64, usize)> = columns.iter().cloned().zip(0..).collect();
+        sorted.sort_unstable();
+        let undetected = (1..=max_weight)
+            .map(|weight| count_zero_sums(&columns, &sorted, 0, weight, 0))
+            .collect();
+        Self { width, poly, data_len, undetected }
+    }
+
+    /// The fewest flipped bits that can go undetected, or None if no error up to the maximum weight analysed does
+    pub fn hamming_distance(&self) -> Option<usize> {
+        self.undetected.iter().position(|&count| count != 0).map(|i| i + 1)
+    }
+
+    /// The number of undetected errors of `weight` bits, if it was analysed
+    pub fn undetected_of_weight(&self, weight: usize) -> Option<u64> {
+        weight.checked_sub(1).and_then(|i| self.undetected.get(i).cloned())
+    }
+}
+
+/// The syndrome of flipping each bit of a codeword of `len` bits, i.e. x^k mod the generator polynomial.
+/// An error goes undetected when the syndromes of its bits xor to zero
+fn syndromes(width: u8, poly: u64, len: usize) -> Vec<u64> {
+    let mask = u64::MAX >> (64 - width as u32);
+    let top = 1 << (width - 1);
+    let mut syndrome = 1 & mask;
+    (0..len).map(|_| {
+        let current = syndrome;
+        syndrome = if syndrome & top != 0 { ((syndrome << 1) ^ poly) & mask } else { (syndrome << 1) & mask };
+        current
+    }).collect()
+}
+
+/// Number of sets of `remaining` columns from `start` on whose xor with `sum` is zero.
+/// The last column is looked up in the sorted (column, index) pairs instead of enumerated
+fn count_zero_sums(columns: &[u64], sorted: &[(u64, usize)], start: usize, remaining: usize, sum: u64) -> u64 {
+    if remaining == 1 {
+        let lo = sorted.partition_point(|&entry| entry < (sum, start));
+        let hi = sorted.partition_point(|&(column, _)| column <= sum);
+        return (hi - lo) as u64;
+    }
+    (start..columns.len())
+        .map(|i| count_zero_sums(columns, sorted, i + 1, remaining - 1, sum ^ columns[i]))
+        .sum()
+}
+
+/// Every polynomial of the width with the x^0 term, analysed, from the best.
+///
+/// The best has the largest Hamming distance, and then the fewest undetected errors of each weight in turn.
+/// There are 2^(width - 1) polynomials, so this is only practical for small widths
+pub fn search_polys(width: u8, data_len: usize, max_weight: usize) -> Vec<PolyAnalysis> {
+    assert!((1..=32).contains(&width), "The width must be from 1 to 32 to search.");
+    let mut results: Vec<PolyAnalysis> = (0..1_u64 << (width - 1))
+        .map(|i| PolyAnalysis::new(width, (i << 1) | 1, data_len, max_weight))
+        .collect();
+    results.sort_by(|a, b| a.undetected.cmp(&b.undetected).then(a.poly.cmp(&b.poly)));
+    results
+}
+
+impl<W: CrcWidth> CrcParams<W> {
+    /// `PolyAnalysis` of the polynomial over messages of `data_len` bits
+    pub fn analyse(&self, data_len: usize, max_weight: usize) -> PolyAnalysis {
+        PolyAnalysis::new(self.width, self.poly.into_u64(), data_len, max_weight)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_analysis_against_brute_force() {
+        // Every codeword of a linear code is an undetected error of its weight
+        let params = CrcParams { name: "", width: 5, poly: 0x05_u8, init: 0, ref_in: false, ref_out: false, xor_out: 0, check: 0 };
+        let data_len = 10;
+        let mut weights = vec![0; data_len + 5];
+        for message in 1..(1_u32 << data_len) {
+            let bits = (0..data_len).map(|i| message >> i & 1 != 0);
+            let checksum = params.checksum_bits(bits);
+            weights[(message.count_ones() + checksum.count_ones()) as usize - 1] += 1;
+        }
+        let analysis = params.analyse(data_len, data_len + 5);
+        assert_eq!(analysis.undetected, weights);
+        assert_eq!(analysis.hamming_distance(), Some(3));
+        // Whatever the init, xor_out and reflections
+        assert_eq!(CRC5_PARAMS.analyse(data_len, 4).undetected, &weights[..4]);
+    }
+
+    #[test]
+    fn test_analysis_odd_errors() {
+        // x^8 + x^2 + x + 1 has the factor x + 1, which detects any odd number of flipped bits
+        let analysis = CRC8_PARAMS.analyse(32, 5);
+        assert_eq!(analysis.undetected_of_weight(1), Some(0));
+        assert_eq!(analysis.undetected_of_weight(3), Some(0));
+        assert_eq!(analysis.undetected_of_weight(5), Some(0));
+        assert_eq!(analysis.undetected_of_weight(6), None);
+        assert_eq!(analysis.hamming_distance(), Some(4));
+    }
+
+    #[test]
+    fn test_search_polys() {
+        let results = search_polys(5, 20, 4);
+        assert_eq!(results.len(), 16);
+        assert!(results.windows(2).all(|pair| pair[0].undetected <= pair[1].undetected));
+        // The polynomial of symcode's 25 bit codes is the best of its width
+        assert_eq!(results[0], CRC5_PARAMS.analyse(20, 4));
+        assert_eq!(results[0].hamming_distance(), Some(3));
+
+        // The odd weights are left to the x + 1 factor
+        let results = search_polys(8, 32, 4);
+        assert_eq!(results[0].hamming_distance(), Some(4));
+        assert!(results[0].undetected[3] < CRC8_PARAMS.analyse(32, 4).undetected[3]);
+    }
+}
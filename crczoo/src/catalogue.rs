@@ -0,0 +1,189 @@
+use crate::*;
+
+macro_rules! presets {
+    ($($const_name:ident: $t:ty = ($name:expr, $width:expr, $poly:expr, $init:expr, $ref_in:expr, $ref_out:expr, $xor_out:expr, $check:expr);)*) => {$(
+        pub const $const_name: CrcParams<$t> = CrcParams {
+            name: $name, width: $width, poly: $poly, init: $init, ref_in: $ref_in, ref_out: $ref_out, xor_out: $xor_out, check: $check,
+        };
+    )*};
+}
+
+// Widths other than those of the predefined functions, from https://reveng.sourceforge.io/crc-catalogue/
+presets! {
+    CRC3_GSM_PARAMS: u8 = ("CRC-3/GSM", 3, 0x3, 0x0, false, false, 0x7, 0x4);
+    CRC3_ROHC_PARAMS: u8 = ("CRC-3/ROHC", 3, 0x3, 0x7, true, true, 0x0, 0x6);
+    CRC4_G_704_PARAMS: u8 = ("CRC-4/G-704", 4, 0x3, 0x0, true, true, 0x0, 0x7);
+    CRC4_INTERLAKEN_PARAMS: u8 = ("CRC-4/INTERLAKEN", 4, 0x3, 0xF, false, false, 0xF, 0xB);
+    CRC5_EPC_C1G2_PARAMS: u8 = ("CRC-5/EPC-C1G2", 5, 0x09, 0x09, false, false, 0x00, 0x00);
+    CRC5_G_704_PARAMS: u8 = ("CRC-5/G-704", 5, 0x15, 0x00, true, true, 0x00, 0x07);
+    CRC6_CDMA2000_A_PARAMS: u8 = ("CRC-6/CDMA2000-A", 6, 0x27, 0x3F, false, false, 0x00, 0x0D);
+    CRC6_CDMA2000_B_PARAMS: u8 = ("CRC-6/CDMA2000-B", 6, 0x07, 0x3F, false, false, 0x00, 0x3B);
+    CRC6_DARC_PARAMS: u8 = ("CRC-6/DARC", 6, 0x19, 0x00, true, true, 0x00, 0x26);
+    CRC6_G_704_PARAMS: u8 = ("CRC-6/G-704", 6, 0x03, 0x00, true, true, 0x00, 0x06);
+    CRC6_GSM_PARAMS: u8 = ("CRC-6/GSM", 6, 0x2F, 0x00, false, false, 0x3F, 0x13);
+    CRC7_MMC_PARAMS: u8 = ("CRC-7/MMC", 7, 0x09, 0x00, false, false, 0x00, 0x75);
+    CRC7_ROHC_PARAMS: u8 = ("CRC-7/ROHC", 7, 0x4F, 0x7F, true, true, 0x00, 0x53);
+    CRC7_UMTS_PARAMS: u8 = ("CRC-7/UMTS", 7, 0x45, 0x00, false, false, 0x00, 0x61);
+    CRC10_ATM_PARAMS: u16 = ("CRC-10/ATM", 10, 0x233, 0x000, false, false, 0x000, 0x199);
+    CRC10_CDMA2000_PARAMS: u16 = ("CRC-10/CDMA2000", 10, 0x3D9, 0x3FF, false, false, 0x000, 0x233);
+    CRC10_GSM_PARAMS: u16 = ("CRC-10/GSM", 10, 0x175, 0x000, false, false, 0x3FF, 0x12A);
+    CRC11_FLEXRAY_PARAMS: u16 = ("CRC-11/FLEXRAY", 11, 0x385, 0x01A, false, false, 0x000, 0x5A3);
+    CRC11_UMTS_PARAMS: u16 = ("CRC-11/UMTS", 11, 0x307, 0x000, false, false, 0x000, 0x061);
+    CRC12_CDMA2000_PARAMS: u16 = ("CRC-12/CDMA2000", 12, 0xF13, 0xFFF, false, false, 0x000, 0xD4D);
+    CRC12_DECT_PARAMS: u16 = ("CRC-12/DECT", 12, 0x80F, 0x000, false, false, 0x000, 0xF5B);
+    CRC12_GSM_PARAMS: u16 = ("CRC-12/GSM", 12, 0xD31, 0x000, false, false, 0xFFF, 0xB34);
+    CRC12_UMTS_PARAMS: u16 = ("CRC-12/UMTS", 12, 0x80F, 0x000, false, true, 0x000, 0xDAF);
+    CRC13_BBC_PARAMS: u16 = ("CRC-13/BBC", 13, 0x1CF5, 0x0000, false, false, 0x0000, 0x04FA);
+    CRC14_DARC_PARAMS: u16 = ("CRC-14/DARC", 14, 0x0805, 0x0000, true, true, 0x0000, 0x082D);
+    CRC14_GSM_PARAMS: u16 = ("CRC-14/GSM", 14, 0x202D, 0x0000, false, false, 0x3FFF, 0x30AE);
+    CRC15_CAN_PARAMS: u16 = ("CRC-15/CAN", 15, 0x4599, 0x0000, false, false, 0x0000, 0x059E);
+    CRC15_MPT1327_PARAMS: u16 = ("CRC-15/MPT1327", 15, 0x6815, 0x0000, false, false, 0x0001, 0x2566);
+    CRC17_CAN_FD_PARAMS: u32 = ("CRC-17/CAN-FD", 17, 0x1685B, 0x00000, false, false, 0x00000, 0x04F03);
+    CRC21_CAN_FD_PARAMS: u32 = ("CRC-21/CAN-FD", 21, 0x102899, 0x000000, false, false, 0x000000, 0x0ED841);
+    CRC24_BLE_PARAMS: u32 = ("CRC-24/BLE", 24, 0x00065B, 0x555555, true, true, 0x000000, 0xC25A56);
+    CRC24_FLEXRAY_A_PARAMS: u32 = ("CRC-24/FLEXRAY-A", 24, 0x5D6DCB, 0xFEDCBA, false, false, 0x000000, 0x7979BD);
+    CRC24_FLEXRAY_B_PARAMS: u32 = ("CRC-24/FLEXRAY-B", 24, 0x5D6DCB, 0xABCDEF, false, false, 0x000000, 0x1F23B8);
+    CRC24_INTERLAKEN_PARAMS: u32 = ("CRC-24/INTERLAKEN", 24, 0x328B63, 0xFFFFFF, false, false, 0xFFFFFF, 0xB4F3E6);
+    CRC24_LTE_A_PARAMS: u32 = ("CRC-24/LTE-A", 24, 0x864CFB, 0x000000, false, false, 0x000000, 0xCDE703);
+    CRC24_LTE_B_PARAMS: u32 = ("CRC-24/LTE-B", 24, 0x800063, 0x000000, false, false, 0x000000, 0x23EF52);
+    CRC24_OPENPGP_PARAMS: u32 = ("CRC-24/OPENPGP", 24, 0x864CFB, 0xB704CE, false, false, 0x000000, 0x21CF02);
+    CRC30_CDMA_PARAMS: u32 = ("CRC-30/CDMA", 30, 0x2030B9C7, 0x3FFFFFFF, false, false, 0x3FFFFFFF, 0x04C34ABF);
+    CRC31_PHILIPS_PARAMS: u32 = ("CRC-31/PHILIPS", 31, 0x04C11DB7, 0x7FFFFFFF, false, false, 0x7FFFFFFF, 0x0CE9E46C);
+    CRC40_GSM_PARAMS: u64 = ("CRC-40/GSM", 40, 0x0004820009, 0x0000000000, false, false, 0xFFFFFFFFFF, 0xD4164FC646);
+    CRC64_ECMA_182_PARAMS: u64 = ("CRC-64/ECMA-182", 64, 0x42F0E1EBA9EA3693, 0x0000000000000000, false, false, 0x0000000000000000, 0x6C40DF5F0B497347);
+    CRC64_GO_ISO_PARAMS: u64 = ("CRC-64/GO-ISO", 64, 0x000000000000001B, 0xFFFFFFFFFFFFFFFF, true, true, 0xFFFFFFFFFFFFFFFF, 0xB90956C775A41001);
+    CRC64_REDIS_PARAMS: u64 = ("CRC-64/REDIS", 64, 0xAD93D23594C935A9, 0x0000000000000000, true, true, 0x0000000000000000, 0xE9C6D914C4B8D9CA);
+    CRC64_WE_PARAMS: u64 = ("CRC-64/WE", 64, 0x42F0E1EBA9EA3693, 0xFFFFFFFFFFFFFFFF, false, false, 0xFFFFFFFFFFFFFFFF, 0x62EC59E3F1A4F00A);
+    CRC64_XZ_PARAMS: u64 = ("CRC-64/XZ", 64, 0x42F0E1EBA9EA3693, 0xFFFFFFFFFFFFFFFF, true, true, 0xFFFFFFFFFFFFFFFF, 0x995DC9BBDF1939FA);
+}
+
+/// Every preset of the crate, by width, in a register wide enough for any of them.
+///
+/// The predefined functions of the widths 5, 8, 16 and 32 are faster, see Readme.
+pub const CATALOGUE: &[CrcParams<u64>] = &[
+    CRC3_GSM_PARAMS.widen(),
+    CRC3_ROHC_PARAMS.widen(),
+    CRC4_G_704_PARAMS.widen(),
+    CRC4_INTERLAKEN_PARAMS.widen(),
+    CRC5_EPC_C1G2_PARAMS.widen(),
+    CRC5_G_704_PARAMS.widen(),
+    CRC5_PARAMS.widen(),
+    CRC6_CDMA2000_A_PARAMS.widen(),
+    CRC6_CDMA2000_B_PARAMS.widen(),
+    CRC6_DARC_PARAMS.widen(),
+    CRC6_G_704_PARAMS.widen(),
+    CRC6_GSM_PARAMS.widen(),
+    CRC7_MMC_PARAMS.widen(),
+    CRC7_ROHC_PARAMS.widen(),
+    CRC7_UMTS_PARAMS.widen(),
+    CRC8_PARAMS.widen(),
+    CRC8_CDMA2000_PARAMS.widen(),
+    CRC8_DARC_PARAMS.widen(),
+    CRC8_DVB_S2_PARAMS.widen(),
+    CRC8_EBU_PARAMS.widen(),
+    CRC8_I_CODE_PARAMS.widen(),
+    CRC8_ITU_PARAMS.widen(),
+    CRC8_MAXIM_PARAMS.widen(),
+    CRC8_ROHC_PARAMS.widen(),
+    CRC8_WCDMA_PARAMS.widen(),
+    CRC10_ATM_PARAMS.widen(),
+    CRC10_CDMA2000_PARAMS.widen(),
+    CRC10_GSM_PARAMS.widen(),
+    CRC11_FLEXRAY_PARAMS.widen(),
+    CRC11_UMTS_PARAMS.widen(),
+    CRC12_CDMA2000_PARAMS.widen(),
+    CRC12_DECT_PARAMS.widen(),
+    CRC12_GSM_PARAMS.widen(),
+    CRC12_UMTS_PARAMS.widen(),
+    CRC13_BBC_PARAMS.widen(),
+    CRC14_DARC_PARAMS.widen(),
+    CRC14_GSM_PARAMS.widen(),
+    CRC15_CAN_PARAMS.widen(),
+    CRC15_MPT1327_PARAMS.widen(),
+    CRC16_ARC_PARAMS.widen(),
+    CRC16_AUG_CCITT_PARAMS.widen(),
+    CRC16_BUYPASS_PARAMS.widen(),
+    CRC16_CCITT_FALSE_PARAMS.widen(),
+    CRC16_CDMA2000_PARAMS.widen(),
+    CRC16_DDS_110_PARAMS.widen(),
+    CRC16_DECT_R_PARAMS.widen(),
+    CRC16_DECT_X_PARAMS.widen(),
+    CRC16_DNP_PARAMS.widen(),
+    CRC16_EN_13757_PARAMS.widen(),
+    CRC16_GENIBUS_PARAMS.widen(),
+    CRC16_KERMIT_PARAMS.widen(),
+    CRC16_MAXIM_PARAMS.widen(),
+    CRC16_MCRF4XX_PARAMS.widen(),
+    CRC16_MODBUS_PARAMS.widen(),
+    CRC16_RIELLO_PARAMS.widen(),
+    CRC16_T10_DIF_PARAMS.widen(),
+    CRC16_TELEDISK_PARAMS.widen(),
+    CRC16_TMS37157_PARAMS.widen(),
+    CRC16_USB_PARAMS.widen(),
+    CRC16_X_25_PARAMS.widen(),
+    CRC16_XMODEM_PARAMS.widen(),
+    CRC16_A_PARAMS.widen(),
+    CRC17_CAN_FD_PARAMS.widen(),
+    CRC21_CAN_FD_PARAMS.widen(),
+    CRC24_BLE_PARAMS.widen(),
+    CRC24_FLEXRAY_A_PARAMS.widen(),
+    CRC24_FLEXRAY_B_PARAMS.widen(),
+    CRC24_INTERLAKEN_PARAMS.widen(),
+    CRC24_LTE_A_PARAMS.widen(),
+    CRC24_LTE_B_PARAMS.widen(),
+    CRC24_OPENPGP_PARAMS.widen(),
+    CRC30_CDMA_PARAMS.widen(),
+    CRC31_PHILIPS_PARAMS.widen(),
+    CRC32_PARAMS.widen(),
+    CRC32_BZIP2_PARAMS.widen(),
+    CRC32_JAMCRC_PARAMS.widen(),
+    CRC32_MPEG2_PARAMS.widen(),
+    CRC32_POSIX_PARAMS.widen(),
+    CRC32_XFER_PARAMS.widen(),
+    CRC32C_PARAMS.widen(),
+    CRC32D_PARAMS.widen(),
+    CRC32Q_PARAMS.widen(),
+    CRC40_GSM_PARAMS.widen(),
+    CRC64_ECMA_182_PARAMS.widen(),
+    CRC64_GO_ISO_PARAMS.widen(),
+    CRC64_REDIS_PARAMS.widen(),
+    CRC64_WE_PARAMS.widen(),
+    CRC64_XZ_PARAMS.widen(),
+];
+
+/// The preset with the given name, e.g. "CRC-24/OPENPGP", ignoring case
+pub fn find_params(name: &str) -> Option<&'static CrcParams<u64>> {
+    CATALOGUE.iter().find(|params| params.name.eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHECK_SEQUENCE: &[u8] = b"123456789";
+
+    #[test]
+    fn test_catalogue_check() {
+        for params in CATALOGUE.iter() {
+            assert_eq!(Crc::new(params).checksum(CHECK_SEQUENCE), params.check, "{}", params.name);
+            assert_eq!(params.checksum_bits(CHECK_SEQUENCE.iter().flat_map(|byte| {
+                (0..8).map(move |i| byte & (if params.ref_in { 1 << i } else { 0x80 >> i }) != 0)
+            })), params.check, "{}", params.name);
+        }
+        // In the narrowest register
+        assert_eq!(Crc::new(&CRC3_GSM_PARAMS).checksum(CHECK_SEQUENCE), CRC3_GSM_PARAMS.check);
+        assert_eq!(Crc::new(&CRC12_UMTS_PARAMS).checksum(CHECK_SEQUENCE), CRC12_UMTS_PARAMS.check);
+        assert_eq!(Crc::new(&CRC24_BLE_PARAMS).checksum(CHECK_SEQUENCE), CRC24_BLE_PARAMS.check);
+        assert_eq!(Crc::new(&CRC40_GSM_PARAMS).checksum(CHECK_SEQUENCE), CRC40_GSM_PARAMS.check);
+    }
+
+    #[test]
+    fn test_catalogue_names() {
+        assert_eq!(find_params("crc-24/openpgp"), Some(&CRC24_OPENPGP_PARAMS.widen()));
+        assert_eq!(find_params("CRC-5/USB"), Some(&CRC5_PARAMS.widen()));
+        assert_eq!(find_params("CRC-99"), None);
+        for (i, params) in CATALOGUE.iter().enumerate() {
+            assert!(CATALOGUE.iter().skip(i + 1).all(|other| other.name != params.name), "{}", params.name);
+        }
+    }
+}
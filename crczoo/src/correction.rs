@@ -0,0 +1,155 @@
+use crate::{CrcParams, CrcWidth};
+
+/// The longest burst `CrcParams::correct_errors` searches for, as the search doubles with each bit of it
+pub const MAX_CORRECTABLE_BURST_LEN: usize = 16;
+
+/// The outcome of `CrcParams::correct_errors`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CrcCorrection {
+    /// The checksum agrees with the message
+    NoError,
+    /// Some of the `len` bits from `position` of the codeword were flipped back, the first and the last among them
+    Corrected { position: usize, len: usize },
+    /// No burst of errors up to the maximum length explains the checksum, or more than one does
+    Uncorrectable,
+}
+
+impl<W: CrcWidth> CrcParams<W> {
+    /// The checksum bits at the end of the codeword, from the most significant
+    fn checksum_of_codeword(&self, codeword: &[bool]) -> W {
+        let message_len = codeword.len() - self.width as usize;
+        codeword[message_len..].iter().fold(W::ZERO, |acc, &bit| (acc << 1) ^ if bit { W::ONE } else { W::ZERO })
+    }
+
+    /// The CRC of the message part of the codeword xor its checksum part, which is zero if they agree.
+    ///
+    /// A codeword is the message bits followed by the `width` bits of its CRC, from the most significant.
+    /// Panics if the codeword is shorter than the CRC.
+    pub fn syndrome(&self, codeword: &[bool]) -> W {
+        assert!(codeword.len() >= self.width as usize, "The codeword is shorter than the checksum.");
+        let message_len = codeword.len() - self.width as usize;
+        self.checksum_bits(codeword[..message_len].iter().cloned()) ^ self.checksum_of_codeword(codeword)
+    }
+
+    /// The syndrome of a codeword of `len` bits with only the bit at `position` flipped.
+    /// As the CRC is linear but for `init` and `xor_out`, the syndrome of a set of errors is the xor of theirs
+    fn error_syndrome(&self, len: usize, position: usize) -> W {
+        let message_len = len - self.width as usize;
+        if position < message_len {
+            let linear = CrcParams { init: W::ZERO, xor_out: W::ZERO, ..*self };
+            linear.checksum_bits((0..message_len).map(|i| i == position))
+        } else {
+            W::ONE << (len - 1 - position) as u32
+        }
+    }
+
+    /// Flips back the burst of errors, of up to `max_burst_len` bits, which explains the syndrome of the codeword.
+    ///
+    /// The correction is only made if exactly one burst explains it, which depends on the polynomial,
+    /// the length of the codeword and `max_burst_len`. Correcting errors gives up detecting some of the larger ones,
+    /// which may be mistaken for a correctable error.
+    ///
+    /// Every position is tried with every pattern of the bits inside the burst, i.e. about
+    /// `len * 2^(max_burst_len - 2)` syndromes. Panics if `max_burst_len` exceeds `MAX_CORRECTABLE_BURST_LEN`.
+    pub fn correct_errors(&self, codeword: &mut [bool], max_burst_len: usize) -> CrcCorrection {
+        assert!(max_burst_len <= MAX_CORRECTABLE_BURST_LEN, "The burst is too long to be corrected.");
+        let syndrome = self.syndrome(codeword);
+        if syndrome == W::ZERO {
+            return CrcCorrection::NoError;
+        }
+
+        let len = codeword.len();
+        let mut found = None;
+        for burst_len in 1..=max_burst_len.min(len) {
+            for position in 0..=(len - burst_len) {
+                let ends = if burst_len == 1 {
+                    self.error_syndrome(len, position)
+                } else {
+                    self.error_syndrome(len, position) ^ self.error_syndrome(len, position + burst_len - 1)
+                };
+                // The bits between the first and the last may or may not be flipped
+                let num_inner = burst_len.saturating_sub(2);
+                for inner in 0..(1_u64 << num_inner) {
+                    let burst_syndrome = (0..num_inner)
+                        .filter(|k| inner & (1 << k) != 0)
+                        .fold(ends, |acc, k| acc ^ self.error_syndrome(len, position + 1 + k));
+                    if burst_syndrome == syndrome {
+                        if found.is_some() {
+                            return CrcCorrection::Uncorrectable;
+                        }
+                        found = Some((position, burst_len, inner));
+                    }
+                }
+            }
+        }
+
+        match found {
+            Some((position, burst_len, inner)) => {
+                codeword[position] = !codeword[position];
+                if burst_len > 1 {
+                    codeword[position + burst_len - 1] = !codeword[position + burst_len - 1];
+                }
+                for k in (0..burst_len.saturating_sub(2)).filter(|k| inner & (1 << k) != 0) {
+                    codeword[position + 1 + k] = !codeword[position + 1 + k];
+                }
+                CrcCorrection::Corrected { position, len: burst_len }
+            },
+            None => CrcCorrection::Uncorrectable,
+        }
+    }
+
+    /// `correct_errors` of a single bit
+    pub fn correct_single_bit_error(&self, codeword: &mut [bool]) -> CrcCorrection {
+        self.correct_errors(codeword, 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    /// The message bits followed by the bits of its CRC
+    fn codeword<W: CrcWidth>(params: &CrcParams<W>, message: &[bool]) -> Vec<bool> {
+        let checksum = params.checksum_bits(message.iter().cloned());
+        let width = params.width as u32;
+        message.iter().cloned()
+            .chain((0..width).map(|i| (checksum >> (width - 1 - i)) & W::ONE != W::ZERO))
+            .collect()
+    }
+
+    #[test]
+    fn test_correction_single_bit() {
+        let message: Vec<bool> = (0..20).map(|i| i % 3 == 0 || i % 7 == 2).collect();
+        let original = codeword(&CRC5_PARAMS, &message);
+        assert_eq!(CRC5_PARAMS.syndrome(&original), 0);
+        assert_eq!(CRC5_PARAMS.correct_single_bit_error(&mut original.clone()), CrcCorrection::NoError);
+
+        // Every bit of the message and of the checksum
+        for position in 0..original.len() {
+            let mut received = original.clone();
+            received[position] = !received[position];
+            assert_eq!(CRC5_PARAMS.correct_single_bit_error(&mut received), CrcCorrection::Corrected { position, len: 1 });
+            assert_eq!(received, original);
+        }
+    }
+
+    #[test]
+    fn test_correction_burst() {
+        let message: Vec<bool> = (0..32).map(|i| i % 5 == 1 || i % 2 == 0).collect();
+        let original = codeword(&CRC16_XMODEM_PARAMS, &message);
+        for position in 0..original.len() - 3 {
+            let mut received = original.clone();
+            received[position] = !received[position];
+            received[position + 2] = !received[position + 2];
+            assert_eq!(CRC16_XMODEM_PARAMS.correct_errors(&mut received, 3), CrcCorrection::Corrected { position, len: 3 });
+            assert_eq!(received, original);
+        }
+
+        // Too many errors for a single bit
+        let mut received = original.clone();
+        received[3] = !received[3];
+        received[20] = !received[20];
+        received[40] = !received[40];
+        assert_eq!(CRC16_XMODEM_PARAMS.correct_single_bit_error(&mut received), CrcCorrection::Uncorrectable);
+    }
+}
@@ -0,0 +1,411 @@
+use core::fmt::Debug;
+use core::hash::Hasher;
+use core::ops::{BitAnd, BitXor, Shl, Shr};
+#[cfg(feature = "std")]
+use std::io;
+
+/// An unsigned integer holding the register of a CRC of up to `BITS` bits
+pub trait CrcWidth:
+    Copy + Eq + Debug
+    + BitAnd<Output = Self> + BitXor<Output = Self> + Shl<u32, Output = Self> + Shr<u32, Output = Self>
+{
+    const BITS: u32;
+    const ZERO: Self;
+    const ONE: Self;
+
+    fn from_u8(byte: u8) -> Self;
+
+    /// The lowest 8 bits
+    fn low_byte(self) -> u8;
+
+    fn reverse_bits(self) -> Self;
+
+    /// Shifted by a byte, which leaves nothing of a byte wide register
+    fn shl_byte(self) -> Self;
+
+    fn shr_byte(self) -> Self;
+
+    fn into_u64(self) -> u64;
+
+    /// `CrcParams::table`, which is a const fn for each width
+    fn table(params: &CrcParams<Self>) -> [Self; 256];
+}
+
+macro_rules! impl_crc_width {
+    ($($t:ty),*) => {$(
+        impl CrcWidth for $t {
+            const BITS: u32 = <$t>::BITS;
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+
+            #[inline]
+            fn from_u8(byte: u8) -> Self { byte as $t }
+
+            #[inline]
+            fn low_byte(self) -> u8 { self as u8 }
+
+            #[inline]
+            fn reverse_bits(self) -> Self { <$t>::reverse_bits(self) }
+
+            #[inline]
+            fn shl_byte(self) -> Self { self.checked_shl(8).unwrap_or(0) }
+
+            #[inline]
+            fn shr_byte(self) -> Self { self.checked_shr(8).unwrap_or(0) }
+
+            #[inline]
+            fn into_u64(self) -> u64 { self as u64 }
+
+            fn table(params: &CrcParams<Self>) -> [Self; 256] { params.const_table() }
+        }
+    )*};
+}
+
+impl_crc_width!(u8, u16, u32, u64);
+
+macro_rules! impl_widen {
+    ($($t:ty),*) => {$(
+        impl CrcParams<$t> {
+            /// The same params in a u64 register, as in `CATALOGUE`
+            pub const fn widen(self) -> CrcParams<u64> {
+                CrcParams {
+                    name: self.name,
+                    width: self.width,
+                    poly: self.poly as u64,
+                    init: self.init as u64,
+                    ref_in: self.ref_in,
+                    ref_out: self.ref_out,
+                    xor_out: self.xor_out as u64,
+                    check: self.check as u64,
+                }
+            }
+        }
+    )*};
+}
+
+impl_widen!(u8, u16, u32, u64);
+
+macro_rules! impl_const_table {
+    ($($t:ty),*) => {$(
+        impl CrcParams<$t> {
+            /// Same as `table`, at compile time, e.g. for a static table
+            pub const fn const_table(&self) -> [$t; 256] {
+                let shift = <$t>::BITS - self.width as u32;
+                let top: $t = 1 << (<$t>::BITS - 1);
+                let mut table = [0; 256];
+                let mut i = 0;
+                while i < 256 {
+                    let mut crc = if self.ref_in { i as $t } else { (i as $t) << (<$t>::BITS - 8) };
+                    let mut j = 0;
+                    while j < 8 {
+                        crc = if self.ref_in {
+                            if crc & 1 != 0 { (crc >> 1) ^ (self.poly.reverse_bits() >> shift) } else { crc >> 1 }
+                        } else if crc & top != 0 {
+                            (crc << 1) ^ (self.poly << shift)
+                        } else {
+                            crc << 1
+                        };
+                        j += 1;
+                    }
+                    table[i] = crc;
+                    i += 1;
+                }
+                table
+            }
+        }
+    )*};
+}
+
+impl_const_table!(u8, u16, u32, u64);
+
+/// The parameters of a CRC algorithm of any width from 1 to 64, in the Rocksoft model (see Readme),
+/// as listed in CRC catalogues
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CrcParams<W> {
+    pub name: &'static str,
+    /// Number of bits of the CRC, at most the bits of W
+    pub width: u8,
+    pub poly: W,
+    pub init: W,
+    pub ref_in: bool,
+    pub ref_out: bool,
+    pub xor_out: W,
+    /// The CRC of "123456789"
+    pub check: W,
+}
+
+impl<W: CrcWidth> CrcParams<W> {
+    /// Narrower CRCs are kept in the high bits of the register without `ref_in`, and in the low bits with it
+    fn shift(&self) -> u32 {
+        W::BITS - self.width as u32
+    }
+
+    /// The lookup table indexed by a byte of input. With `ref_in`, the table is of the reflected polynomial
+    pub fn table(&self) -> [W; 256] {
+        W::table(self)
+    }
+
+    /// The CRC of `data`, a byte at a time, with `table` from `table` or `const_table`
+    pub fn checksum_with_table(&self, data: &[u8], table: &[W; 256]) -> W {
+        self.output(self.update_register(self.initial_register(), data, table))
+    }
+
+    pub(crate) fn initial_register(&self) -> W {
+        if self.ref_in { self.init.reverse_bits() >> self.shift() } else { self.init << self.shift() }
+    }
+
+    pub(crate) fn update_register(&self, register: W, data: &[u8], table: &[W; 256]) -> W {
+        if self.ref_in {
+            data.iter().fold(register, |crc, d| crc.shr_byte() ^ table[(crc.low_byte() ^ d) as usize])
+        } else {
+            data.iter().fold(register, |crc, d| crc.shl_byte() ^ table[((crc >> (W::BITS - 8)).low_byte() ^ d) as usize])
+        }
+    }
+
+    /// Feeds a single bit of the message
+    fn update_register_bit(&self, register: W, bit: bool) -> W {
+        if self.ref_in {
+            let feedback = (register & W::ONE != W::ZERO) != bit;
+            let register = register >> 1;
+            if feedback { register ^ (self.poly.reverse_bits() >> self.shift()) } else { register }
+        } else {
+            let feedback = (register & (W::ONE << (W::BITS - 1)) != W::ZERO) != bit;
+            let register = register << 1;
+            if feedback { register ^ (self.poly << self.shift()) } else { register }
+        }
+    }
+
+    /// The CRC of a message of any number of bits, in the order given. No table is needed.
+    ///
+    /// A byte of input is 8 bits from the most significant, or from the least significant with `ref_in`.
+    pub fn checksum_bits<I: IntoIterator<Item = bool>>(&self, bits: I) -> W {
+        self.output(bits.into_iter().fold(self.initial_register(), |register, bit| self.update_register_bit(register, bit)))
+    }
+
+    pub(crate) fn output(&self, register: W) -> W {
+        let crc = if self.ref_in { register.reverse_bits() >> self.shift() } else { register >> self.shift() };
+        (if self.ref_out { crc.reverse_bits() >> self.shift() } else { crc }) ^ self.xor_out
+    }
+}
+
+/// A CRC computed over data arriving in chunks.
+///
+/// ```
+/// use crczoo::{Crc, CRC32_PARAMS, crc32};
+///
+/// let mut crc = Crc::new(&CRC32_PARAMS);
+/// crc.update(b"1234");
+/// crc.update(b"56789");
+/// assert_eq!(crc.finalize(), crc32(b"123456789"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Crc<W: CrcWidth> {
+    params: CrcParams<W>,
+    table: [W; 256],
+    register: W,
+}
+
+impl<W: CrcWidth> Crc<W> {
+    /// Panics if the width of the params is 0 or wider than W
+    pub fn new(params: &CrcParams<W>) -> Self {
+        assert!(params.width > 0 && params.width as u32 <= W::BITS, "CRC width out of range.");
+        Self {
+            params: *params,
+            table: params.table(),
+            register: params.initial_register(),
+        }
+    }
+
+    pub fn params(&self) -> &CrcParams<W> {
+        &self.params
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.register = self.params.update_register(self.register, data, &self.table);
+    }
+
+    /// The CRC of the data so far. More data can still be added
+    pub fn finalize(&self) -> W {
+        self.params.output(self.register)
+    }
+
+    /// Adds the bits in the order given, see `CrcParams::checksum_bits`
+    pub fn update_bits<I: IntoIterator<Item = bool>>(&mut self, bits: I) {
+        let params = &self.params;
+        self.register = bits.into_iter().fold(self.register, |register, bit| params.update_register_bit(register, bit));
+    }
+
+    /// Adds the first `bit_len` bits of `data`, taking the bits of a byte in the order of `ref_in`.
+    /// Panics if `data` is shorter than that
+    pub fn update_with_bit_len(&mut self, data: &[u8], bit_len: usize) {
+        assert!(bit_len <= data.len() * 8, "Not enough data for the number of bits.");
+        let (whole, rest) = data.split_at(bit_len / 8);
+        self.update(whole);
+        let ref_in = self.params.ref_in;
+        let num_bits = bit_len % 8;
+        if num_bits > 0 {
+            self.update_bits((0..num_bits).map(|i| rest[0] & (if ref_in { 1 << i } else { 0x80 >> i }) != 0));
+        }
+    }
+
+    /// Starts over, keeping the table
+    pub fn reset(&mut self) {
+        self.register = self.params.initial_register();
+    }
+
+    /// The CRC of `data` alone, regardless of the data so far
+    pub fn checksum(&self, data: &[u8]) -> W {
+        self.params.checksum_with_table(data, &self.table)
+    }
+}
+
+impl<W: CrcWidth> Hasher for Crc<W> {
+    fn finish(&self) -> u64 {
+        self.finalize().into_u64()
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.update(bytes);
+    }
+}
+
+/// To checksum whatever is written, e.g. with `std::io::copy`
+#[cfg(feature = "std")]
+impl<W: CrcWidth> io::Write for Crc<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.update(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hash::Hasher;
+    use crate::*;
+    use crate::test_util::random_messages;
+
+    const CHECK_SEQUENCE: &[u8] = b"123456789";
+
+    fn assert_check<W: CrcWidth>(catalogue: &[CrcParams<W>]) {
+        for params in catalogue.iter() {
+            assert_eq!(Crc::new(params).checksum(CHECK_SEQUENCE), params.check, "{}", params.name);
+        }
+    }
+
+    #[test]
+    fn test_crc_check() {
+        assert_check(&[
+            CRC5_PARAMS,
+            CRC8_PARAMS,
+            CRC8_CDMA2000_PARAMS,
+            CRC8_DARC_PARAMS,
+            CRC8_DVB_S2_PARAMS,
+            CRC8_EBU_PARAMS,
+            CRC8_I_CODE_PARAMS,
+            CRC8_ITU_PARAMS,
+            CRC8_MAXIM_PARAMS,
+            CRC8_ROHC_PARAMS,
+            CRC8_WCDMA_PARAMS,
+        ]);
+        assert_check(&[
+            CRC16_CCITT_FALSE_PARAMS,
+            CRC16_ARC_PARAMS,
+            CRC16_AUG_CCITT_PARAMS,
+            CRC16_BUYPASS_PARAMS,
+            CRC16_CDMA2000_PARAMS,
+            CRC16_DDS_110_PARAMS,
+            CRC16_DECT_R_PARAMS,
+            CRC16_DECT_X_PARAMS,
+            CRC16_DNP_PARAMS,
+            CRC16_EN_13757_PARAMS,
+            CRC16_GENIBUS_PARAMS,
+            CRC16_MAXIM_PARAMS,
+            CRC16_MCRF4XX_PARAMS,
+            CRC16_RIELLO_PARAMS,
+            CRC16_T10_DIF_PARAMS,
+            CRC16_TELEDISK_PARAMS,
+            CRC16_TMS37157_PARAMS,
+            CRC16_USB_PARAMS,
+            CRC16_A_PARAMS,
+            CRC16_KERMIT_PARAMS,
+            CRC16_MODBUS_PARAMS,
+            CRC16_X_25_PARAMS,
+            CRC16_XMODEM_PARAMS,
+        ]);
+        assert_check(&[
+            CRC32_PARAMS,
+            CRC32_BZIP2_PARAMS,
+            CRC32C_PARAMS,
+            CRC32D_PARAMS,
+            CRC32_MPEG2_PARAMS,
+            CRC32_POSIX_PARAMS,
+            CRC32Q_PARAMS,
+            CRC32_JAMCRC_PARAMS,
+            CRC32_XFER_PARAMS,
+        ]);
+    }
+
+    #[test]
+    fn test_crc_streaming() {
+        let mut crc5_stream = Crc::new(&CRC5_PARAMS);
+        let mut crc16_stream = Crc::new(&CRC16_X_25_PARAMS);
+        let mut crc32_stream = Crc::new(&CRC32_BZIP2_PARAMS);
+        for data in random_messages() {
+            for stream in [&mut crc5_stream as &mut dyn Hasher, &mut crc16_stream, &mut crc32_stream].iter_mut() {
+                data.chunks(7).for_each(|chunk| stream.write(chunk));
+            }
+            assert_eq!(crc5_stream.finalize(), crc5(&data));
+            assert_eq!(crc16_stream.finalize(), crc16_x_25(&data));
+            assert_eq!(crc32_stream.finalize(), crc32_bzip2(&data));
+            crc5_stream.reset();
+            crc16_stream.reset();
+            crc32_stream.reset();
+        }
+    }
+
+    /// The bits of the bytes, in the order they are taken with and without `ref_in`
+    fn bits_of(data: &[u8], ref_in: bool) -> Vec<bool> {
+        data.iter().flat_map(|byte| (0..8).map(move |i| byte & (if ref_in { 1 << i } else { 0x80 >> i }) != 0)).collect()
+    }
+
+    #[test]
+    fn test_crc_bits() {
+        for params in [CRC5_PARAMS, CRC8_PARAMS, CRC8_DARC_PARAMS].iter() {
+            let crc = Crc::new(params);
+            for data in random_messages().iter().take(24) {
+                let bits = bits_of(data, params.ref_in);
+                assert_eq!(params.checksum_bits(bits.iter().cloned()), crc.checksum(data));
+                for bit_len in 0..bits.len() {
+                    let mut partial = crc.clone();
+                    partial.update_with_bit_len(data, bit_len);
+                    assert_eq!(partial.finalize(), params.checksum_bits(bits[..bit_len].iter().cloned()));
+                }
+            }
+        }
+
+        // A padding bit changes the CRC
+        let bits = [true, false, true, true, false];
+        let mut padded = bits.to_vec();
+        padded.push(false);
+        assert_ne!(CRC16_XMODEM_PARAMS.checksum_bits(bits.iter().cloned()), CRC16_XMODEM_PARAMS.checksum_bits(padded));
+        let mut crc = Crc::new(&CRC32_PARAMS);
+        crc.update_bits(bits_of(b"1234", true));
+        crc.update(b"56789");
+        assert_eq!(crc.finalize(), CRC32_PARAMS.check);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_crc_io_write() {
+        use std::io::Write;
+        let mut crc = Crc::new(&CRC32C_PARAMS);
+        write!(crc, "{}{}", 1234, 56789).unwrap();
+        std::io::copy(&mut &b""[..], &mut crc).unwrap();
+        assert_eq!(crc.finalize(), CRC32C_PARAMS.check);
+        assert_eq!(crc.finish(), CRC32C_PARAMS.check as u64);
+    }
+}
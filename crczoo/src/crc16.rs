@@ -1,3 +1,5 @@
+use crate::CrcParams;
+
 pub fn calculate_crc16(data: &[u8], poly: u16, init: u16, ref_in: bool, ref_out: bool, xor_out: u16) -> u16 {
     let mut crc = init;
     for d in data.iter() {
@@ -22,123 +24,219 @@ pub fn calculate_crc16(data: &[u8], poly: u16, init: u16, ref_in: bool, ref_out:
 }
 
 pub const CRC16_CCITT_FALSE_POLY: u16 = 0x1021;
+pub const CRC16_CCITT_FALSE_PARAMS: CrcParams<u16> = CrcParams {
+    name: "CRC-16/CCITT-FALSE", width: 16, poly: CRC16_CCITT_FALSE_POLY, init: 0xFFFF, ref_in: false, ref_out: false, xor_out: 0x0000, check: 0x29B1,
+};
+static CRC16_CCITT_FALSE_TABLE: [u16; 256] = CRC16_CCITT_FALSE_PARAMS.const_table();
 pub fn crc16_ccitt_false(data: &[u8]) -> u16 {
-    calculate_crc16(data, CRC16_CCITT_FALSE_POLY, 0xFFFF, false, false, 0x0000)
+    CRC16_CCITT_FALSE_PARAMS.checksum_with_table(data, &CRC16_CCITT_FALSE_TABLE)
 }
 
 pub const CRC16_ARC_POLY: u16 = 0x8005;
+pub const CRC16_ARC_PARAMS: CrcParams<u16> = CrcParams {
+    name: "CRC-16/ARC", width: 16, poly: CRC16_ARC_POLY, init: 0x0000, ref_in: true, ref_out: true, xor_out: 0x0000, check: 0xBB3D,
+};
+static CRC16_ARC_TABLE: [u16; 256] = CRC16_ARC_PARAMS.const_table();
 pub fn crc16_arc(data: &[u8]) -> u16 {
-    calculate_crc16(data, CRC16_ARC_POLY, 0x0000, true, true, 0x0000)
+    CRC16_ARC_PARAMS.checksum_with_table(data, &CRC16_ARC_TABLE)
 }
 
 pub const CRC16_AUG_CCITT_POLY: u16 = 0x1021;
+pub const CRC16_AUG_CCITT_PARAMS: CrcParams<u16> = CrcParams {
+    name: "CRC-16/AUG-CCITT", width: 16, poly: CRC16_AUG_CCITT_POLY, init: 0x1D0F, ref_in: false, ref_out: false, xor_out: 0x0000, check: 0xE5CC,
+};
+static CRC16_AUG_CCITT_TABLE: [u16; 256] = CRC16_AUG_CCITT_PARAMS.const_table();
 pub fn crc16_aug_ccitt(data: &[u8]) -> u16 {
-    calculate_crc16(data, CRC16_AUG_CCITT_POLY, 0x1D0F, false, false, 0x0000)
+    CRC16_AUG_CCITT_PARAMS.checksum_with_table(data, &CRC16_AUG_CCITT_TABLE)
 }
 
 pub const CRC16_BUYPASS_POLY: u16 = 0x8005;
+pub const CRC16_BUYPASS_PARAMS: CrcParams<u16> = CrcParams {
+    name: "CRC-16/BUYPASS", width: 16, poly: CRC16_BUYPASS_POLY, init: 0x0000, ref_in: false, ref_out: false, xor_out: 0x0000, check: 0xFEE8,
+};
+static CRC16_BUYPASS_TABLE: [u16; 256] = CRC16_BUYPASS_PARAMS.const_table();
 pub fn crc16_buypass(data: &[u8]) -> u16 {
-    calculate_crc16(data, CRC16_BUYPASS_POLY, 0x0000, false, false, 0x0000)
+    CRC16_BUYPASS_PARAMS.checksum_with_table(data, &CRC16_BUYPASS_TABLE)
 }
 
 pub const CRC16_CDMA2000_POLY: u16 = 0xC867;
+pub const CRC16_CDMA2000_PARAMS: CrcParams<u16> = CrcParams {
+    name: "CRC-16/CDMA2000", width: 16, poly: CRC16_CDMA2000_POLY, init: 0xFFFF, ref_in: false, ref_out: false, xor_out: 0x0000, check: 0x4C06,
+};
+static CRC16_CDMA2000_TABLE: [u16; 256] = CRC16_CDMA2000_PARAMS.const_table();
 pub fn crc16_cdma2000(data: &[u8]) -> u16 {
-    calculate_crc16(data, CRC16_CDMA2000_POLY, 0xFFFF, false, false, 0x0000)
+    CRC16_CDMA2000_PARAMS.checksum_with_table(data, &CRC16_CDMA2000_TABLE)
 }
 
 pub const CRC16_DDS_110_POLY: u16 = 0x8005;
+pub const CRC16_DDS_110_PARAMS: CrcParams<u16> = CrcParams {
+    name: "CRC-16/DDS-110", width: 16, poly: CRC16_DDS_110_POLY, init: 0x800D, ref_in: false, ref_out: false, xor_out: 0x0000, check: 0x9ECF,
+};
+static CRC16_DDS_110_TABLE: [u16; 256] = CRC16_DDS_110_PARAMS.const_table();
 pub fn crc16_dds_110(data: &[u8]) -> u16 {
-    calculate_crc16(data, CRC16_DDS_110_POLY, 0x800D, false, false, 0x0000)
+    CRC16_DDS_110_PARAMS.checksum_with_table(data, &CRC16_DDS_110_TABLE)
 }
 
 pub const CRC16_DECT_R_POLY: u16 = 0x0589;
+pub const CRC16_DECT_R_PARAMS: CrcParams<u16> = CrcParams {
+    name: "CRC-16/DECT-R", width: 16, poly: CRC16_DECT_R_POLY, init: 0x0000, ref_in: false, ref_out: false, xor_out: 0x0001, check: 0x007E,
+};
+static CRC16_DECT_R_TABLE: [u16; 256] = CRC16_DECT_R_PARAMS.const_table();
 pub fn crc16_dect_r(data: &[u8]) -> u16 {
-    calculate_crc16(data, CRC16_DECT_R_POLY, 0x0000, false, false, 0x0001)
+    CRC16_DECT_R_PARAMS.checksum_with_table(data, &CRC16_DECT_R_TABLE)
 }
 
 pub const CRC16_DECT_X_POLY: u16 = 0x0589;
+pub const CRC16_DECT_X_PARAMS: CrcParams<u16> = CrcParams {
+    name: "CRC-16/DECT-X", width: 16, poly: CRC16_DECT_X_POLY, init: 0x0000, ref_in: false, ref_out: false, xor_out: 0x0000, check: 0x007F,
+};
+static CRC16_DECT_X_TABLE: [u16; 256] = CRC16_DECT_X_PARAMS.const_table();
 pub fn crc16_dect_x(data: &[u8]) -> u16 {
-    calculate_crc16(data, CRC16_DECT_X_POLY, 0x0000, false, false, 0x0000)
+    CRC16_DECT_X_PARAMS.checksum_with_table(data, &CRC16_DECT_X_TABLE)
 }
 
 pub const CRC16_DNP_POLY: u16 = 0x3D65;
+pub const CRC16_DNP_PARAMS: CrcParams<u16> = CrcParams {
+    name: "CRC-16/DNP", width: 16, poly: CRC16_DNP_POLY, init: 0x0000, ref_in: true, ref_out: true, xor_out: 0xFFFF, check: 0xEA82,
+};
+static CRC16_DNP_TABLE: [u16; 256] = CRC16_DNP_PARAMS.const_table();
 pub fn crc16_dnp(data: &[u8]) -> u16 {
-    calculate_crc16(data, CRC16_DNP_POLY, 0x0000, true, true, 0xFFFF)
+    CRC16_DNP_PARAMS.checksum_with_table(data, &CRC16_DNP_TABLE)
 }
 
 pub const CRC16_EN_13757_POLY: u16 = 0x3D65;
+pub const CRC16_EN_13757_PARAMS: CrcParams<u16> = CrcParams {
+    name: "CRC-16/EN-13757", width: 16, poly: CRC16_EN_13757_POLY, init: 0x0000, ref_in: false, ref_out: false, xor_out: 0xFFFF, check: 0xC2B7,
+};
+static CRC16_EN_13757_TABLE: [u16; 256] = CRC16_EN_13757_PARAMS.const_table();
 pub fn crc16_en_13757(data: &[u8]) -> u16 {
-    calculate_crc16(data, CRC16_EN_13757_POLY, 0x0000, false, false, 0xFFFF)
+    CRC16_EN_13757_PARAMS.checksum_with_table(data, &CRC16_EN_13757_TABLE)
 }
 
 pub const CRC16_GENIBUS_POLY: u16 = 0x1021;
+pub const CRC16_GENIBUS_PARAMS: CrcParams<u16> = CrcParams {
+    name: "CRC-16/GENIBUS", width: 16, poly: CRC16_GENIBUS_POLY, init: 0xFFFF, ref_in: false, ref_out: false, xor_out: 0xFFFF, check: 0xD64E,
+};
+static CRC16_GENIBUS_TABLE: [u16; 256] = CRC16_GENIBUS_PARAMS.const_table();
 pub fn crc16_genibus(data: &[u8]) -> u16 {
-    calculate_crc16(data, CRC16_GENIBUS_POLY, 0xFFFF, false, false, 0xFFFF)
+    CRC16_GENIBUS_PARAMS.checksum_with_table(data, &CRC16_GENIBUS_TABLE)
 }
 
 pub const CRC16_MAXIM_POLY: u16 = 0x8005;
+pub const CRC16_MAXIM_PARAMS: CrcParams<u16> = CrcParams {
+    name: "CRC-16/MAXIM", width: 16, poly: CRC16_MAXIM_POLY, init: 0x0000, ref_in: true, ref_out: true, xor_out: 0xFFFF, check: 0x44C2,
+};
+static CRC16_MAXIM_TABLE: [u16; 256] = CRC16_MAXIM_PARAMS.const_table();
 pub fn crc16_maxim(data: &[u8]) -> u16 {
-    calculate_crc16(data, CRC16_MAXIM_POLY, 0x0000, true, true, 0xFFFF)
+    CRC16_MAXIM_PARAMS.checksum_with_table(data, &CRC16_MAXIM_TABLE)
 }
 
 pub const CRC16_MCRF4XX_POLY: u16 = 0x1021;
+pub const CRC16_MCRF4XX_PARAMS: CrcParams<u16> = CrcParams {
+    name: "CRC-16/MCRF4XX", width: 16, poly: CRC16_MCRF4XX_POLY, init: 0xFFFF, ref_in: true, ref_out: true, xor_out: 0x0000, check: 0x6F91,
+};
+static CRC16_MCRF4XX_TABLE: [u16; 256] = CRC16_MCRF4XX_PARAMS.const_table();
 pub fn crc16_mcrf4xx(data: &[u8]) -> u16 {
-    calculate_crc16(data, CRC16_MCRF4XX_POLY, 0xFFFF, true, true, 0x0000)
+    CRC16_MCRF4XX_PARAMS.checksum_with_table(data, &CRC16_MCRF4XX_TABLE)
 }
 
 pub const CRC16_RIELLO_POLY: u16 = 0x1021;
+pub const CRC16_RIELLO_PARAMS: CrcParams<u16> = CrcParams {
+    name: "CRC-16/RIELLO", width: 16, poly: CRC16_RIELLO_POLY, init: 0xB2AA, ref_in: true, ref_out: true, xor_out: 0x0000, check: 0x63D0,
+};
+static CRC16_RIELLO_TABLE: [u16; 256] = CRC16_RIELLO_PARAMS.const_table();
 pub fn crc16_riello(data: &[u8]) -> u16 {
-    calculate_crc16(data, CRC16_RIELLO_POLY, 0xB2AA, true, true, 0x0000)
+    CRC16_RIELLO_PARAMS.checksum_with_table(data, &CRC16_RIELLO_TABLE)
 }
 
 pub const CRC16_T10_DIF_POLY: u16 = 0x8BB7;
+pub const CRC16_T10_DIF_PARAMS: CrcParams<u16> = CrcParams {
+    name: "CRC-16/T10-DIF", width: 16, poly: CRC16_T10_DIF_POLY, init: 0x0000, ref_in: false, ref_out: false, xor_out: 0x0000, check: 0xD0DB,
+};
+static CRC16_T10_DIF_TABLE: [u16; 256] = CRC16_T10_DIF_PARAMS.const_table();
 pub fn crc16_t10_dif(data: &[u8]) -> u16 {
-    calculate_crc16(data, CRC16_T10_DIF_POLY, 0x0000, false, false, 0x0000)
+    CRC16_T10_DIF_PARAMS.checksum_with_table(data, &CRC16_T10_DIF_TABLE)
 }
 
 pub const CRC16_TELEDISK_POLY: u16 = 0xA097;
+pub const CRC16_TELEDISK_PARAMS: CrcParams<u16> = CrcParams {
+    name: "CRC-16/TELEDISK", width: 16, poly: CRC16_TELEDISK_POLY, init: 0x0000, ref_in: false, ref_out: false, xor_out: 0x0000, check: 0x0FB3,
+};
+static CRC16_TELEDISK_TABLE: [u16; 256] = CRC16_TELEDISK_PARAMS.const_table();
 pub fn crc16_teledisk(data: &[u8]) -> u16 {
-    calculate_crc16(data, CRC16_TELEDISK_POLY, 0x0000, false, false, 0x0000)
+    CRC16_TELEDISK_PARAMS.checksum_with_table(data, &CRC16_TELEDISK_TABLE)
 }
 
 pub const CRC16_TMS37157_POLY: u16 = 0x1021;
+pub const CRC16_TMS37157_PARAMS: CrcParams<u16> = CrcParams {
+    name: "CRC-16/TMS37157", width: 16, poly: CRC16_TMS37157_POLY, init: 0x89EC, ref_in: true, ref_out: true, xor_out: 0x0000, check: 0x26B1,
+};
+static CRC16_TMS37157_TABLE: [u16; 256] = CRC16_TMS37157_PARAMS.const_table();
 pub fn crc16_tms37157(data: &[u8]) -> u16 {
-    calculate_crc16(data, CRC16_TMS37157_POLY, 0x89EC, true, true, 0x0000)
+    CRC16_TMS37157_PARAMS.checksum_with_table(data, &CRC16_TMS37157_TABLE)
 }
 
 pub const CRC16_USB_POLY: u16 = 0x8005;
+pub const CRC16_USB_PARAMS: CrcParams<u16> = CrcParams {
+    name: "CRC-16/USB", width: 16, poly: CRC16_USB_POLY, init: 0xFFFF, ref_in: true, ref_out: true, xor_out: 0xFFFF, check: 0xB4C8,
+};
+static CRC16_USB_TABLE: [u16; 256] = CRC16_USB_PARAMS.const_table();
 pub fn crc16_usb(data: &[u8]) -> u16 {
-    calculate_crc16(data, CRC16_USB_POLY, 0xFFFF, true, true, 0xFFFF)
+    CRC16_USB_PARAMS.checksum_with_table(data, &CRC16_USB_TABLE)
 }
 
 pub const CRC16_A_POLY: u16 = 0x1021;
+pub const CRC16_A_PARAMS: CrcParams<u16> = CrcParams {
+    name: "CRC-A", width: 16, poly: CRC16_A_POLY, init: 0xC6C6, ref_in: true, ref_out: true, xor_out: 0x0000, check: 0xBF05,
+};
+static CRC16_A_TABLE: [u16; 256] = CRC16_A_PARAMS.const_table();
 pub fn crc16_a(data: &[u8]) -> u16 {
-    calculate_crc16(data, CRC16_A_POLY, 0xC6C6, true, true, 0x0000)
+    CRC16_A_PARAMS.checksum_with_table(data, &CRC16_A_TABLE)
 }
 
 pub const CRC16_KERMIT_POLY: u16 = 0x1021;
+pub const CRC16_KERMIT_PARAMS: CrcParams<u16> = CrcParams {
+    name: "CRC-16/KERMIT", width: 16, poly: CRC16_KERMIT_POLY, init: 0x0000, ref_in: true, ref_out: true, xor_out: 0x0000, check: 0x2189,
+};
+static CRC16_KERMIT_TABLE: [u16; 256] = CRC16_KERMIT_PARAMS.const_table();
 pub fn crc16_kermit(data: &[u8]) -> u16 {
-    calculate_crc16(data, CRC16_KERMIT_POLY, 0x0000, true, true, 0x0000)
+    CRC16_KERMIT_PARAMS.checksum_with_table(data, &CRC16_KERMIT_TABLE)
 }
 
 pub const CRC16_MODBUS_POLY: u16 = 0x8005;
+pub const CRC16_MODBUS_PARAMS: CrcParams<u16> = CrcParams {
+    name: "CRC-16/MODBUS", width: 16, poly: CRC16_MODBUS_POLY, init: 0xFFFF, ref_in: true, ref_out: true, xor_out: 0x0000, check: 0x4B37,
+};
+static CRC16_MODBUS_TABLE: [u16; 256] = CRC16_MODBUS_PARAMS.const_table();
 pub fn crc16_modbus(data: &[u8]) -> u16 {
-    calculate_crc16(data, CRC16_MODBUS_POLY, 0xFFFF, true, true, 0x0000)
+    CRC16_MODBUS_PARAMS.checksum_with_table(data, &CRC16_MODBUS_TABLE)
 }
 
 pub const CRC16_X_25_POLY: u16 = 0x1021;
+pub const CRC16_X_25_PARAMS: CrcParams<u16> = CrcParams {
+    name: "CRC-16/X-25", width: 16, poly: CRC16_X_25_POLY, init: 0xFFFF, ref_in: true, ref_out: true, xor_out: 0xFFFF, check: 0x906E,
+};
+static CRC16_X_25_TABLE: [u16; 256] = CRC16_X_25_PARAMS.const_table();
 pub fn crc16_x_25(data: &[u8]) -> u16 {
-    calculate_crc16(data, CRC16_X_25_POLY, 0xFFFF, true, true, 0xFFFF)
+    CRC16_X_25_PARAMS.checksum_with_table(data, &CRC16_X_25_TABLE)
 }
 
 pub const CRC16_XMODEM_POLY: u16 = 0x1021;
+pub const CRC16_XMODEM_PARAMS: CrcParams<u16> = CrcParams {
+    name: "CRC-16/XMODEM", width: 16, poly: CRC16_XMODEM_POLY, init: 0x0000, ref_in: false, ref_out: false, xor_out: 0x0000, check: 0x31C3,
+};
+static CRC16_XMODEM_TABLE: [u16; 256] = CRC16_XMODEM_PARAMS.const_table();
 pub fn crc16_xmodem(data: &[u8]) -> u16 {
-    calculate_crc16(data, CRC16_XMODEM_POLY, 0x0000, false, false, 0x0000)
+    CRC16_XMODEM_PARAMS.checksum_with_table(data, &CRC16_XMODEM_TABLE)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::{Rng, random_messages};
+
+    /// A named variant and its parameters
+    type Variant = (fn(&[u8]) -> u16, CrcParams<u16>);
 
     fn check_sequence() -> Vec<u8> {
         "123456789".to_owned().into_bytes()
@@ -282,4 +380,53 @@ mod tests {
     fn test_crc16_xmodem() {
         assert_eq!(crc16_xmodem(&check_sequence()), 0x31C3);
     }
-}
+
+    #[test]
+    fn test_crc16_variants_match_reference() {
+        let variants: [Variant; 23] = [
+            (crc16_ccitt_false, CRC16_CCITT_FALSE_PARAMS),
+            (crc16_arc, CRC16_ARC_PARAMS),
+            (crc16_aug_ccitt, CRC16_AUG_CCITT_PARAMS),
+            (crc16_buypass, CRC16_BUYPASS_PARAMS),
+            (crc16_cdma2000, CRC16_CDMA2000_PARAMS),
+            (crc16_dds_110, CRC16_DDS_110_PARAMS),
+            (crc16_dect_r, CRC16_DECT_R_PARAMS),
+            (crc16_dect_x, CRC16_DECT_X_PARAMS),
+            (crc16_dnp, CRC16_DNP_PARAMS),
+            (crc16_en_13757, CRC16_EN_13757_PARAMS),
+            (crc16_genibus, CRC16_GENIBUS_PARAMS),
+            (crc16_maxim, CRC16_MAXIM_PARAMS),
+            (crc16_mcrf4xx, CRC16_MCRF4XX_PARAMS),
+            (crc16_riello, CRC16_RIELLO_PARAMS),
+            (crc16_t10_dif, CRC16_T10_DIF_PARAMS),
+            (crc16_teledisk, CRC16_TELEDISK_PARAMS),
+            (crc16_tms37157, CRC16_TMS37157_PARAMS),
+            (crc16_usb, CRC16_USB_PARAMS),
+            (crc16_a, CRC16_A_PARAMS),
+            (crc16_kermit, CRC16_KERMIT_PARAMS),
+            (crc16_modbus, CRC16_MODBUS_PARAMS),
+            (crc16_x_25, CRC16_X_25_PARAMS),
+            (crc16_xmodem, CRC16_XMODEM_PARAMS),
+        ];
+        for data in random_messages() {
+            for (crc, params) in variants.iter() {
+                assert_eq!(crc(&data), calculate_crc16(&data, params.poly, params.init, params.ref_in, params.ref_out, params.xor_out));
+            }
+        }
+    }
+
+    #[test]
+    fn test_crc16_table_matches_reference() {
+        let mut rng = Rng(16);
+        for _ in 0..32 {
+            let (poly, init, xor_out) = (rng.next() as u16, rng.next() as u16, rng.next() as u16);
+            let (ref_in, ref_out) = (rng.next() & 1 != 0, rng.next() & 2 != 0);
+            let params = CrcParams { name: "", width: 16, poly, init, ref_in, ref_out, xor_out, check: 0 };
+            let table = params.const_table();
+            for data in random_messages().iter().step_by(7) {
+                let expected = calculate_crc16(data, poly, init, ref_in, ref_out, xor_out);
+                assert_eq!(params.checksum_with_table(data, &table), expected);
+            }
+        }
+    }
+}
\ No newline at end of file
@@ -1,3 +1,5 @@
+use crate::CrcParams;
+
 pub fn calculate_crc32(data: &[u8], poly: u32, init: u32, ref_in: bool, ref_out: bool, xor_out: u32) -> u32 {
     let mut crc = init;
     for d in data.iter() {
@@ -21,54 +23,150 @@ pub fn calculate_crc32(data: &[u8], poly: u32, init: u32, ref_in: bool, ref_out:
     crc ^ xor_out
 }
 
+impl CrcParams<u32> {
+    /// The tables for slicing by N bytes, where `tables[k]` is the table of a byte followed by k zero bytes.
+    /// `tables[0]` is `const_table`
+    pub const fn slice_tables<const N: usize>(&self) -> [[u32; 256]; N] {
+        let table = self.const_table();
+        let mut tables = [table; N];
+        let mut k = 1;
+        while k < N {
+            let mut i = 0;
+            while i < 256 {
+                let prev = tables[k - 1][i];
+                tables[k][i] = if self.ref_in {
+                    (prev >> 8) ^ table[(prev & 0xFF) as usize]
+                } else {
+                    (prev << 8) ^ table[(prev >> 24) as usize]
+                };
+                i += 1;
+            }
+            k += 1;
+        }
+        tables
+    }
+
+    /// Same as `checksum_with_table`, 4 bytes at a time, with `tables` from `slice_tables::<4>`
+    pub fn checksum_slice4(&self, data: &[u8], tables: &[[u32; 256]; 4]) -> u32 {
+        let mut register = self.initial_register();
+        let mut chunks = data.chunks_exact(4);
+        for chunk in &mut chunks {
+            register = self.update_register_slice(register, [chunk[0], chunk[1], chunk[2], chunk[3]], tables);
+        }
+        self.output(self.update_register(register, chunks.remainder(), &tables[0]))
+    }
+
+    /// Same as `checksum_with_table`, 8 bytes at a time, with `tables` from `slice_tables::<8>`
+    pub fn checksum_slice8(&self, data: &[u8], tables: &[[u32; 256]; 8]) -> u32 {
+        let mut register = self.initial_register();
+        let mut chunks = data.chunks_exact(8);
+        for chunk in &mut chunks {
+            let mut word = [0; 8];
+            word.copy_from_slice(chunk);
+            register = self.update_register_slice(register, word, tables);
+        }
+        self.output(self.update_register(register, chunks.remainder(), &tables[0]))
+    }
+
+    /// Feeds N >= 4 bytes at once: the first 4 are combined with the register,
+    /// then each byte is looked up in the table of the number of bytes following it
+    fn update_register_slice<const N: usize>(&self, register: u32, mut bytes: [u8; N], tables: &[[u32; 256]; N]) -> u32 {
+        let head = if self.ref_in { register.to_le_bytes() } else { register.to_be_bytes() };
+        for (byte, r) in bytes.iter_mut().zip(head.iter()) {
+            *byte ^= r;
+        }
+        bytes.iter().enumerate().fold(0, |crc, (i, &byte)| crc ^ tables[N - 1 - i][byte as usize])
+    }
+}
+
 pub const CRC32_POLY: u32 = 0x04C11DB7;
+pub const CRC32_PARAMS: CrcParams<u32> = CrcParams {
+    name: "CRC-32", width: 32, poly: CRC32_POLY, init: 0xFFFFFFFF, ref_in: true, ref_out: true, xor_out: 0xFFFFFFFF, check: 0xCBF43926,
+};
+static CRC32_TABLES: [[u32; 256]; 8] = CRC32_PARAMS.slice_tables::<8>();
 pub fn crc32(data: &[u8]) -> u32 {
-    calculate_crc32(data, CRC32_POLY, 0xFFFFFFFF, true, true, 0xFFFFFFFF)
+    CRC32_PARAMS.checksum_slice8(data, &CRC32_TABLES)
 }
 
 pub const CRC32_BZIP2_POLY: u32 = 0x04C11DB7;
+pub const CRC32_BZIP2_PARAMS: CrcParams<u32> = CrcParams {
+    name: "CRC-32/BZIP2", width: 32, poly: CRC32_BZIP2_POLY, init: 0xFFFFFFFF, ref_in: false, ref_out: false, xor_out: 0xFFFFFFFF, check: 0xFC891918,
+};
+static CRC32_BZIP2_TABLES: [[u32; 256]; 8] = CRC32_BZIP2_PARAMS.slice_tables::<8>();
 pub fn crc32_bzip2(data: &[u8]) -> u32 {
-    calculate_crc32(data, CRC32_BZIP2_POLY, 0xFFFFFFFF, false, false, 0xFFFFFFFF)
+    CRC32_BZIP2_PARAMS.checksum_slice8(data, &CRC32_BZIP2_TABLES)
 }
 
 pub const CRC32C_POLY: u32 = 0x1EDC6F41;
+pub const CRC32C_PARAMS: CrcParams<u32> = CrcParams {
+    name: "CRC-32C", width: 32, poly: CRC32C_POLY, init: 0xFFFFFFFF, ref_in: true, ref_out: true, xor_out: 0xFFFFFFFF, check: 0xE3069283,
+};
+static CRC32C_TABLES: [[u32; 256]; 8] = CRC32C_PARAMS.slice_tables::<8>();
 pub fn crc32c(data: &[u8]) -> u32 {
-    calculate_crc32(data, CRC32C_POLY, 0xFFFFFFFF, true, true, 0xFFFFFFFF)
+    CRC32C_PARAMS.checksum_slice8(data, &CRC32C_TABLES)
 }
 
 pub const CRC32D_POLY: u32 = 0xA833982B;
+pub const CRC32D_PARAMS: CrcParams<u32> = CrcParams {
+    name: "CRC-32D", width: 32, poly: CRC32D_POLY, init: 0xFFFFFFFF, ref_in: true, ref_out: true, xor_out: 0xFFFFFFFF, check: 0x87315576,
+};
+static CRC32D_TABLES: [[u32; 256]; 8] = CRC32D_PARAMS.slice_tables::<8>();
 pub fn crc32d(data: &[u8]) -> u32 {
-    calculate_crc32(data, CRC32D_POLY, 0xFFFFFFFF, true, true, 0xFFFFFFFF)
+    CRC32D_PARAMS.checksum_slice8(data, &CRC32D_TABLES)
 }
 
 pub const CRC32_MPEG2_POLY: u32 = 0x04C11DB7;
+pub const CRC32_MPEG2_PARAMS: CrcParams<u32> = CrcParams {
+    name: "CRC-32/MPEG-2", width: 32, poly: CRC32_MPEG2_POLY, init: 0xFFFFFFFF, ref_in: false, ref_out: false, xor_out: 0x00000000, check: 0x0376E6E7,
+};
+static CRC32_MPEG2_TABLES: [[u32; 256]; 8] = CRC32_MPEG2_PARAMS.slice_tables::<8>();
 pub fn crc32_mpeg2(data: &[u8]) -> u32 {
-    calculate_crc32(data, CRC32_MPEG2_POLY, 0xFFFFFFFF, false, false, 0x00000000)
+    CRC32_MPEG2_PARAMS.checksum_slice8(data, &CRC32_MPEG2_TABLES)
 }
 
 pub const CRC32_POSIX_POLY: u32 = 0x04C11DB7;
+pub const CRC32_POSIX_PARAMS: CrcParams<u32> = CrcParams {
+    name: "CRC-32/POSIX", width: 32, poly: CRC32_POSIX_POLY, init: 0x00000000, ref_in: false, ref_out: false, xor_out: 0xFFFFFFFF, check: 0x765E7680,
+};
+static CRC32_POSIX_TABLES: [[u32; 256]; 8] = CRC32_POSIX_PARAMS.slice_tables::<8>();
 pub fn crc32_posix(data: &[u8]) -> u32 {
-    calculate_crc32(data, CRC32_POSIX_POLY, 0x00000000, false, false, 0xFFFFFFFF)
+    CRC32_POSIX_PARAMS.checksum_slice8(data, &CRC32_POSIX_TABLES)
 }
 
 pub const CRC32Q_POLY: u32 = 0x814141AB;
+pub const CRC32Q_PARAMS: CrcParams<u32> = CrcParams {
+    name: "CRC-32Q", width: 32, poly: CRC32Q_POLY, init: 0x00000000, ref_in: false, ref_out: false, xor_out: 0x00000000, check: 0x3010BF7F,
+};
+static CRC32Q_TABLES: [[u32; 256]; 8] = CRC32Q_PARAMS.slice_tables::<8>();
 pub fn crc32q(data: &[u8]) -> u32 {
-    calculate_crc32(data, CRC32Q_POLY, 0x00000000, false, false, 0x00000000)
+    CRC32Q_PARAMS.checksum_slice8(data, &CRC32Q_TABLES)
 }
 
 pub const CRC32_JAMCRC_POLY: u32 = 0x04C11DB7;
+pub const CRC32_JAMCRC_PARAMS: CrcParams<u32> = CrcParams {
+    name: "CRC-32/JAMCRC", width: 32, poly: CRC32_JAMCRC_POLY, init: 0xFFFFFFFF, ref_in: true, ref_out: true, xor_out: 0x00000000, check: 0x340BC6D9,
+};
+static CRC32_JAMCRC_TABLES: [[u32; 256]; 8] = CRC32_JAMCRC_PARAMS.slice_tables::<8>();
 pub fn crc32_jamcrc(data: &[u8]) -> u32 {
-    calculate_crc32(data, CRC32_JAMCRC_POLY, 0xFFFFFFFF, true, true, 0x00000000)
+    CRC32_JAMCRC_PARAMS.checksum_slice8(data, &CRC32_JAMCRC_TABLES)
 }
 
 pub const CRC32_XFER_POLY: u32 = 0x000000AF;
+pub const CRC32_XFER_PARAMS: CrcParams<u32> = CrcParams {
+    name: "CRC-32/XFER", width: 32, poly: CRC32_XFER_POLY, init: 0x00000000, ref_in: false, ref_out: false, xor_out: 0x00000000, check: 0xBD0BE338,
+};
+static CRC32_XFER_TABLES: [[u32; 256]; 8] = CRC32_XFER_PARAMS.slice_tables::<8>();
 pub fn crc32_xfer(data: &[u8]) -> u32 {
-    calculate_crc32(data, CRC32_XFER_POLY, 0x00000000, false, false, 0x00000000)
+    CRC32_XFER_PARAMS.checksum_slice8(data, &CRC32_XFER_TABLES)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::{Rng, random_messages};
+
+    /// A named variant and its parameters
+    type Variant = (fn(&[u8]) -> u32, CrcParams<u32>);
 
     fn check_sequence() -> Vec<u8> {
         "123456789".to_owned().into_bytes()
@@ -147,4 +245,43 @@ mod tests {
     fn test_crc32_xfer() {
         assert_eq!(crc32_xfer(&check_sequence()), 0xBD0BE338);
     }
+
+    #[test]
+    fn test_crc32_variants_match_reference() {
+        let variants: [Variant; 9] = [
+            (crc32, CRC32_PARAMS),
+            (crc32_bzip2, CRC32_BZIP2_PARAMS),
+            (crc32c, CRC32C_PARAMS),
+            (crc32d, CRC32D_PARAMS),
+            (crc32_mpeg2, CRC32_MPEG2_PARAMS),
+            (crc32_posix, CRC32_POSIX_PARAMS),
+            (crc32q, CRC32Q_PARAMS),
+            (crc32_jamcrc, CRC32_JAMCRC_PARAMS),
+            (crc32_xfer, CRC32_XFER_PARAMS),
+        ];
+        for data in random_messages() {
+            for (crc, params) in variants.iter() {
+                assert_eq!(crc(&data), calculate_crc32(&data, params.poly, params.init, params.ref_in, params.ref_out, params.xor_out));
+            }
+        }
+    }
+
+    #[test]
+    fn test_crc32_table_matches_reference() {
+        let mut rng = Rng(32);
+        for _ in 0..32 {
+            let (poly, init, xor_out) = (rng.next() as u32, rng.next() as u32, rng.next() as u32);
+            let (ref_in, ref_out) = (rng.next() & 1 != 0, rng.next() & 2 != 0);
+            let params = CrcParams { name: "", width: 32, poly, init, ref_in, ref_out, xor_out, check: 0 };
+            let table = params.const_table();
+            let slice4 = params.slice_tables::<4>();
+            let slice8 = params.slice_tables::<8>();
+            for data in random_messages().iter().step_by(7) {
+                let expected = calculate_crc32(data, poly, init, ref_in, ref_out, xor_out);
+                assert_eq!(params.checksum_with_table(data, &table), expected);
+                assert_eq!(params.checksum_slice4(data, &slice4), expected);
+                assert_eq!(params.checksum_slice8(data, &slice8), expected);
+            }
+        }
+    }
 }
\ No newline at end of file
@@ -1,3 +1,5 @@
+use crate::CrcParams;
+
 pub fn calculate_crc5(data: &[u8], poly: u8, init: u8, ref_in: bool, ref_out: bool, xor_out: u8) -> u8 {
     let mut crc = init;
     for d in data.iter() {
@@ -23,13 +25,18 @@ pub fn calculate_crc5(data: &[u8], poly: u8, init: u8, ref_in: bool, ref_out: bo
 }
 
 pub const CRC5_POLY: u8 = 0x5;
+pub const CRC5_PARAMS: CrcParams<u8> = CrcParams {
+    name: "CRC-5/USB", width: 5, poly: CRC5_POLY, init: 0x1f, ref_in: true, ref_out: true, xor_out: 0x1f, check: 0x19,
+};
+static CRC5_TABLE: [u8; 256] = CRC5_PARAMS.const_table();
 pub fn crc5(data: &[u8]) -> u8 {
-    calculate_crc5(data, CRC5_POLY, 0x1f, true, true, 0x1f)
+    CRC5_PARAMS.checksum_with_table(data, &CRC5_TABLE)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::{Rng, random_messages};
 
     fn check_sequence() -> Vec<u8> {
         "123456789".to_owned().into_bytes()
@@ -63,4 +70,27 @@ mod tests {
         data.append(&mut check_sequence_long());
         assert_eq!(crc5(&data), 0xc);
     }
-}
+
+    #[test]
+    fn test_crc5_matches_reference() {
+        let params = CRC5_PARAMS;
+        for data in random_messages() {
+            assert_eq!(crc5(&data), calculate_crc5(&data, params.poly, params.init, params.ref_in, params.ref_out, params.xor_out));
+        }
+    }
+
+    #[test]
+    fn test_crc5_table_matches_reference() {
+        let mut rng = Rng(5);
+        for _ in 0..32 {
+            let (poly, init, xor_out) = (rng.next() as u8 & 0x1f, rng.next() as u8 & 0x1f, rng.next() as u8 & 0x1f);
+            let (ref_in, ref_out) = (rng.next() & 1 != 0, rng.next() & 2 != 0);
+            let params = CrcParams { name: "", width: 5, poly, init, ref_in, ref_out, xor_out, check: 0 };
+            let table = params.const_table();
+            for data in random_messages().iter().step_by(7) {
+                let expected = calculate_crc5(data, poly, init, ref_in, ref_out, xor_out);
+                assert_eq!(params.checksum_with_table(data, &table), expected);
+            }
+        }
+    }
+}
\ No newline at end of file
@@ -1,3 +1,5 @@
+use crate::CrcParams;
+
 pub fn calculate_crc8(data: &[u8], poly: u8, init: u8, ref_in: bool, ref_out: bool, xor_out: u8) -> u8 {
     let mut crc = init;
     for d in data.iter() {
@@ -22,58 +24,102 @@ pub fn calculate_crc8(data: &[u8], poly: u8, init: u8, ref_in: bool, ref_out: bo
 }
 
 pub const CRC8_POLY: u8 = 0x07; 
+pub const CRC8_PARAMS: CrcParams<u8> = CrcParams {
+    name: "CRC-8", width: 8, poly: CRC8_POLY, init: 0x00, ref_in: false, ref_out: false, xor_out: 0x00, check: 0xF4,
+};
+static CRC8_TABLE: [u8; 256] = CRC8_PARAMS.const_table();
 pub fn crc8(data: &[u8]) -> u8 {
-    calculate_crc8(data, CRC8_POLY, 0x00, false, false, 0x00)
+    CRC8_PARAMS.checksum_with_table(data, &CRC8_TABLE)
 }
 
 pub const CRC8_CDMA2000_POLY: u8 = 0x9B;
+pub const CRC8_CDMA2000_PARAMS: CrcParams<u8> = CrcParams {
+    name: "CRC-8/CDMA2000", width: 8, poly: CRC8_CDMA2000_POLY, init: 0xFF, ref_in: false, ref_out: false, xor_out: 0x00, check: 0xDA,
+};
+static CRC8_CDMA2000_TABLE: [u8; 256] = CRC8_CDMA2000_PARAMS.const_table();
 pub fn crc8_cdma2000(data: &[u8]) -> u8 {
-    calculate_crc8(data, CRC8_CDMA2000_POLY, 0xFF, false, false, 0x00)
+    CRC8_CDMA2000_PARAMS.checksum_with_table(data, &CRC8_CDMA2000_TABLE)
 }
 
 pub const CRC8_DARC_POLY: u8 = 0x39;
+pub const CRC8_DARC_PARAMS: CrcParams<u8> = CrcParams {
+    name: "CRC-8/DARC", width: 8, poly: CRC8_DARC_POLY, init: 0x00, ref_in: true, ref_out: true, xor_out: 0x00, check: 0x15,
+};
+static CRC8_DARC_TABLE: [u8; 256] = CRC8_DARC_PARAMS.const_table();
 pub fn crc8_darc(data: &[u8]) -> u8 {
-    calculate_crc8(data, CRC8_DARC_POLY, 0x00, true, true, 0x00)
+    CRC8_DARC_PARAMS.checksum_with_table(data, &CRC8_DARC_TABLE)
 }
 
 pub const CRC8_DVB_S2_POLY: u8 = 0xD5;
+pub const CRC8_DVB_S2_PARAMS: CrcParams<u8> = CrcParams {
+    name: "CRC-8/DVB-S2", width: 8, poly: CRC8_DVB_S2_POLY, init: 0x00, ref_in: false, ref_out: false, xor_out: 0x00, check: 0xBC,
+};
+static CRC8_DVB_S2_TABLE: [u8; 256] = CRC8_DVB_S2_PARAMS.const_table();
 pub fn crc8_dvb_s2(data: &[u8]) -> u8 {
-    calculate_crc8(data, CRC8_DVB_S2_POLY, 0x00, false, false, 0x00)
+    CRC8_DVB_S2_PARAMS.checksum_with_table(data, &CRC8_DVB_S2_TABLE)
 }
 
 pub const CRC8_EBU_POLY: u8 = 0x1D;
+pub const CRC8_EBU_PARAMS: CrcParams<u8> = CrcParams {
+    name: "CRC-8/EBU", width: 8, poly: CRC8_EBU_POLY, init: 0xFF, ref_in: true, ref_out: true, xor_out: 0x00, check: 0x97,
+};
+static CRC8_EBU_TABLE: [u8; 256] = CRC8_EBU_PARAMS.const_table();
 pub fn crc8_ebu(data: &[u8]) -> u8 {
-    calculate_crc8(data, CRC8_EBU_POLY, 0xFF, true, true, 0x00)
+    CRC8_EBU_PARAMS.checksum_with_table(data, &CRC8_EBU_TABLE)
 }
 
 pub const CRC8_I_CODE_POLY: u8 = 0x1D;
+pub const CRC8_I_CODE_PARAMS: CrcParams<u8> = CrcParams {
+    name: "CRC-8/I-CODE", width: 8, poly: CRC8_I_CODE_POLY, init: 0xFD, ref_in: false, ref_out: false, xor_out: 0x00, check: 0x7E,
+};
+static CRC8_I_CODE_TABLE: [u8; 256] = CRC8_I_CODE_PARAMS.const_table();
 pub fn crc8_i_code(data: &[u8]) -> u8 {
-    calculate_crc8(data, CRC8_I_CODE_POLY, 0xFD, false, false, 0x00)
+    CRC8_I_CODE_PARAMS.checksum_with_table(data, &CRC8_I_CODE_TABLE)
 }
 
 pub const CRC8_ITU_POLY: u8 = 0x07;
+pub const CRC8_ITU_PARAMS: CrcParams<u8> = CrcParams {
+    name: "CRC-8/ITU", width: 8, poly: CRC8_ITU_POLY, init: 0x00, ref_in: false, ref_out: false, xor_out: 0x55, check: 0xA1,
+};
+static CRC8_ITU_TABLE: [u8; 256] = CRC8_ITU_PARAMS.const_table();
 pub fn crc8_itu(data: &[u8]) -> u8 {
-    calculate_crc8(data, CRC8_ITU_POLY, 0x00, false, false, 0x55)
+    CRC8_ITU_PARAMS.checksum_with_table(data, &CRC8_ITU_TABLE)
 }
 
 pub const CRC8_MAXIM_POLY: u8 = 0x31;
+pub const CRC8_MAXIM_PARAMS: CrcParams<u8> = CrcParams {
+    name: "CRC-8/MAXIM", width: 8, poly: CRC8_MAXIM_POLY, init: 0x00, ref_in: true, ref_out: true, xor_out: 0x00, check: 0xA1,
+};
+static CRC8_MAXIM_TABLE: [u8; 256] = CRC8_MAXIM_PARAMS.const_table();
 pub fn crc8_maxim(data: &[u8]) -> u8 {
-    calculate_crc8(data, CRC8_MAXIM_POLY, 0x00, true, true, 0x00)
+    CRC8_MAXIM_PARAMS.checksum_with_table(data, &CRC8_MAXIM_TABLE)
 }
 
 pub const CRC8_ROHC_POLY: u8 = 0x07;
+pub const CRC8_ROHC_PARAMS: CrcParams<u8> = CrcParams {
+    name: "CRC-8/ROHC", width: 8, poly: CRC8_ROHC_POLY, init: 0xFF, ref_in: true, ref_out: true, xor_out: 0x00, check: 0xD0,
+};
+static CRC8_ROHC_TABLE: [u8; 256] = CRC8_ROHC_PARAMS.const_table();
 pub fn crc8_rohc(data: &[u8]) -> u8 {
-    calculate_crc8(data, CRC8_ROHC_POLY, 0xFF, true, true, 0x00)
+    CRC8_ROHC_PARAMS.checksum_with_table(data, &CRC8_ROHC_TABLE)
 }
 
 pub const CRC8_WCDMA_POLY: u8 = 0x9B;
+pub const CRC8_WCDMA_PARAMS: CrcParams<u8> = CrcParams {
+    name: "CRC-8/WCDMA", width: 8, poly: CRC8_WCDMA_POLY, init: 0x00, ref_in: true, ref_out: true, xor_out: 0x00, check: 0x25,
+};
+static CRC8_WCDMA_TABLE: [u8; 256] = CRC8_WCDMA_PARAMS.const_table();
 pub fn crc8_wcdma(data: &[u8]) -> u8 {
-    calculate_crc8(data, CRC8_WCDMA_POLY, 0x00, true, true, 0x00)
+    CRC8_WCDMA_PARAMS.checksum_with_table(data, &CRC8_WCDMA_TABLE)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::{Rng, random_messages};
+
+    /// A named variant and its parameters
+    type Variant = (fn(&[u8]) -> u8, CrcParams<u8>);
 
     fn check_sequence() -> Vec<u8> {
         "123456789".to_owned().into_bytes()
@@ -168,4 +214,40 @@ mod tests {
     fn test_crc8_wcdma() {
         assert_eq!(crc8_wcdma(&check_sequence()), 0x25);
     }
+
+    #[test]
+    fn test_crc8_variants_match_reference() {
+        let variants: [Variant; 10] = [
+            (crc8, CRC8_PARAMS),
+            (crc8_cdma2000, CRC8_CDMA2000_PARAMS),
+            (crc8_darc, CRC8_DARC_PARAMS),
+            (crc8_dvb_s2, CRC8_DVB_S2_PARAMS),
+            (crc8_ebu, CRC8_EBU_PARAMS),
+            (crc8_i_code, CRC8_I_CODE_PARAMS),
+            (crc8_itu, CRC8_ITU_PARAMS),
+            (crc8_maxim, CRC8_MAXIM_PARAMS),
+            (crc8_rohc, CRC8_ROHC_PARAMS),
+            (crc8_wcdma, CRC8_WCDMA_PARAMS),
+        ];
+        for data in random_messages() {
+            for (crc, params) in variants.iter() {
+                assert_eq!(crc(&data), calculate_crc8(&data, params.poly, params.init, params.ref_in, params.ref_out, params.xor_out));
+            }
+        }
+    }
+
+    #[test]
+    fn test_crc8_table_matches_reference() {
+        let mut rng = Rng(8);
+        for _ in 0..32 {
+            let (poly, init, xor_out) = (rng.next() as u8, rng.next() as u8, rng.next() as u8);
+            let (ref_in, ref_out) = (rng.next() & 1 != 0, rng.next() & 2 != 0);
+            let params = CrcParams { name: "", width: 8, poly, init, ref_in, ref_out, xor_out, check: 0 };
+            let table = params.const_table();
+            for data in random_messages().iter().step_by(7) {
+                let expected = calculate_crc8(data, poly, init, ref_in, ref_out, xor_out);
+                assert_eq!(params.checksum_with_table(data, &table), expected);
+            }
+        }
+    }
 }
\ No newline at end of file
@@ -1,9 +1,44 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+#[cfg(feature = "std")]
+mod analysis;
+mod catalogue;
+mod correction;
+mod crc;
 mod crc5;
 mod crc8;
 mod crc16;
 mod crc32;
 
+#[cfg(feature = "std")]
+pub use analysis::*;
+pub use catalogue::*;
+pub use correction::*;
+pub use crc::*;
 pub use crc5::*;
 pub use crc8::*;
 pub use crc16::*;
-pub use crc32::*;
\ No newline at end of file
+pub use crc32::*;
+
+#[cfg(test)]
+mod test_util {
+    /// xorshift64, to generate test data without dependencies
+    pub(crate) struct Rng(pub(crate) u64);
+
+    impl Rng {
+        pub(crate) fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+    }
+
+    /// Messages of every length up to 64 bytes and a few longer ones, with random content
+    pub(crate) fn random_messages() -> Vec<Vec<u8>> {
+        let mut rng = Rng(0x9E3779B97F4A7C15);
+        (0..=64).chain([255, 1000, 1027].iter().cloned())
+            .map(|len| (0..len).map(|_| rng.next() as u8).collect())
+            .collect()
+    }
+}
@@ -0,0 +1,160 @@
+//! Exports the rectified codes and glyph crops of captured frames as a labelled dataset.
+//!
+//! ```sh
+//! cargo run --example export_dataset -- alphabet.pgm frames/ dataset/ [ground_truth.csv]
+//! ```
+//!
+//! The alphabet and the frames are binary PGM (P5) or PPM (P6) images, e.g. converted with
+//! `convert frame.jpg frame.ppm`. The alphabet is laid out as `AlphabetReaderParams::default()` expects.
+//! The dataset is written into a directory, or into a tar archive if the output ends with `.tar`.
+//!
+//! Each line of the optional ground truth is the file name of a frame and the labels of its glyphs:
+//! ```text
+//! frame_01.ppm,LongRR ArrowDD TriforceU SmallTripleL FourKites
+//! ```
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::Path;
+
+use visioncortex::{Color, ColorImage};
+
+use symcode::acute32::{Acute32, Acute32DatasetExporter, Acute32SymcodeConfig, AlphabetReader, AlphabetReaderParams, DatasetSink, DirectorySink, GlyphLabel, TarSink};
+use symcode::interfaces::{DummyDebugger, Finder, Fitter};
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 4 || args.len() > 5 {
+        eprintln!("Usage: {} <alphabet.pgm|ppm> <frames_dir> <output_dir|output.tar> [ground_truth.csv]", args[0]);
+        std::process::exit(2);
+    }
+    if let Err(e) = run(&args[1], &args[2], &args[3], args.get(4)) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(alphabet_path: &str, frames_dir: &str, output: &str, ground_truth_path: Option<&String>) -> io::Result<()> {
+    let mut config = Acute32SymcodeConfig::default();
+    let alphabet = read_pnm(Path::new(alphabet_path))?
+        .to_binary_image(|c| (c.r as u32 + c.g as u32 + c.b as u32) < 3 * 128);
+    let library = AlphabetReader::read_alphabet_to_library(alphabet, AlphabetReaderParams::default(), &config, &DummyDebugger)
+        .map_err(invalid_data)?;
+    config.symbol_library = library.into();
+
+    let ground_truth = match ground_truth_path {
+        Some(path) => read_ground_truth(Path::new(path))?,
+        None => HashMap::new(),
+    };
+
+    let mut frames: Vec<_> = std::fs::read_dir(frames_dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| matches!(path.extension().and_then(|ext| ext.to_str()), Some("pgm") | Some("ppm")))
+        .collect();
+    frames.sort();
+
+    if output.ends_with(".tar") {
+        let sink = TarSink::new(BufWriter::new(File::create(output)?));
+        export(&config, &frames, &ground_truth, sink)
+    } else {
+        export(&config, &frames, &ground_truth, DirectorySink::new(output))
+    }
+}
+
+/// Frames in which no code is found are reported and skipped
+fn export<S: DatasetSink>(config: &Acute32SymcodeConfig, frames: &[std::path::PathBuf], ground_truth: &HashMap<String, Vec<GlyphLabel>>, sink: S) -> io::Result<()> {
+    let acute32 = Acute32::new(config);
+    let (finder, fitter, reader) = (acute32.get_finder(), acute32.get_fitter(), acute32.get_reader());
+    let mut exporter = Acute32DatasetExporter::new(sink);
+    for path in frames.iter() {
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        let frame = read_pnm(path)?;
+        let located = finder.find(&frame)
+            .and_then(|finder_positions| fitter.fit(finder_positions, frame.width, frame.height));
+        let image_to_object = match located {
+            Ok(image_to_object) => image_to_object,
+            Err(e) => {
+                eprintln!("{}: {}", name, e);
+                continue;
+            },
+        };
+        let output = reader.read_with_details(frame, image_to_object);
+        exporter.export_frame(&output, ground_truth.get(&name).map(|labels| labels.as_slice()))?;
+        println!("{}: frame {}", name, exporter.num_frames() - 1);
+    }
+    exporter.finish()?;
+    Ok(())
+}
+
+fn read_ground_truth(path: &Path) -> io::Result<HashMap<String, Vec<GlyphLabel>>> {
+    let labels_by_name: HashMap<String, GlyphLabel> = (0..GlyphLabel::num_variants())
+        .map(|i| {
+            let label = GlyphLabel::from_usize_representation(i);
+            (format!("{:?}", label), label)
+        })
+        .collect();
+    std::fs::read_to_string(path)?.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let mut fields = line.splitn(2, ',');
+            let name = fields.next().unwrap_or("").trim().to_owned();
+            let labels = fields.next().unwrap_or("").split_whitespace()
+                .map(|label| labels_by_name.get(label).cloned().ok_or_else(|| invalid_data("Unknown glyph label in ground truth.")))
+                .collect::<io::Result<Vec<GlyphLabel>>>()?;
+            Ok((name, labels))
+        })
+        .collect()
+}
+
+/// Reads a binary PGM (P5) or PPM (P6) image of 8 bit samples
+fn read_pnm(path: &Path) -> io::Result<ColorImage> {
+    let bytes = std::fs::read(path)?;
+    // The header is the magic number, width, height and maximum value, separated by whitespace and comments
+    let mut fields = vec![];
+    let mut i = 0;
+    while fields.len() < 4 && i < bytes.len() {
+        if bytes[i] == b'#' {
+            while i < bytes.len() && bytes[i] != b'\n' {
+                i += 1;
+            }
+        } else if bytes[i].is_ascii_whitespace() {
+            i += 1;
+        } else {
+            let start = i;
+            while i < bytes.len() && !bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            fields.push(String::from_utf8_lossy(&bytes[start..i]).into_owned());
+        }
+    }
+    let channels = match fields.first().map(|s| s.as_str()) {
+        Some("P5") => 1,
+        Some("P6") => 3,
+        _ => return Err(invalid_data("Only binary PGM (P5) and PPM (P6) images are supported.")),
+    };
+    let parse = |field: Option<&String>| field.and_then(|s| s.parse::<usize>().ok()).ok_or_else(|| invalid_data("Malformed image header."));
+    let (width, height, max_value) = (parse(fields.get(1))?, parse(fields.get(2))?, parse(fields.get(3))?);
+    if max_value > 255 {
+        return Err(invalid_data("Only 8 bit images are supported."));
+    }
+    // A single whitespace separates the header from the samples
+    let samples = bytes.get(i + 1..).unwrap_or(&[]);
+    if samples.len() < width * height * channels {
+        return Err(invalid_data("The image is truncated."));
+    }
+
+    let mut image = ColorImage::new_w_h(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let pixel = &samples[(y * width + x) * channels..][..channels];
+            let color = if channels == 1 { Color::new(pixel[0], pixel[0], pixel[0]) } else { Color::new(pixel[0], pixel[1], pixel[2]) };
+            image.set_pixel(x, y, &color);
+        }
+    }
+    Ok(image)
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_owned())
+}
@@ -0,0 +1,212 @@
+use std::collections::VecDeque;
+use super::{GlyphLabel, GlyphPrediction, RecognizerOutput};
+
+/// Parameters of `GlyphAccumulator`
+#[derive(Clone, Copy, Debug)]
+pub struct AccumulatorParams {
+    /// Number of recent frames kept for voting
+    pub window: usize,
+    /// Minimum number of frames agreeing on the winning label of each glyph
+    pub min_votes: usize,
+    /// Minimum share of the winning label in the total score of each glyph
+    pub min_agreement: f64,
+    /// Whether consensus can be reached before the window is full
+    pub early_exit: bool,
+}
+
+impl Default for AccumulatorParams {
+    fn default() -> Self {
+        Self {
+            window: 5,
+            min_votes: 2,
+            min_agreement: 0.6,
+            early_exit: true,
+        }
+    }
+}
+
+impl AccumulatorParams {
+    pub fn window(mut self, window: usize) -> Self {
+        self.window = window;
+        self
+    }
+
+    pub fn min_votes(mut self, min_votes: usize) -> Self {
+        self.min_votes = min_votes;
+        self
+    }
+
+    pub fn min_agreement(mut self, min_agreement: f64) -> Self {
+        self.min_agreement = min_agreement;
+        self
+    }
+
+    pub fn early_exit(mut self, early_exit: bool) -> Self {
+        self.early_exit = early_exit;
+        self
+    }
+}
+
+/// The winning label of one glyph over the frames
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct GlyphVote {
+    label: GlyphLabel,
+    /// Number of frames predicting the label
+    votes: usize,
+    /// Share of the label in the total score of valid predictions
+    agreement: f64,
+}
+
+/// Accumulates the glyph predictions of several frames of the same code and
+/// votes on a consensus, so that a glyph misread in one frame does not fail the scan.
+///
+/// Invalid predictions do not vote. Each valid prediction votes with its score.
+#[derive(Debug, Default)]
+pub struct GlyphAccumulator {
+    params: AccumulatorParams,
+    frames: VecDeque<Vec<GlyphPrediction>>,
+}
+
+impl GlyphAccumulator {
+    pub fn new(params: AccumulatorParams) -> Self {
+        Self {
+            params,
+            frames: VecDeque::new(),
+        }
+    }
+
+    pub fn params(&self) -> &AccumulatorParams {
+        &self.params
+    }
+
+    pub fn num_frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Adds the predictions of one frame, and returns the consensus if it is reached.
+    ///
+    /// Frames with a different number of glyphs than the accumulated ones start over.
+    pub fn push(&mut self, predictions: Vec<GlyphPrediction>) -> Option<Vec<GlyphLabel>> {
+        if self.frames.front().is_some_and(|frame| frame.len() != predictions.len()) {
+            self.frames.clear();
+        }
+        self.frames.push_back(predictions);
+        while self.frames.len() > std::cmp::max(self.params.window, 1) {
+            self.frames.pop_front();
+        }
+        self.consensus()
+    }
+
+    /// Adds the glyphs read by `Acute32Recognizer::read_with_details`
+    pub fn push_output(&mut self, output: &RecognizerOutput) -> Option<Vec<GlyphLabel>> {
+        self.push(output.glyphs.iter().map(|glyph| glyph.prediction).collect())
+    }
+
+    /// The winning label of each glyph, Invalid if a glyph has no valid prediction
+    pub fn best_labels(&self) -> Vec<GlyphLabel> {
+        self.votes().into_iter()
+            .map(|vote| vote.map_or(GlyphLabel::Invalid, |vote| vote.label))
+            .collect()
+    }
+
+    /// The winning labels, if every glyph is confident enough
+    pub fn consensus(&self) -> Option<Vec<GlyphLabel>> {
+        if self.frames.is_empty() || (!self.params.early_exit && self.frames.len() < self.params.window) {
+            return None;
+        }
+        self.votes().into_iter()
+            .map(|vote| {
+                let vote = vote?;
+                if vote.votes >= self.params.min_votes && vote.agreement >= self.params.min_agreement {
+                    Some(vote.label)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    fn votes(&self) -> Vec<Option<GlyphVote>> {
+        let num_glyphs = self.frames.front().map_or(0, |frame| frame.len());
+        (0..num_glyphs).map(|i| {
+            // (label, number of votes, total score)
+            let mut totals: Vec<(GlyphLabel, usize, f64)> = vec![];
+            self.frames.iter().map(|frame| frame[i]).for_each(|prediction| {
+                if prediction.label == GlyphLabel::Invalid {
+                    return;
+                }
+                match totals.iter_mut().find(|(label, _, _)| *label == prediction.label) {
+                    Some((_, votes, total)) => {
+                        *votes += 1;
+                        *total += prediction.score;
+                    },
+                    None => totals.push((prediction.label, 1, prediction.score)),
+                }
+            });
+            let sum: f64 = totals.iter().map(|(_, _, total)| total).sum();
+            totals.into_iter()
+                .fold(None, |best: Option<(GlyphLabel, usize, f64)>, current| match best {
+                    Some(best) if best.2 >= current.2 => Some(best),
+                    _ => Some(current),
+                })
+                .map(|(label, votes, total)| GlyphVote {
+                    label,
+                    votes,
+                    agreement: if sum > 0.0 { total / sum } else { 1.0 },
+                })
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn prediction(label: GlyphLabel, score: f64) -> GlyphPrediction {
+        GlyphPrediction { label, score }
+    }
+
+    #[test]
+    fn accumulator_consensus_after_agreeing_frames() {
+        let mut accumulator = GlyphAccumulator::new(AccumulatorParams::default());
+        assert_eq!(accumulator.push(vec![prediction(GlyphLabel::LongRR, 0.9), prediction(GlyphLabel::LongDD, 0.8)]), None);
+        // One misread glyph
+        assert_eq!(accumulator.push(vec![prediction(GlyphLabel::LongRR, 0.9), prediction(GlyphLabel::LongRL, 0.5)]), None);
+        assert_eq!(accumulator.best_labels(), vec![GlyphLabel::LongRR, GlyphLabel::LongDD]);
+        assert_eq!(
+            accumulator.push(vec![prediction(GlyphLabel::LongRR, 0.9), prediction(GlyphLabel::LongDD, 0.8)]),
+            Some(vec![GlyphLabel::LongRR, GlyphLabel::LongDD])
+        );
+    }
+
+    #[test]
+    fn accumulator_invalid_glyphs_do_not_vote() {
+        let mut accumulator = GlyphAccumulator::new(AccumulatorParams::default().min_votes(1));
+        assert_eq!(accumulator.push(vec![prediction(GlyphLabel::Invalid, 0.0)]), None);
+        assert_eq!(accumulator.best_labels(), vec![GlyphLabel::Invalid]);
+        assert_eq!(accumulator.push(vec![prediction(GlyphLabel::LongDD, 0.7)]), Some(vec![GlyphLabel::LongDD]));
+    }
+
+    #[test]
+    fn accumulator_window_and_early_exit() {
+        let params = AccumulatorParams::default().window(3).min_votes(1).early_exit(false);
+        let mut accumulator = GlyphAccumulator::new(params);
+        assert_eq!(accumulator.push(vec![prediction(GlyphLabel::LongRR, 0.9)]), None);
+        assert_eq!(accumulator.push(vec![prediction(GlyphLabel::LongRR, 0.9)]), None);
+        assert_eq!(accumulator.push(vec![prediction(GlyphLabel::LongRR, 0.9)]), Some(vec![GlyphLabel::LongRR]));
+        for _ in 0..3 {
+            accumulator.push(vec![prediction(GlyphLabel::LongDD, 0.9)]);
+        }
+        // Older frames are out of the window
+        assert_eq!(accumulator.num_frames(), 3);
+        assert_eq!(accumulator.consensus(), Some(vec![GlyphLabel::LongDD]));
+
+        // A frame of another layout starts over
+        accumulator.push(vec![prediction(GlyphLabel::LongDD, 0.9); 2]);
+        assert_eq!(accumulator.num_frames(), 1);
+    }
+}
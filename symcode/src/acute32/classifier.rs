@@ -0,0 +1,428 @@
+use visioncortex::{BinaryImage, Sampler};
+
+use crate::math::euclid_dist_vec_f64;
+use super::{Acute32Library, Acute32SymcodeConfig, GlyphLabel};
+
+/// The label predicted for a glyph image, and how confident the classifier is about it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GlyphPrediction {
+    pub label: GlyphLabel,
+    /// In the range [0, 1], the higher the more confident
+    pub score: f64,
+}
+
+impl Default for GlyphPrediction {
+    fn default() -> Self {
+        Self { label: GlyphLabel::Invalid, score: 0.0 }
+    }
+}
+
+/// To classify a cropped glyph image in the rectified object space into a label
+pub trait GlyphClassifier {
+    fn classify(&self, image: &BinaryImage, symcode_config: &Acute32SymcodeConfig) -> GlyphPrediction;
+}
+
+/// The template matcher, by trace then by pixelwise difference
+impl GlyphClassifier for Acute32Library {
+    fn classify(&self, image: &BinaryImage, symcode_config: &Acute32SymcodeConfig) -> GlyphPrediction {
+        self.find_most_similar_glyph_with_score(image.clone(), symcode_config)
+    }
+}
+
+pub struct ZoningKnnParams {
+    /// The glyph image is divided into zones_per_side x zones_per_side zones
+    pub zones_per_side: usize,
+    /// Number of nearest neighbours that vote
+    pub k: usize,
+    /// Maximum translation (in pixels) applied to the templates in augmentation
+    pub max_shift: i32,
+    /// Fraction of pixels flipped in the noisy augmentations
+    pub noise_ratio: f64,
+    pub seed: u64,
+}
+
+impl Default for ZoningKnnParams {
+    fn default() -> Self {
+        Self {
+            zones_per_side: 8,
+            k: 5,
+            max_shift: 6,
+            noise_ratio: 0.05,
+            seed: 1,
+        }
+    }
+}
+
+impl ZoningKnnParams {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn zones_per_side(mut self, zones_per_side: usize) -> Self {
+        self.zones_per_side = zones_per_side;
+        self
+    }
+
+    pub fn k(mut self, k: usize) -> Self {
+        self.k = k;
+        self
+    }
+
+    pub fn max_shift(mut self, max_shift: i32) -> Self {
+        self.max_shift = max_shift;
+        self
+    }
+
+    pub fn noise_ratio(mut self, noise_ratio: f64) -> Self {
+        self.noise_ratio = noise_ratio;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// At least one zone and one neighbour, as no neighbour cannot vote
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.zones_per_side == 0 {
+            return Err("ZoningKnnParams error: zones_per_side must be positive.");
+        }
+        if self.k == 0 {
+            return Err("ZoningKnnParams error: k must be positive.");
+        }
+        Ok(())
+    }
+}
+
+/// A k-nearest-neighbours classifier over zoning features (ink density of each zone),
+/// trained from augmented renders of the library templates.
+pub struct ZoningKnnClassifier {
+    zones_per_side: usize,
+    k: usize,
+    samples: Vec<(GlyphLabel, Vec<f64>)>,
+}
+
+impl ZoningKnnClassifier {
+    const MODEL_HEADER: &'static str = "symcode-zoning-knn 1";
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Train the classifier from the templates in the library, each template is rendered
+    /// with shifts, erosion, dilation and noise.
+    pub fn train(library: &Acute32Library, params: &ZoningKnnParams, symcode_config: &Acute32SymcodeConfig) -> Result<Self, &'static str> {
+        params.validate()?;
+        let mut classifier = Self {
+            zones_per_side: params.zones_per_side,
+            k: params.k,
+            samples: vec![],
+        };
+        let mut rng = Lcg::new(params.seed);
+        for i in 0..library.len() {
+            let template = library.get_glyph_at(i).unwrap();
+            let image = Sampler::resample_image(&template.image, symcode_config.params.symbol_width, symcode_config.params.symbol_height);
+            for augmented in augment(&image, params, &mut rng) {
+                classifier.add_sample(&augmented, template.label)?;
+            }
+        }
+        Ok(classifier)
+    }
+
+    /// The label cannot be Invalid, which could not be voted for
+    pub fn add_sample(&mut self, image: &BinaryImage, label: GlyphLabel) -> Result<(), &'static str> {
+        if label == GlyphLabel::Invalid {
+            return Err("ZoningKnnClassifier error: cannot add a sample of an invalid label.");
+        }
+        let features = zoning_features(image, self.zones_per_side);
+        self.samples.push((label, features));
+        Ok(())
+    }
+
+    /// Serialize the trained model into a plain text format. The features are written exactly,
+    /// so that a loaded model classifies as the saved one
+    pub fn to_model_string(&self) -> Result<String, &'static str> {
+        let mut lines = vec![
+            Self::MODEL_HEADER.to_owned(),
+            format!("{} {} {}", self.zones_per_side, self.k, self.samples.len()),
+        ];
+        for (label, features) in self.samples.iter() {
+            let mut line = GlyphLabel::self_to_primitive(*label)
+                .ok_or("ZoningKnnClassifier error: cannot save a sample of an invalid label.")?
+                .to_string();
+            for feature in features.iter() {
+                line.push(' ');
+                line.push_str(&feature.to_string());
+            }
+            lines.push(line);
+        }
+        Ok(lines.join("\n"))
+    }
+
+    /// Deserialize a model produced by `to_model_string`
+    pub fn from_model_string(model: &str) -> Result<Self, &'static str> {
+        let mut lines = model.lines();
+        if lines.next() != Some(Self::MODEL_HEADER) {
+            return Err("ZoningKnnClassifier error: unrecognized model header.");
+        }
+        let parse_usize = |s: Option<&str>| -> Result<usize, &'static str> {
+            s.and_then(|s| s.parse().ok()).ok_or("ZoningKnnClassifier error: malformed model parameters.")
+        };
+        let mut params = lines.next().unwrap_or("").split_whitespace();
+        let zones_per_side = parse_usize(params.next())?;
+        let k = parse_usize(params.next())?;
+        let num_samples = parse_usize(params.next())?;
+        ZoningKnnParams::new().zones_per_side(zones_per_side).k(k).validate()?;
+
+        let num_features = zones_per_side * zones_per_side;
+        let mut samples = Vec::with_capacity(num_samples);
+        for line in lines.take(num_samples) {
+            let mut values = line.split_whitespace();
+            let label = parse_usize(values.next())?;
+            if label >= GlyphLabel::num_variants() {
+                return Err("ZoningKnnClassifier error: unknown label in model.");
+            }
+            let features: Vec<f64> = values
+                .map(|v| v.parse::<f64>().ok().filter(|feature| feature.is_finite()))
+                .collect::<Option<_>>()
+                .ok_or("ZoningKnnClassifier error: malformed feature in model.")?;
+            if features.len() != num_features {
+                return Err("ZoningKnnClassifier error: wrong number of features in model.");
+            }
+            samples.push((GlyphLabel::from_usize_representation(label), features));
+        }
+        if samples.len() != num_samples {
+            return Err("ZoningKnnClassifier error: model is truncated.");
+        }
+
+        Ok(Self { zones_per_side, k, samples })
+    }
+}
+
+impl GlyphClassifier for ZoningKnnClassifier {
+    fn classify(&self, image: &BinaryImage, _symcode_config: &Acute32SymcodeConfig) -> GlyphPrediction {
+        if self.samples.is_empty() {
+            return GlyphPrediction::default();
+        }
+        let features = zoning_features(image, self.zones_per_side);
+
+        let mut neighbours: Vec<(f64, GlyphLabel)> = self.samples.iter()
+            .map(|(label, sample)| (euclid_dist_vec_f64(&features, sample), *label))
+            .collect();
+        neighbours.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        // Each neighbour votes with a weight inversely proportional to its distance
+        let mut votes = vec![0.0; GlyphLabel::num_variants()];
+        let mut total = 0.0;
+        for (dist, label) in neighbours.into_iter().take(self.k) {
+            let weight = 1.0 / (dist + 1e-6);
+            votes[GlyphLabel::self_to_primitive(label).unwrap()] += weight;
+            total += weight;
+        }
+        let (best, &best_votes) = votes.iter().enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .unwrap();
+
+        GlyphPrediction {
+            label: GlyphLabel::from_usize_representation(best),
+            score: best_votes / total,
+        }
+    }
+}
+
+/// Ink density of each zone, in row major order
+fn zoning_features(image: &BinaryImage, zones_per_side: usize) -> Vec<f64> {
+    let mut features = Vec::with_capacity(zones_per_side * zones_per_side);
+    for zy in 0..zones_per_side {
+        let (top, bottom) = (zy * image.height / zones_per_side, (zy + 1) * image.height / zones_per_side);
+        for zx in 0..zones_per_side {
+            let (left, right) = (zx * image.width / zones_per_side, (zx + 1) * image.width / zones_per_side);
+            let area = (right - left) * (bottom - top);
+            if area == 0 {
+                features.push(0.0);
+                continue;
+            }
+            let mut count = 0;
+            for y in top..bottom {
+                for x in left..right {
+                    if image.get_pixel(x, y) {
+                        count += 1;
+                    }
+                }
+            }
+            features.push(count as f64 / area as f64);
+        }
+    }
+    features
+}
+
+/// Renders of a template under the distortions expected in the field
+fn augment(image: &BinaryImage, params: &ZoningKnnParams, rng: &mut Lcg) -> Vec<BinaryImage> {
+    let mut result = vec![image.clone(), erode(image), dilate(image)];
+    let s = params.max_shift;
+    if s > 0 {
+        for &(dx, dy) in [(-s, 0), (s, 0), (0, -s), (0, s), (-s, -s), (s, s), (-s, s), (s, -s)].iter() {
+            result.push(translate(image, dx, dy));
+        }
+    }
+    if params.noise_ratio > 0.0 {
+        for base in [image.clone(), erode(image), dilate(image)].iter() {
+            result.push(add_noise(base, params.noise_ratio, rng));
+        }
+    }
+    result
+}
+
+fn translate(image: &BinaryImage, dx: i32, dy: i32) -> BinaryImage {
+    let mut result = BinaryImage::new_w_h(image.width, image.height);
+    for y in 0..image.height {
+        for x in 0..image.width {
+            let (sx, sy) = (x as i32 - dx, y as i32 - dy);
+            if 0 <= sx && sx < image.width as i32 && 0 <= sy && sy < image.height as i32 {
+                result.set_pixel(x, y, image.get_pixel(sx as usize, sy as usize));
+            }
+        }
+    }
+    result
+}
+
+/// Apply f to the 4-neighbourhood of each pixel, out of bound pixels are treated as empty
+fn morph(image: &BinaryImage, f: fn(&[bool]) -> bool) -> BinaryImage {
+    let mut result = BinaryImage::new_w_h(image.width, image.height);
+    let get = |x: i32, y: i32| {
+        0 <= x && x < image.width as i32 && 0 <= y && y < image.height as i32 && image.get_pixel(x as usize, y as usize)
+    };
+    for y in 0..image.height {
+        for x in 0..image.width {
+            let (x, y) = (x as i32, y as i32);
+            let neighbourhood = [get(x, y), get(x-1, y), get(x+1, y), get(x, y-1), get(x, y+1)];
+            result.set_pixel(x as usize, y as usize, f(&neighbourhood));
+        }
+    }
+    result
+}
+
+fn erode(image: &BinaryImage) -> BinaryImage {
+    morph(image, |n| n.iter().all(|p| *p))
+}
+
+fn dilate(image: &BinaryImage) -> BinaryImage {
+    morph(image, |n| n.iter().any(|p| *p))
+}
+
+fn add_noise(image: &BinaryImage, noise_ratio: f64, rng: &mut Lcg) -> BinaryImage {
+    let mut result = image.clone();
+    for y in 0..image.height {
+        for x in 0..image.width {
+            if rng.next_f64() < noise_ratio {
+                result.set_pixel(x, y, !image.get_pixel(x, y));
+            }
+        }
+    }
+    result
+}
+
+/// A small deterministic generator so that training is reproducible without extra dependencies
+struct Lcg(u64);
+
+impl Lcg {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next_f64(&mut self) -> f64 {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (self.0 >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block_image(left: usize, top: usize) -> BinaryImage {
+        let mut image = BinaryImage::new_w_h(16, 16);
+        for y in top..top+8 {
+            for x in left..left+8 {
+                image.set_pixel(x, y, true);
+            }
+        }
+        image
+    }
+
+    fn classifier() -> ZoningKnnClassifier {
+        let mut classifier = ZoningKnnClassifier { zones_per_side: 4, k: 1, samples: vec![] };
+        classifier.add_sample(&block_image(0, 0), GlyphLabel::LongRR).unwrap();
+        classifier.add_sample(&block_image(8, 8), GlyphLabel::LongDD).unwrap();
+        classifier
+    }
+
+    #[test]
+    fn zoning_features_density() {
+        let features = zoning_features(&block_image(0, 0), 2);
+        assert_eq!(features, vec![1.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn zoning_knn_classify_nearest() {
+        let config = Acute32SymcodeConfig::default();
+        let prediction = classifier().classify(&block_image(7, 7), &config);
+        assert_eq!(prediction.label, GlyphLabel::LongDD);
+        assert!(prediction.score > 0.99);
+    }
+
+    #[test]
+    fn zoning_knn_model_round_trip() {
+        let classifier = classifier();
+        let loaded = ZoningKnnClassifier::from_model_string(&classifier.to_model_string().unwrap()).unwrap();
+        assert_eq!(loaded.len(), 2);
+        assert_eq!(loaded.zones_per_side, 4);
+        assert_eq!(loaded.samples[1], classifier.samples[1]);
+        assert!(ZoningKnnClassifier::from_model_string("not a model").is_err());
+
+        // Features which are not short decimals, e.g. of zones of 3 x 3 pixels, are kept exactly
+        let mut classifier = ZoningKnnClassifier { zones_per_side: 3, k: 3, samples: vec![] };
+        let mut image = BinaryImage::new_w_h(9, 9);
+        image.set_pixel(0, 0, true);
+        image.set_pixel(4, 5, true);
+        image.set_pixel(8, 1, true);
+        classifier.add_sample(&image, GlyphLabel::ArrowRL).unwrap();
+        classifier.add_sample(&translate(&image, 1, 2), GlyphLabel::TriforceU).unwrap();
+        let model = classifier.to_model_string().unwrap();
+        let loaded = ZoningKnnClassifier::from_model_string(&model).unwrap();
+        assert_eq!((loaded.zones_per_side, loaded.k), (3, 3));
+        assert_eq!(loaded.samples, classifier.samples);
+        assert_eq!(loaded.to_model_string().unwrap(), model);
+    }
+
+    #[test]
+    fn zoning_knn_rejects_invalid() {
+        let mut classifier = classifier();
+        assert!(classifier.add_sample(&block_image(0, 8), GlyphLabel::Invalid).is_err());
+        assert_eq!(classifier.len(), 2);
+
+        // No neighbour would vote
+        assert!(ZoningKnnParams::new().k(0).validate().is_err());
+        assert!(ZoningKnnParams::new().zones_per_side(0).validate().is_err());
+        assert!(ZoningKnnParams::new().validate().is_ok());
+        let model = classifier.to_model_string().unwrap().replacen("4 1 2", "4 0 2", 1);
+        assert!(ZoningKnnClassifier::from_model_string(&model).is_err());
+        assert!(ZoningKnnClassifier::train(&Acute32Library::default(), &ZoningKnnParams::new().k(0), &Acute32SymcodeConfig::default()).is_err());
+
+        // A feature which is not finite would have no order among the distances
+        let model = |feature: &str| format!("{}\n2 1 1\n0 0 {} 0.5 1", ZoningKnnClassifier::MODEL_HEADER, feature);
+        assert!(ZoningKnnClassifier::from_model_string(&model("0.25")).is_ok());
+        assert!(ZoningKnnClassifier::from_model_string(&model("NaN")).is_err());
+        assert!(ZoningKnnClassifier::from_model_string(&model("inf")).is_err());
+        assert!(ZoningKnnClassifier::from_model_string(&model("x")).is_err());
+
+        // Samples of an invalid label cannot be saved as another label
+        classifier.samples.push((GlyphLabel::Invalid, vec![0.0; 16]));
+        assert!(classifier.to_model_string().is_err());
+    }
+}
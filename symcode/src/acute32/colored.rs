@@ -0,0 +1,394 @@
+use bit_vec::BitVec;
+use visioncortex::{Color, ColorImage, PerspectiveTransform, PointF64};
+use crate::interfaces::{ColorSymcodeGenerator, Debugger, Decoder as DecoderInterface, DummyDebugger, Encoder as EncoderInterface, Reader};
+use crate::math::{crc5_of_bits, into_bitvec, num_bits_to_store};
+use super::{Acute32Generator, Acute32Recognizer, Acute32SymcodeConfig, GlyphLabel, Polarity, RecognizerOutput};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// The inks the glyphs of the colored variant may be printed in.
+///
+/// Inks are told apart by their hue, so they should differ in chromaticity rather than brightness.
+/// The finders are printed in the first ink, which should be dark.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct InkPalette {
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_helpers::colors"))]
+    inks: Vec<Color>,
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_helpers::color"))]
+    pub background: Color,
+}
+
+impl Default for InkPalette {
+    fn default() -> Self {
+        Self {
+            inks: vec![
+                Color::new(0, 0, 0),
+                Color::new(200, 30, 30),
+                Color::new(20, 140, 40),
+                Color::new(30, 50, 200),
+            ],
+            background: Color::new(255, 255, 255),
+        }
+    }
+}
+
+impl InkPalette {
+    /// The number of inks has to be a power of two, at least 2
+    pub fn new(inks: Vec<Color>, background: Color) -> Result<Self, &'static str> {
+        let palette = Self { inks, background };
+        palette.validate()?;
+        Ok(palette)
+    }
+
+    /// A deserialized palette may have any number of inks
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.inks.len() < 2 || !self.inks.len().is_power_of_two() {
+            return Err("Ink palette: the number of inks must be a power of two, at least 2.");
+        }
+        Ok(())
+    }
+
+    pub fn inks(&self) -> &[Color] {
+        &self.inks
+    }
+
+    pub fn ink(&self, index: usize) -> Option<Color> {
+        self.inks.get(index).copied()
+    }
+
+    /// Number of bits the ink of a glyph encodes
+    pub fn num_bits(&self) -> usize {
+        num_bits_to_store(self.inks.len())
+    }
+
+    /// The index of the ink closest in chromaticity (the color divided by its brightness),
+    /// so that the classification is not affected by the brightness of the lighting
+    pub fn classify(&self, color: &Color) -> usize {
+        let chroma = |c: &Color| {
+            let sum = c.r as f64 + c.g as f64 + c.b as f64;
+            if sum == 0.0 {
+                return [1.0 / 3.0; 3];
+            }
+            [c.r as f64 / sum, c.g as f64 / sum, c.b as f64 / sum]
+        };
+        let observed = chroma(color);
+        let distance = |ink: &Color| {
+            chroma(ink).iter().zip(observed.iter()).map(|(a, b)| (a - b) * (a - b)).sum::<f64>()
+        };
+        self.inks.iter().enumerate()
+            .min_by(|(_, a), (_, b)| distance(a).partial_cmp(&distance(b)).unwrap())
+            .map_or(0, |(i, _)| i)
+    }
+}
+
+/// A glyph of the colored variant: its shape and the index of its ink in the palette
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ColoredGlyph {
+    pub label: GlyphLabel,
+    pub ink: usize,
+}
+
+const CHECKSUM_BITS: usize = 5;
+
+fn ink_palette(config: &Acute32SymcodeConfig) -> Result<&InkPalette, &'static str> {
+    config.params.ink_palette.as_ref().ok_or("Colored variant: no ink palette in config.")
+}
+
+fn label_bits() -> usize {
+    num_bits_to_store(GlyphLabel::num_variants())
+}
+
+/// Encodes the payload into colored glyphs, each carrying the bits of its label followed by the bits of its ink.
+///
+/// As in `Acute32Encoder`, the last 5 bits of the code are the CRC5 checksum of the payload.
+pub struct Acute32ColorEncoder<'a> {
+    config: &'a Acute32SymcodeConfig,
+}
+
+impl<'a> Acute32ColorEncoder<'a> {
+    pub fn new(config: &'a Acute32SymcodeConfig) -> Acute32ColorEncoder<'a> {
+        Self { config }
+    }
+
+    /// Number of payload bits in a code of num_glyphs glyphs
+    pub fn payload_len(&self, num_glyphs: usize) -> Result<usize, &'static str> {
+        let bits_per_glyph = label_bits() + ink_palette(self.config)?.num_bits();
+        Ok((bits_per_glyph * num_glyphs).saturating_sub(CHECKSUM_BITS))
+    }
+}
+
+impl EncoderInterface for Acute32ColorEncoder<'_> {
+    type SymcodeRepresentation = Vec<ColoredGlyph>;
+
+    fn encode(&self, payload: BitVec, num_glyphs: usize) -> Result<Self::SymcodeRepresentation, &'static str> {
+        let ink_bits = ink_palette(self.config)?.num_bits();
+        if payload.len() != self.payload_len(num_glyphs)? {
+            panic!("Input bits length and self-defined length do not agree!");
+        }
+
+        let checksum = into_bitvec(crc5_of_bits(&payload) as usize, CHECKSUM_BITS);
+        let payload_with_checksum: BitVec = payload.iter().chain(checksum.iter()).collect();
+
+        let bits_per_glyph = label_bits() + ink_bits;
+        let result: Self::SymcodeRepresentation = (0..num_glyphs).map(|i| {
+            let offset = i * bits_per_glyph;
+            let label_bit_vec = BitVec::from_fn(label_bits(), |j| payload_with_checksum[offset + j]);
+            let ink_bit_vec = BitVec::from_fn(ink_bits, |j| payload_with_checksum[offset + label_bits() + j]);
+            ColoredGlyph {
+                label: self.config.params.label_bit_mapping.bits_to_label(label_bit_vec),
+                ink: GlyphLabel::bit_vec_to_primitive(ink_bit_vec),
+            }
+        }).collect();
+
+        // Sanity check
+        match Acute32ColorDecoder::new(self.config).decode(result.clone()) {
+            Ok(decoded_payload) => if payload != decoded_payload { return Err("Encoder error: sanity check failed.") },
+            Err(e) => return Err(e),
+        }
+
+        Ok(result)
+    }
+}
+
+pub struct Acute32ColorDecoder<'a> {
+    config: &'a Acute32SymcodeConfig,
+}
+
+impl<'a> Acute32ColorDecoder<'a> {
+    pub fn new(config: &'a Acute32SymcodeConfig) -> Acute32ColorDecoder<'a> {
+        Self { config }
+    }
+}
+
+impl DecoderInterface for Acute32ColorDecoder<'_> {
+    type Symbol = ColoredGlyph;
+
+    type Err = &'static str;
+
+    fn decode(&self, encoded_data: Vec<Self::Symbol>) -> Result<BitVec, Self::Err> {
+        let palette = ink_palette(self.config)?;
+        let mut bits = BitVec::new();
+        for glyph in encoded_data.iter() {
+            match self.config.params.label_bit_mapping.label_to_bits(glyph.label) {
+                Some(bit_vec) => bits.extend(bit_vec),
+                None => return Err("Decoder error: Some recognized glyph is invalid."),
+            }
+            if glyph.ink >= palette.inks().len() {
+                return Err("Decoder error: Some recognized ink is not in the palette.");
+            }
+            bits.extend(into_bitvec(glyph.ink, palette.num_bits()));
+        }
+        if bits.len() <= CHECKSUM_BITS {
+            return Err("Decoder error: Not enough glyphs.");
+        }
+
+        let payload_len = bits.len() - CHECKSUM_BITS;
+        let payload: BitVec = bits.iter().take(payload_len).collect();
+        let checksum = GlyphLabel::bit_vec_to_primitive(bits.iter().skip(payload_len).collect());
+        if crc5_of_bits(&payload) as usize != checksum {
+            Err("Decoder error: Checksum fail")
+        } else {
+            Ok(payload)
+        }
+    }
+
+    fn num_bits_per_symbol(&self) -> usize {
+        label_bits() + self.config.params.ink_palette.as_ref().map_or(0, |palette| palette.num_bits())
+    }
+}
+
+/// Renders a colored code: the finders in the first ink and each glyph in its own ink, on the palette background
+pub struct Acute32ColorGenerator<'a> {
+    config: &'a Acute32SymcodeConfig,
+}
+
+impl<'a> Acute32ColorGenerator<'a> {
+    pub fn new(config: &'a Acute32SymcodeConfig) -> Acute32ColorGenerator<'a> {
+        Self { config }
+    }
+}
+
+impl ColorSymcodeGenerator for Acute32ColorGenerator<'_> {
+    type SymcodeRepresentation = Vec<ColoredGlyph>;
+
+    fn generate(&self, symcode: Self::SymcodeRepresentation) -> Result<ColorImage, &'static str> {
+        let config = self.config;
+        let palette = ink_palette(config)?;
+        let mut symcode_image = ColorImage::new_w_h(config.params.code_width, config.params.code_height);
+        for y in 0..symcode_image.height {
+            for x in 0..symcode_image.width {
+                symcode_image.set_pixel(x, y, &palette.background);
+            }
+        }
+
+        let mut paint = |image: &visioncortex::BinaryImage, left: i32, top: i32, color: &Color| {
+            for y in 0..image.height {
+                for x in 0..image.width {
+                    let (cx, cy) = (left + x as i32, top + y as i32);
+                    if image.get_pixel(x, y) && cx >= 0 && cy >= 0 && (cx as usize) < config.params.code_width && (cy as usize) < config.params.code_height {
+                        symcode_image.set_pixel(cx as usize, cy as usize, color);
+                    }
+                }
+            }
+        };
+
+        // The finders alone
+        let finders = Acute32Generator::new(config).generate_with_polarity(vec![GlyphLabel::Invalid; symcode.len()], Polarity::DarkOnLight);
+        paint(&finders, 0, 0, &palette.inks()[0]);
+
+        for (glyph, anchor) in symcode.iter().zip(config.params.glyph_anchors.iter()) {
+            if glyph.label == GlyphLabel::Invalid {
+                continue;
+            }
+            let ink = palette.ink(glyph.ink).ok_or("Generator error: Ink is not in the palette.")?;
+            if let Some(template) = config.symbol_library.get_glyph_with_label(glyph.label) {
+                let top_left = anchor.to_point_i32();
+                paint(&template.image, top_left.x, top_left.y, &ink);
+            }
+        }
+
+        Ok(symcode_image)
+    }
+}
+
+/// Reads the shapes of the glyphs as `Acute32Recognizer` does, then the ink of each glyph
+/// from the mean color of the frame under its ink pixels
+pub struct Acute32ColorRecognizer<'a> {
+    config: &'a Acute32SymcodeConfig,
+    debugger: &'a dyn Debugger,
+}
+
+impl<'a> Acute32ColorRecognizer<'a> {
+    pub fn new(config: &'a Acute32SymcodeConfig) -> Acute32ColorRecognizer<'a> {
+        Self { config, debugger: &DummyDebugger }
+    }
+
+    /// Renders the rectified code and the glyph crops
+    pub fn debugger(mut self, debugger: &'a dyn Debugger) -> Self {
+        self.debugger = debugger;
+        self
+    }
+
+    /// Same as `read`, but keeps the output of reading the shapes
+    pub fn read_with_details(&self, raw_frame: ColorImage, image_to_object: PerspectiveTransform) -> Result<(RecognizerOutput, Vec<ColoredGlyph>), &'static str> {
+        let palette = ink_palette(self.config)?;
+        let (output, image_to_object) = Acute32Recognizer::read_glyphs_with_final_transform(
+            &raw_frame, image_to_object, &self.config.symbol_library, self.config, self.debugger
+        );
+        let glyphs = output.glyphs.iter().map(|glyph| {
+            let ink = match (&glyph.image, &glyph.rect) {
+                (Some(image), Some(rect)) if glyph.prediction.label != GlyphLabel::Invalid => {
+                    Self::mean_ink_color(&raw_frame, &image_to_object, image, rect.left, rect.top, self.config)
+                        .map_or(0, |color| palette.classify(&color))
+                },
+                _ => 0,
+            };
+            ColoredGlyph { label: glyph.prediction.label, ink }
+        }).collect();
+        Ok((output, glyphs))
+    }
+
+    /// The mean color of the frame at the set pixels of the glyph image, which is at (left, top) in the object space
+    fn mean_ink_color(raw_frame: &ColorImage, image_to_object: &PerspectiveTransform, glyph_image: &visioncortex::BinaryImage, left: i32, top: i32, config: &Acute32SymcodeConfig) -> Option<Color> {
+        let mut sum = [0u64; 3];
+        let mut count = 0;
+        for y in 0..glyph_image.height {
+            for x in 0..glyph_image.width {
+                if !glyph_image.get_pixel(x, y) {
+                    continue;
+                }
+                let point = image_to_object.transform_inverse(PointF64::new((left + x as i32) as f64, (top + y as i32) as f64));
+                let point = config.params.lens_distortion.map_or(point, |lens| lens.distort(point)).to_point_i32();
+                if point.x < 0 || point.y < 0 || point.x as usize >= raw_frame.width || point.y as usize >= raw_frame.height {
+                    continue;
+                }
+                let c = raw_frame.get_pixel(point.x as usize, point.y as usize);
+                sum[0] += c.r as u64;
+                sum[1] += c.g as u64;
+                sum[2] += c.b as u64;
+                count += 1;
+            }
+        }
+        if count == 0 {
+            return None;
+        }
+        Some(Color::new((sum[0] / count) as u8, (sum[1] / count) as u8, (sum[2] / count) as u8))
+    }
+}
+
+impl Reader for Acute32ColorRecognizer<'_> {
+    type Symbol = ColoredGlyph;
+
+    fn read(&self, raw_frame: ColorImage, image_to_object: PerspectiveTransform) -> Result<Vec<ColoredGlyph>, &'static str> {
+        let (output, glyphs) = self.read_with_details(raw_frame, image_to_object)?;
+        output.check_quiet_zone(self.config)?;
+        Ok(glyphs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::acute32::ReaderMode;
+    use crate::acute32::test_fixtures::{identity, use_half_glyph_library};
+    use super::*;
+
+    fn colored_config() -> Acute32SymcodeConfig {
+        let mut config = Acute32SymcodeConfig::default();
+        config.params.ink_palette = Some(InkPalette::default());
+        config
+    }
+
+    #[test]
+    fn colored_palette_classify() {
+        let palette = InkPalette::default();
+        assert_eq!(palette.num_bits(), 2);
+        assert_eq!(palette.classify(&Color::new(90, 85, 88)), 0);
+        // Darker and brighter than the inks themselves
+        assert_eq!(palette.classify(&Color::new(120, 20, 20)), 1);
+        assert_eq!(palette.classify(&Color::new(90, 230, 110)), 2);
+        assert_eq!(palette.classify(&Color::new(10, 20, 90)), 3);
+        assert!(InkPalette::new(vec![Color::new(0, 0, 0); 3], Color::new(255, 255, 255)).is_err());
+    }
+
+    #[test]
+    fn colored_encode_decode() {
+        let config = colored_config();
+        let encoder = Acute32ColorEncoder::new(&config);
+        // 5 glyphs of 5 + 2 bits, less the checksum
+        assert_eq!(encoder.payload_len(5), Ok(30));
+
+        let payload = BitVec::from_fn(30, |i| i % 3 == 0 || i % 7 == 1);
+        let symcode = encoder.encode(payload.clone(), 5).unwrap();
+        assert!(symcode.iter().any(|glyph| glyph.ink != 0));
+        let decoder = Acute32ColorDecoder::new(&config);
+        assert_eq!(decoder.decode(symcode.clone()), Ok(payload));
+
+        // A glyph read in the wrong ink fails the checksum
+        let mut misread = symcode;
+        misread[2].ink = (misread[2].ink + 1) % 4;
+        assert!(decoder.decode(misread).is_err());
+
+        assert!(Acute32ColorDecoder::new(&Acute32SymcodeConfig::default()).decode(vec![]).is_err());
+    }
+
+    #[test]
+    fn colored_generated_code_reads_shape_and_ink() {
+        let mut config = colored_config();
+        config.params.reader_mode = ReaderMode::AnchorSampling { search_radius: 0, search_step: 1 };
+        let (left, right) = use_half_glyph_library(&mut config);
+
+        let symcode = vec![
+            ColoredGlyph { label: left, ink: 1 },
+            ColoredGlyph { label: right, ink: 0 },
+            ColoredGlyph { label: left, ink: 3 },
+            ColoredGlyph { label: right, ink: 2 },
+            ColoredGlyph { label: right, ink: 1 },
+        ];
+        let frame = Acute32ColorGenerator::new(&config).generate(symcode.clone()).unwrap();
+        let center = config.params.finder_positions[0].to_point_i32();
+        assert_eq!(frame.get_pixel(center.x as usize, center.y as usize), Color::new(0, 0, 0));
+
+        assert_eq!(Acute32ColorRecognizer::new(&config).read(frame, identity(&config)), Ok(symcode));
+    }
+}
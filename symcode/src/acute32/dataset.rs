@@ -0,0 +1,267 @@
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use visioncortex::BinaryImage;
+
+use super::{GlyphLabel, RecognizerOutput};
+
+/// Destination of the exported files
+pub trait DatasetSink {
+    /// `path` is relative to the root of the dataset, with '/' as separator
+    fn write_file(&mut self, path: &str, contents: &[u8]) -> io::Result<()>;
+
+    /// Called once after all files are written
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Writes the dataset into a directory on the filesystem
+pub struct DirectorySink {
+    root: PathBuf,
+}
+
+impl DirectorySink {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl DatasetSink for DirectorySink {
+    fn write_file(&mut self, path: &str, contents: &[u8]) -> io::Result<()> {
+        let mut file_path = self.root.clone();
+        path.split('/').for_each(|component| file_path.push(component));
+        if let Some(parent) = file_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(file_path, contents)
+    }
+}
+
+/// Writes the dataset as an uncompressed tar archive
+pub struct TarSink<W: Write> {
+    writer: W,
+}
+
+const TAR_BLOCK_SIZE: usize = 512;
+
+impl<W: Write> TarSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// ustar header of a regular file
+    fn header(path: &str, size: usize) -> io::Result<[u8; TAR_BLOCK_SIZE]> {
+        if path.len() >= 100 {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "TarSink error: path too long."));
+        }
+        let mut header = [0u8; TAR_BLOCK_SIZE];
+        let mut put = |offset: usize, bytes: &[u8]| header[offset..offset + bytes.len()].copy_from_slice(bytes);
+        put(0, path.as_bytes());
+        put(100, b"0000644\0");
+        put(108, b"0000000\0");
+        put(116, b"0000000\0");
+        put(124, format!("{:011o}\0", size).as_bytes());
+        put(136, b"00000000000\0");
+        put(148, b"        "); // checksum is computed with this field filled with spaces
+        put(156, b"0");
+        put(257, b"ustar\0");
+        put(263, b"00");
+
+        let checksum: u32 = header.iter().map(|b| *b as u32).sum();
+        header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+        Ok(header)
+    }
+}
+
+impl<W: Write> DatasetSink for TarSink<W> {
+    fn write_file(&mut self, path: &str, contents: &[u8]) -> io::Result<()> {
+        self.writer.write_all(&Self::header(path, contents.len())?)?;
+        self.writer.write_all(contents)?;
+        let padding = (TAR_BLOCK_SIZE - contents.len() % TAR_BLOCK_SIZE) % TAR_BLOCK_SIZE;
+        self.writer.write_all(&vec![0; padding])
+    }
+
+    /// The end of archive is marked by two empty blocks
+    fn finish(&mut self) -> io::Result<()> {
+        self.writer.write_all(&[0; 2 * TAR_BLOCK_SIZE])?;
+        self.writer.flush()
+    }
+}
+
+/// Exports the rectified codes and the glyph crops of scanned frames, with the predicted
+/// labels and scores (and ground truth if known) listed in `labels.csv`.
+///
+/// Layout of the dataset:
+/// ```text
+/// frame_000000/code.pbm
+/// frame_000000/glyph_0.pbm
+/// ...
+/// labels.csv
+/// ```
+pub struct Acute32DatasetExporter<S: DatasetSink> {
+    sink: S,
+    num_frames: usize,
+    manifest: Vec<String>,
+}
+
+impl<S: DatasetSink> Acute32DatasetExporter<S> {
+    const MANIFEST_HEADER: &'static str = "frame,glyph,image,predicted,score,ground_truth";
+
+    pub fn new(sink: S) -> Self {
+        Self {
+            sink,
+            num_frames: 0,
+            manifest: vec![Self::MANIFEST_HEADER.to_owned()],
+        }
+    }
+
+    pub fn num_frames(&self) -> usize {
+        self.num_frames
+    }
+
+    /// Exports the output of `Acute32Recognizer::read_with_details` for one frame.
+    ///
+    /// `ground_truth`, if given, should have one label for each glyph anchor.
+    pub fn export_frame(&mut self, output: &RecognizerOutput, ground_truth: Option<&[GlyphLabel]>) -> io::Result<()> {
+        if let Some(ground_truth) = ground_truth {
+            if ground_truth.len() != output.glyphs.len() {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, "Dataset error: ground truth length does not agree with number of glyphs."));
+            }
+        }
+
+        let frame_dir = format!("frame_{:06}", self.num_frames);
+        self.sink.write_file(&format!("{}/code.pbm", frame_dir), &to_pbm(&output.rectified_image))?;
+
+        for (i, glyph) in output.glyphs.iter().enumerate() {
+            let image_path = if let Some(image) = &glyph.image {
+                let path = format!("{}/glyph_{}.pbm", frame_dir, i);
+                self.sink.write_file(&path, &to_pbm(image))?;
+                path
+            } else {
+                String::new()
+            };
+            let ground_truth = ground_truth.map_or(String::new(), |labels| format!("{:?}", labels[i]));
+            self.manifest.push(format!(
+                "{},{},{},{:?},{:.4},{}",
+                self.num_frames, i, image_path, glyph.prediction.label, glyph.prediction.score, ground_truth
+            ));
+        }
+
+        self.num_frames += 1;
+        Ok(())
+    }
+
+    /// Writes the manifest and returns the sink
+    pub fn finish(mut self) -> io::Result<S> {
+        let mut manifest = self.manifest.join("\n");
+        manifest.push('\n');
+        self.sink.write_file("labels.csv", manifest.as_bytes())?;
+        self.sink.finish()?;
+        Ok(self.sink)
+    }
+}
+
+/// Encodes the image as binary PBM (P4), set pixels are black
+pub fn to_pbm(image: &BinaryImage) -> Vec<u8> {
+    let mut result = format!("P4\n{} {}\n", image.width, image.height).into_bytes();
+    let row_bytes = image.width.div_ceil(8);
+    for y in 0..image.height {
+        let mut row = vec![0u8; row_bytes];
+        for x in 0..image.width {
+            if image.get_pixel(x, y) {
+                row[x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+        result.extend_from_slice(&row);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::acute32::{GlyphPrediction, Polarity, RecognizedGlyph};
+
+    #[derive(Default)]
+    struct MemorySink {
+        files: Vec<(String, Vec<u8>)>,
+    }
+
+    impl DatasetSink for MemorySink {
+        fn write_file(&mut self, path: &str, contents: &[u8]) -> io::Result<()> {
+            self.files.push((path.to_owned(), contents.to_vec()));
+            Ok(())
+        }
+    }
+
+    fn output() -> RecognizerOutput {
+        let mut glyph_image = BinaryImage::new_w_h(2, 2);
+        glyph_image.set_pixel(0, 0, true);
+        RecognizerOutput {
+            rectified_image: BinaryImage::new_w_h(4, 4),
+            polarity: Polarity::DarkOnLight,
+            glyphs: vec![
+                RecognizedGlyph { image: Some(glyph_image), rect: None, prediction: GlyphPrediction { label: GlyphLabel::LongRR, score: 0.5 } },
+                RecognizedGlyph { image: None, rect: None, prediction: GlyphPrediction::default() },
+            ],
+            refinement: None,
+            quiet_zone: None,
+        }
+    }
+
+    #[test]
+    fn dataset_pbm() {
+        let mut image = BinaryImage::new_w_h(9, 2);
+        image.set_pixel(0, 0, true);
+        image.set_pixel(8, 1, true);
+        let mut expected = b"P4\n9 2\n".to_vec();
+        expected.extend_from_slice(&[0x80, 0x00, 0x00, 0x80]);
+        assert_eq!(to_pbm(&image), expected);
+    }
+
+    #[test]
+    fn dataset_export_frames() {
+        let mut exporter = Acute32DatasetExporter::new(MemorySink::default());
+        exporter.export_frame(&output(), None).unwrap();
+        exporter.export_frame(&output(), Some(&[GlyphLabel::LongRR, GlyphLabel::LongDD])).unwrap();
+        assert!(exporter.export_frame(&output(), Some(&[GlyphLabel::LongRR])).is_err());
+        let sink = exporter.finish().unwrap();
+
+        let paths: Vec<&str> = sink.files.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(paths, vec![
+            "frame_000000/code.pbm", "frame_000000/glyph_0.pbm",
+            "frame_000001/code.pbm", "frame_000001/glyph_0.pbm",
+            "labels.csv",
+        ]);
+        let manifest = String::from_utf8(sink.files[4].1.clone()).unwrap();
+        assert_eq!(manifest,
+            "frame,glyph,image,predicted,score,ground_truth\n\
+            0,0,frame_000000/glyph_0.pbm,LongRR,0.5000,\n\
+            0,1,,Invalid,0.0000,\n\
+            1,0,frame_000001/glyph_0.pbm,LongRR,0.5000,LongRR\n\
+            1,1,,Invalid,0.0000,LongDD\n"
+        );
+    }
+
+    #[test]
+    fn dataset_tar_archive() {
+        let mut exporter = Acute32DatasetExporter::new(TarSink::new(vec![]));
+        exporter.export_frame(&output(), None).unwrap();
+        let archive = exporter.finish().unwrap().into_inner();
+
+        // 3 files each with a header block and a data block, plus 2 end blocks
+        assert_eq!(archive.len(), 8 * 512);
+        assert_eq!(&archive[0..21], b"frame_000000/code.pbm");
+        assert_eq!(&archive[257..263], b"ustar\0");
+        let checksum: u32 = archive[0..512].iter().enumerate()
+            .map(|(i, b)| if (148..156).contains(&i) { b' ' as u32 } else { *b as u32 })
+            .sum();
+        assert_eq!(&archive[148..156], format!("{:06o}\0 ", checksum).as_bytes());
+        assert!(archive[6 * 512..].iter().all(|b| *b == 0));
+    }
+}
@@ -1,9 +1,9 @@
 use bit_vec::BitVec;
+use crczoo::CrcCorrection;
 use crate::interfaces::Decoder;
 use super::{Acute32SymcodeConfig, GlyphLabel};
 
 pub struct Acute32Decoder<'a> {
-    #[allow(dead_code)]
     config: &'a Acute32SymcodeConfig,
 }
 
@@ -11,9 +11,21 @@ impl<'a> Acute32Decoder<'a> {
     pub fn new(config: &'a Acute32SymcodeConfig) -> Acute32Decoder<'a> {
         Self { config }
     }
+
+    /// Locates the wrong bit among the payload and checksum bits by the CRC syndrome
+    fn correct_single_bit_error(payload: &BitVec, checksum: u8) -> Result<BitVec, &'static str> {
+        let mut codeword: Vec<bool> = payload.iter().chain(crate::math::into_bitvec(checksum as usize, 5).iter()).collect();
+        match crczoo::CRC5_PARAMS.correct_single_bit_error(&mut codeword) {
+            CrcCorrection::Corrected { position, .. } => {
+                log::info!("Decoder: corrected bit {}.", position);
+                Ok(codeword[..payload.len()].iter().cloned().collect())
+            },
+            _ => Err("Decoder error: Checksum fail"),
+        }
+    }
 }
 
-// Dummy implementation, error detection/correction will be supported later
+/// Verifies the 20 bit payload against its 5 bit CRC, correcting a single wrong bit if configured to
 impl Decoder for Acute32Decoder<'_> {
     type Symbol = GlyphLabel;
 
@@ -23,7 +35,7 @@ impl Decoder for Acute32Decoder<'_> {
         let num_bits_per_symbol = self.num_bits_per_symbol();
         let mut decoded_data = vec![];
         for &symbol in encoded_data.iter() {
-            if let Some(bit_vec) = GlyphLabel::self_to_bit_vec(symbol, num_bits_per_symbol) {
+            if let Some(bit_vec) = self.config.params.label_bit_mapping.label_to_bits(symbol) {
                 decoded_data.push(bit_vec);
             } else {
                 return Err("Decoder error: Some recognized glyph is invalid.");
@@ -45,10 +57,12 @@ impl Decoder for Acute32Decoder<'_> {
             }
         }
 
-        if crczoo::crc5(&payload.to_bytes()) != checksum {
-            Err("Decoder error: Checksum fail")
-        } else {
+        if self.config.params.payload_checksum(&payload) == checksum {
             Ok(payload)
+        } else if self.config.params.correct_single_bit_errors && !self.config.params.legacy_checksum {
+            Self::correct_single_bit_error(&payload, checksum)
+        } else {
+            Err("Decoder error: Checksum fail")
         }
     }
 
@@ -56,3 +70,37 @@ impl Decoder for Acute32Decoder<'_> {
         crate::math::num_bits_to_store(GlyphLabel::num_variants())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::interfaces::Encoder;
+    use super::super::Acute32Encoder;
+    use super::*;
+
+    #[test]
+    fn decoder_single_bit_error() {
+        let mut config = Acute32SymcodeConfig::default();
+        let payload = BitVec::from_fn(20, |i| i % 3 == 1);
+        let mut symcode = Acute32Encoder::new(&config).encode(payload.clone(), 5).unwrap();
+        // A glyph taken for one which differs in a bit
+        let primitive = GlyphLabel::self_to_primitive(symcode[2]).unwrap();
+        symcode[2] = GlyphLabel::from_usize_representation(primitive ^ 0b100);
+        assert!(Acute32Decoder::new(&config).decode(symcode.clone()).is_err());
+
+        config.params.correct_single_bit_errors = true;
+        assert_eq!(Acute32Decoder::new(&config).decode(symcode), Ok(payload));
+    }
+
+    #[test]
+    fn decoder_legacy_checksum() {
+        let mut legacy = Acute32SymcodeConfig::default();
+        legacy.params.legacy_checksum = true;
+        let payload = BitVec::from_fn(20, |i| i % 3 == 1);
+        let symcode = Acute32Encoder::new(&legacy).encode(payload.clone(), 5).unwrap();
+        assert_eq!(Acute32Decoder::new(&legacy).decode(symcode.clone()), Ok(payload.clone()));
+
+        // The checksum of the padded bytes differs, so a code printed by symcode 0.1 does not decode by default
+        assert_ne!(crate::math::crc5_of_padded_bytes(&payload), crate::math::crc5_of_bits(&payload));
+        assert!(Acute32Decoder::new(&Acute32SymcodeConfig::default()).decode(symcode).is_err());
+    }
+}
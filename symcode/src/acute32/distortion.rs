@@ -0,0 +1,189 @@
+use visioncortex::{BoundingRect, PointF64};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Brown-Conrady lens distortion model, with radial (k1, k2, k3) and tangential (p1, p2) coefficients.
+///
+/// The camera intrinsics (focal lengths fx, fy and principal point cx, cy) are in pixels of the frame.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LensDistortion {
+    pub k1: f64,
+    pub k2: f64,
+    pub k3: f64,
+    pub p1: f64,
+    pub p2: f64,
+    pub fx: f64,
+    pub fy: f64,
+    pub cx: f64,
+    pub cy: f64,
+}
+
+impl LensDistortion {
+    const UNDISTORT_ITERATIONS: usize = 20;
+
+    /// No distortion until the coefficients are set
+    pub fn new(fx: f64, fy: f64, cx: f64, cy: f64) -> Self {
+        Self { k1: 0.0, k2: 0.0, k3: 0.0, p1: 0.0, p2: 0.0, fx, fy, cx, cy }
+    }
+
+    pub fn radial(mut self, k1: f64, k2: f64, k3: f64) -> Self {
+        self.k1 = k1;
+        self.k2 = k2;
+        self.k3 = k3;
+        self
+    }
+
+    pub fn tangential(mut self, p1: f64, p2: f64) -> Self {
+        self.p1 = p1;
+        self.p2 = p2;
+        self
+    }
+
+    /// Radial scale and tangential offset at normalized coordinates
+    fn distortion_at(&self, x: f64, y: f64) -> (f64, f64, f64) {
+        let r2 = x * x + y * y;
+        let radial = 1.0 + r2 * (self.k1 + r2 * (self.k2 + r2 * self.k3));
+        let dx = 2.0 * self.p1 * x * y + self.p2 * (r2 + 2.0 * x * x);
+        let dy = self.p1 * (r2 + 2.0 * y * y) + 2.0 * self.p2 * x * y;
+        (radial, dx, dy)
+    }
+
+    /// Maps an ideal (undistorted) point to where it appears in the frame
+    pub fn distort(&self, point: PointF64) -> PointF64 {
+        let (x, y) = ((point.x - self.cx) / self.fx, (point.y - self.cy) / self.fy);
+        let (radial, dx, dy) = self.distortion_at(x, y);
+        PointF64::new(
+            (x * radial + dx) * self.fx + self.cx,
+            (y * radial + dy) * self.fy + self.cy,
+        )
+    }
+
+    /// Maps a point in the frame to its ideal (undistorted) position, by fixed-point iteration
+    pub fn undistort(&self, point: PointF64) -> PointF64 {
+        let (xd, yd) = ((point.x - self.cx) / self.fx, (point.y - self.cy) / self.fy);
+        let (mut x, mut y) = (xd, yd);
+        for _ in 0..Self::UNDISTORT_ITERATIONS {
+            let (radial, dx, dy) = self.distortion_at(x, y);
+            x = (xd - dx) / radial;
+            y = (yd - dy) / radial;
+        }
+        PointF64::new(x * self.fx + self.cx, y * self.fy + self.cy)
+    }
+
+    pub fn undistort_rect(&self, rect: &BoundingRect) -> BoundingRect {
+        Self::map_rect(rect, |p| self.undistort(p))
+    }
+
+    pub fn distort_rect(&self, rect: &BoundingRect) -> BoundingRect {
+        Self::map_rect(rect, |p| self.distort(p))
+    }
+
+    /// The bounding rect of the mapped corners and edge midpoints, which may bow under distortion
+    fn map_rect(rect: &BoundingRect, f: impl Fn(PointF64) -> PointF64) -> BoundingRect {
+        let (left, top, right, bottom) = (rect.left as f64, rect.top as f64, rect.right as f64, rect.bottom as f64);
+        let (mid_x, mid_y) = ((left + right) / 2.0, (top + bottom) / 2.0);
+        let points: Vec<PointF64> = [
+            (left, top), (mid_x, top), (right, top),
+            (left, mid_y), (right, mid_y),
+            (left, bottom), (mid_x, bottom), (right, bottom),
+        ].iter().map(|&(x, y)| f(PointF64::new(x, y))).collect();
+        BoundingRect {
+            left: points.iter().map(|p| p.x).fold(std::f64::MAX, f64::min).floor() as i32,
+            top: points.iter().map(|p| p.y).fold(std::f64::MAX, f64::min).floor() as i32,
+            right: points.iter().map(|p| p.x).fold(std::f64::MIN, f64::max).ceil() as i32,
+            bottom: points.iter().map(|p| p.y).fold(std::f64::MIN, f64::max).ceil() as i32,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bit_vec::BitVec;
+    use visioncortex::{BinaryImage, Color, ColorImage, PerspectiveTransform};
+    use crate::interfaces::{Decoder, DummyDebugger, Encoder, Finder, Reader, SymcodeGenerator};
+    use crate::acute32::{Acute32, Acute32SymcodeConfig, Acute32Tracker, Acute32TransformFitter, ReaderMode};
+    use crate::acute32::test_fixtures::use_synthetic_library;
+    use super::*;
+
+    /// The shorter the focal length, the stronger the distortion towards the corners of the frame
+    fn barrel(focal_length: f64) -> LensDistortion {
+        LensDistortion::new(focal_length, focal_length, 200.0, 200.0).radial(-0.25, 0.05, 0.0).tangential(0.002, -0.001)
+    }
+
+    #[test]
+    fn distortion_round_trip() {
+        let distortion = barrel(300.0);
+        for &(x, y) in [(0.0, 0.0), (200.0, 200.0), (37.0, 351.0), (390.0, 15.0)].iter() {
+            let p = PointF64::new(x, y);
+            assert!((distortion.undistort(distortion.distort(p)) - p).norm() < 1e-6);
+        }
+        // Barrel distortion pulls the corners towards the center
+        assert!((distortion.distort(PointF64::new(0.0, 0.0)) - PointF64::new(200.0, 200.0)).norm() < 200.0 * std::f64::consts::SQRT_2);
+
+        let rect = distortion.undistort_rect(&BoundingRect::new_x_y_w_h(10, 10, 20, 20));
+        assert!(rect.left < 10 && rect.top < 10);
+    }
+
+    /// Perspective of the camera, from the undistorted frame to the object space
+    fn camera(config: &Acute32SymcodeConfig) -> PerspectiveTransform {
+        let (w, h) = (config.params.code_width as f64, config.params.code_height as f64);
+        PerspectiveTransform::from_point_f64(
+            &[PointF64::new(40.0, 50.0), PointF64::new(370.0, 30.0), PointF64::new(360.0, 375.0), PointF64::new(25.0, 350.0)],
+            &[PointF64::new(0.0, 0.0), PointF64::new(w, 0.0), PointF64::new(w, h), PointF64::new(0.0, h)],
+        )
+    }
+
+    /// Renders the code as seen by the camera through the lens
+    fn capture(code: &BinaryImage, distortion: &LensDistortion, config: &Acute32SymcodeConfig) -> ColorImage {
+        let image_to_object = camera(config);
+        let mut frame = ColorImage::new_w_h(400, 400);
+        for y in 0..frame.height {
+            for x in 0..frame.width {
+                let p = image_to_object.transform(distortion.undistort(PointF64::new(x as f64, y as f64))).to_point_i32();
+                let color = if code.get_pixel_safe(p.x, p.y) { Color::new(0, 0, 0) } else { Color::new(255, 255, 255) };
+                frame.set_pixel(x, y, &color);
+            }
+        }
+        frame
+    }
+
+    /// The whole single image pipeline: finder, fitter, reader and decoder
+    fn scan(frame: &ColorImage, config: &Acute32SymcodeConfig) -> Result<BitVec, &'static str> {
+        let acute32 = Acute32::new(config);
+        let finder_positions = acute32.get_finder().find(frame)?;
+        let image_to_object = Acute32TransformFitter::fit_transform(frame.width, frame.height, finder_positions, config, &DummyDebugger)?;
+        let labels = acute32.get_reader().read(frame.clone(), image_to_object)?;
+        acute32.get_decoder().decode(labels)
+    }
+
+    #[test]
+    fn distortion_generated_code_still_decodes() {
+        let mut config = Acute32SymcodeConfig::default();
+        config.params.reader_mode = ReaderMode::AnchorSampling { search_radius: 0, search_step: 1 };
+        use_synthetic_library(&mut config);
+
+        let payload = BitVec::from_fn(20, |i| i % 3 == 0);
+        let acute32 = Acute32::new(&config);
+        let labels = acute32.get_encoder().encode(payload.clone(), config.params.num_glyphs_in_code()).unwrap();
+        let code = acute32.get_generator().generate(labels);
+
+        // Strong enough that the homography fitted to the distorted finders misses the glyphs at the corners
+        let distortion = barrel(250.0);
+        let frame = capture(&code, &distortion, &config);
+
+        // Without the correction, the finders are found but do not fit a homography well and the glyphs are sampled off their cells
+        assert_eq!(acute32.get_finder().find(&frame).map(|candidates| candidates.len()), Ok(4));
+        assert_ne!(scan(&frame, &config), Ok(payload.clone()));
+
+        // The finders are located in the undistorted image space, where the camera is a homography
+        config.params.lens_distortion = Some(distortion);
+        assert_eq!(scan(&frame, &config), Ok(payload.clone()));
+
+        // And searched for around their undistorted positions when tracking
+        let mut tracker = Acute32Tracker::default();
+        assert_eq!(tracker.track_and_decode(frame.clone(), &config), Ok(payload.clone()));
+        assert!(tracker.track(frame.clone(), &config).unwrap().tracked);
+        assert_eq!(tracker.track_and_decode(frame, &config), Ok(payload));
+    }
+}
@@ -23,7 +23,7 @@ impl EncoderInterface for Acute32Encoder<'_> {
             panic!("Input bits length and self-defined length do not agree!");
         }
         
-        let checksum = crate::math::into_bitvec(crczoo::crc5(&payload.to_bytes()) as usize, 5);
+        let checksum = crate::math::into_bitvec(self.config.params.payload_checksum(&payload) as usize, 5);
         
         // This payload is used to generate the code image
         let payload_with_checksum = BitVec::from_fn(
@@ -48,7 +48,7 @@ impl EncoderInterface for Acute32Encoder<'_> {
                 let index = i*symbol_num_bits + j;
                 payload_with_checksum[index]
             });
-            result.push(GlyphLabel::from_bit_vec(symbol_bit_vec));
+            result.push(self.config.params.label_bit_mapping.bits_to_label(symbol_bit_vec));
         }
 
         // Sanity check
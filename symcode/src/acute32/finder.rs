@@ -1,6 +1,6 @@
-use visioncortex::{BinaryImage, BoundingRect, ColorImage, Shape};
-use crate::{interfaces::Finder as FinderInterface, interfaces::FinderElement, interfaces::Debugger};
-use super::{Acute32SymcodeConfig, local_adaptive_threshold, valid_pointf64_on_image};
+use visioncortex::{BinaryImage, BoundingRect, ColorImage, Sampler, Shape};
+use crate::{interfaces::Finder as FinderInterface, interfaces::FinderElement, interfaces::Debugger, interfaces::DummyDebugger};
+use super::{Acute32Layout, Acute32SymcodeConfig, Polarity, fill_cluster_holes, image_diff_area, local_adaptive_threshold};
 
 /// Specific implementation of Finder symbol element
 #[derive(Default)]
@@ -30,30 +30,160 @@ impl FinderElement for CircleFinder {
     }
 }
 
+/// Side of the square the shapes are resampled to for matching against the finder image
+const FINDER_MATCH_SIZE: usize = 32;
+/// Shapes smaller than this (in pixels) cannot show the details of the finder
+const FINDER_MIN_SIZE: usize = 8;
+
+/// Whether the shape, turned by one of the steps up to a quarter turn and stretched to a square,
+/// differs from the finder image in at most `tolerance` of the ink of the finder image.
+///
+/// Stretching makes up for the finder being seen at an angle.
+fn matches_finder_image(finder: &dyn FinderElement, shape: &Shape, tolerance: f64) -> bool {
+    let image = &shape.image;
+    if image.width < FINDER_MIN_SIZE || image.height < FINDER_MIN_SIZE {
+        return false;
+    }
+    let finder_image = finder.to_image(FINDER_MATCH_SIZE, FINDER_MATCH_SIZE);
+    let max_diff = (finder_image.area() as f64 * tolerance) as u64;
+    let steps = 6;
+    (0..steps).any(|i| {
+        let angle = i as f64 * std::f64::consts::FRAC_PI_2 / (steps as f64);
+        let rotated_image = if i > 0 {
+            image.rotate(angle).crop()
+        } else {
+            image.clone()
+        };
+        if rotated_image.width == 0 || rotated_image.height == 0 {
+            return false;
+        }
+        let resampled = Sampler::resample_image(&rotated_image, FINDER_MATCH_SIZE, FINDER_MATCH_SIZE);
+        image_diff_area(&resampled, &finder_image) <= max_diff
+    })
+}
+
+/// Alternating bands of ink and gap from the edge of the ellipse inwards, the outermost band is ink
+fn concentric_bands_image(width: usize, height: usize, num_bands: usize) -> BinaryImage {
+    let mut image = BinaryImage::new_w_h(width, height);
+    let (rx, ry) = (width as f64 / 2.0, height as f64 / 2.0);
+    for y in 0..height {
+        for x in 0..width {
+            let dx = (x as f64 + 0.5 - rx) / rx;
+            let dy = (y as f64 + 0.5 - ry) / ry;
+            let r = (dx * dx + dy * dy).sqrt();
+            if r <= 1.0 {
+                let band = std::cmp::min(((1.0 - r) * num_bands as f64) as usize, num_bands - 1);
+                image.set_pixel(x, y, band.is_multiple_of(2));
+            }
+        }
+    }
+    image
+}
+
+/// Rings of equal width around an empty center
+pub struct ConcentricRingsFinder {
+    pub rings: usize,
+}
+
+impl Default for ConcentricRingsFinder {
+    fn default() -> Self {
+        Self { rings: 2 }
+    }
+}
+
+impl FinderElement for ConcentricRingsFinder {
+
+    fn to_image(&self, width: usize, height: usize) -> BinaryImage {
+        concentric_bands_image(width, height, 2 * std::cmp::max(self.rings, 1))
+    }
+
+    fn is_finder(&self, shape: Shape) -> bool {
+        matches_finder_image(self, &shape, 0.3)
+    }
+
+    fn has_nested_parts(&self) -> bool {
+        true
+    }
+}
+
+/// A ring around a solid disk
+#[derive(Default)]
+pub struct BullseyeFinder;
+
+impl FinderElement for BullseyeFinder {
+
+    fn to_image(&self, width: usize, height: usize) -> BinaryImage {
+        concentric_bands_image(width, height, 3)
+    }
+
+    fn is_finder(&self, shape: Shape) -> bool {
+        matches_finder_image(self, &shape, 0.3)
+    }
+
+    fn has_nested_parts(&self) -> bool {
+        true
+    }
+}
+
+/// A square with a square hole in the middle
+pub struct SquareHoleFinder {
+    /// Side of the hole as a fraction of the side of the square
+    pub hole_ratio: f64,
+}
+
+impl Default for SquareHoleFinder {
+    fn default() -> Self {
+        Self { hole_ratio: 0.5 }
+    }
+}
+
+impl FinderElement for SquareHoleFinder {
+
+    fn to_image(&self, width: usize, height: usize) -> BinaryImage {
+        let mut image = BinaryImage::new_w_h(width, height);
+        let margin = (1.0 - self.hole_ratio) / 2.0;
+        let hole = |v: usize, size: usize| {
+            let t = (v as f64 + 0.5) / size as f64;
+            margin <= t && t < 1.0 - margin
+        };
+        for y in 0..height {
+            for x in 0..width {
+                image.set_pixel(x, y, !(hole(x, width) && hole(y, height)));
+            }
+        }
+        image
+    }
+
+    fn is_finder(&self, shape: Shape) -> bool {
+        matches_finder_image(self, &shape, 0.25)
+    }
+
+    fn has_nested_parts(&self) -> bool {
+        true
+    }
+}
+
 /// Specific implementation of Finder candidates
 pub struct Acute32FinderCandidate<'a> {
     config: &'a Acute32SymcodeConfig,
+    debugger: &'a dyn Debugger,
 }
 
 impl<'a> Acute32FinderCandidate<'a> {
 
     pub fn new(config: &'a Acute32SymcodeConfig) -> Acute32FinderCandidate<'a> {
-        Self { config }
+        Self { config, debugger: &DummyDebugger }
     }
 
-    pub fn valid_config(config: &Acute32SymcodeConfig) -> Result<(), &'static str> {
-        if config.finder_positions.len() < 4 {
-            return Err("Number of finder candidates specified in FinderCandidates' config is less than 4.");
-        }
-
-        // Each finder position cannot be out of boundary of the code
-        for &finder in config.finder_positions.iter() {
-            if !valid_pointf64_on_image(finder, config.code_width, config.code_height) {
-                return Err("A finder is out of the boundary in the object space.");
-            }
-        }
+    /// Renders the binarized frame and the finder candidates
+    pub fn debugger(mut self, debugger: &'a dyn Debugger) -> Self {
+        self.debugger = debugger;
+        self
+    }
 
-        Ok(())
+    /// The layout of the finders and glyphs has to be valid, see `Acute32Layout::validate`
+    pub fn valid_config(config: &Acute32SymcodeConfig) -> Result<(), &'static str> {
+        Acute32Layout::from_params(&config.params).validate()
     }
 
     fn render_finder_candidates(debugger: &dyn Debugger, finder_candidates: &[BoundingRect]) {
@@ -62,12 +192,32 @@ impl<'a> Acute32FinderCandidate<'a> {
         });
     }
 
-    fn extract_finder_positions(image: BinaryImage, finder: &CircleFinder) -> Vec<BoundingRect> {
+    /// Binarizes the frame for the polarity and extracts the finders
+    fn find_with_polarity(&self, input: &ColorImage, polarity: Polarity) -> (BinaryImage, Vec<BoundingRect>) {
+        let binary_raw_frame = local_adaptive_threshold(
+            input, std::cmp::min(input.width, input.height) / 4, 0.2, polarity
+        );
+        let finder_candidates = Acute32FinderCandidate::extract_finder_positions(&binary_raw_frame, self.config.finder.as_ref());
+        (binary_raw_frame, finder_candidates)
+    }
+
+    /// Whether the number of candidates can be fitted
+    fn plausible_candidates(&self, num_candidates: usize) -> bool {
+        self.config.params.finder_positions.len() <= num_candidates && num_candidates <= self.config.params.max_finder_candidates()
+    }
+
+    /// For finders made of nested parts, each cluster is tested together with what is inside its holes
+    pub(crate) fn extract_finder_positions(image: &BinaryImage, finder: &dyn FinderElement) -> Vec<BoundingRect> {
         let clusters = image.to_clusters(false);
         
         clusters.clusters.iter()
             .filter_map(|cluster| {
-                if finder.is_finder(Shape::from(cluster.to_binary_image())) {
+                let shape_image = if finder.has_nested_parts() {
+                    fill_cluster_holes(&cluster.to_binary_image(), &image.crop_with_rect(cluster.rect))
+                } else {
+                    cluster.to_binary_image()
+                };
+                if finder.is_finder(Shape::from(shape_image)) {
                     Some(cluster.rect)
                 } else {
                     None
@@ -82,22 +232,125 @@ impl<'a> FinderInterface for Acute32FinderCandidate<'a> {
         let config = self.config;
         Acute32FinderCandidate::valid_config(config)?;
 
-        // Get the reference to the input raw frame
-        let raw_frame = input;
-        // Binarize
-        let binary_raw_frame = local_adaptive_threshold(
-            raw_frame, std::cmp::min(input.width, input.height) / 4, 0.2
-        );
-        config.debugger.render_binary_image_to_canvas(&binary_raw_frame)?;
+        // Binarize and extract, trying both polarities if it is not configured
+        let (binary_raw_frame, mut finder_candidates) = match config.params.polarity {
+            Some(polarity) => self.find_with_polarity(input, polarity),
+            None => {
+                let dark_on_light = self.find_with_polarity(input, Polarity::DarkOnLight);
+                let light_on_dark = self.find_with_polarity(input, Polarity::LightOnDark);
+                // Prefer the polarity that can be fitted, with fewer spurious candidates
+                let dark_plausible = self.plausible_candidates(dark_on_light.1.len());
+                let light_plausible = self.plausible_candidates(light_on_dark.1.len());
+                if light_plausible && (!dark_plausible || light_on_dark.1.len() < dark_on_light.1.len()) {
+                    light_on_dark
+                } else {
+                    dark_on_light
+                }
+            },
+        };
+        self.debugger.render_binary_image_to_canvas(&binary_raw_frame)?;
+
+        Acute32FinderCandidate::render_finder_candidates(self.debugger, &finder_candidates);
 
-        // Processing starts
-        let finder_candidates = Acute32FinderCandidate::extract_finder_positions(binary_raw_frame, &config.finder);
-        Acute32FinderCandidate::render_finder_candidates(config.debugger.as_ref(), &finder_candidates);
+        // The fitter assumes a pure homography, so the positions are reported in the undistorted image space
+        if let Some(lens_distortion) = &config.params.lens_distortion {
+            finder_candidates.iter_mut().for_each(|rect| *rect = lens_distortion.undistort_rect(rect));
+        }
 
-        if finder_candidates.len() > config.max_finder_candidates() {
+        if finder_candidates.len() > config.params.max_finder_candidates() {
             Err("Too many finder candidates!")
         } else {
             Ok(finder_candidates)
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The image stretched as if seen at an angle
+    fn stretched(finder: &dyn FinderElement) -> Shape {
+        Shape::from(finder.to_image(60, 40))
+    }
+
+    #[test]
+    fn finder_elements_match_their_own_shape_only() {
+        let rings = ConcentricRingsFinder::default();
+        let bullseye = BullseyeFinder;
+        let square = SquareHoleFinder::default();
+        assert!(rings.is_finder(stretched(&rings)));
+        assert!(bullseye.is_finder(stretched(&bullseye)));
+        assert!(square.is_finder(stretched(&square)));
+
+        assert!(!rings.is_finder(stretched(&bullseye)));
+        assert!(!bullseye.is_finder(stretched(&rings)));
+        assert!(!square.is_finder(stretched(&rings)));
+        assert!(!rings.is_finder(stretched(&square)));
+        // A round logo
+        assert!(!rings.is_finder(Shape::circle(50, 50)));
+        assert!(!bullseye.is_finder(Shape::circle(50, 50)));
+        // Too small to tell
+        assert!(!square.is_finder(Shape::from(square.to_image(6, 6))));
+    }
+
+    #[test]
+    fn finder_cluster_with_holes_filled() {
+        // A ring with a dot inside, and a pixel of another cluster in the corner
+        let frame = BinaryImage::from_string(
+          &("*----\n".to_owned() +
+            "-***-\n" +
+            "-*-*-\n" +
+            "-***-\n" +
+            "-----")
+        );
+        let ring = BinaryImage::from_string(
+          &("-----\n".to_owned() +
+            "-***-\n" +
+            "-*-*-\n" +
+            "-***-\n" +
+            "-----")
+        );
+        let mut frame_with_dot = frame.clone();
+        frame_with_dot.set_pixel(2, 2, true);
+        let filled = fill_cluster_holes(&ring, &frame_with_dot);
+        assert!(filled.get_pixel(2, 2));
+        assert!(!filled.get_pixel(0, 0));
+        assert_eq!(filled.area(), 9);
+        assert_eq!(fill_cluster_holes(&ring, &frame).area(), 8);
+    }
+
+    #[test]
+    fn finder_holes_filled_for_nested_finders_only() {
+        let mut frame = BinaryImage::new_w_h(60, 60);
+        frame.paste_from(&BullseyeFinder.to_image(40, 40), visioncortex::PointI32::new(10, 10));
+
+        // The ring is found with the disk inside it
+        let rects = Acute32FinderCandidate::extract_finder_positions(&frame, &BullseyeFinder);
+        assert_eq!(rects.len(), 1);
+        assert_eq!((rects[0].width(), rects[0].height()), (40, 40));
+
+        // A disk with its center cut off by a thin gap, which would be a circle with the hole filled.
+        // The circle finder tests each cluster on its own, so only the center is a circle
+        let mut frame = BinaryImage::new_w_h(60, 60);
+        for y in 0..60 {
+            for x in 0..60 {
+                let r = ((x as f64 - 29.5).powi(2) + (y as f64 - 29.5).powi(2)).sqrt();
+                frame.set_pixel(x, y, r <= 20.0 && !(10.0 < r && r <= 11.5));
+            }
+        }
+        let rects = Acute32FinderCandidate::extract_finder_positions(&frame, &CircleFinder);
+        assert_eq!(rects.len(), 1);
+        assert!(rects[0].width() < 25);
+    }
+
+    #[test]
+    fn finder_concentric_bands() {
+        let image = ConcentricRingsFinder { rings: 2 }.to_image(40, 40);
+        // Outer ring, gap, inner ring, empty center
+        assert!(image.get_pixel(20, 1));
+        assert!(!image.get_pixel(20, 7));
+        assert!(image.get_pixel(20, 12));
+        assert!(!image.get_pixel(20, 20));
+        assert!(BullseyeFinder.to_image(40, 40).get_pixel(20, 20));
+    }
+}
@@ -1,23 +1,30 @@
 use permutator::{Combination, Permutation};
 use visioncortex::{BoundingRect, PointF64, PerspectiveTransform};
 use crate::math::{clockwise_points_f64, euclid_dist_f64, normalize_point_f64};
-use crate::interfaces::Fitter;
+use crate::interfaces::{Debugger, DummyDebugger, Fitter};
 use super::{Acute32SymcodeConfig, valid_pointf64_on_image};
 
 pub struct Acute32TransformFitter<'a> {
     config: &'a Acute32SymcodeConfig,
+    debugger: &'a dyn Debugger,
 }
 
 impl<'a> Acute32TransformFitter<'a> {
 
     pub fn new(config: &'a Acute32SymcodeConfig) -> Acute32TransformFitter<'a> {
-        Self { config }
+        Self { config, debugger: &DummyDebugger }
+    }
+
+    /// Renders the finders of the best transform
+    pub fn debugger(mut self, debugger: &'a dyn Debugger) -> Self {
+        self.debugger = debugger;
+        self
     }
 
     /// Use the top of each finder in object space as check points
     fn calculate_check_points(symcode_config: &crate::acute32::Acute32SymcodeConfig) -> Vec<PointF64> {
-        symcode_config.finder_positions.iter()
-            .map(|p| PointF64::new(p.x, p.y - (symcode_config.symbol_height >> 1) as f64))
+        symcode_config.params.finder_positions.iter()
+            .map(|p| PointF64::new(p.x, p.y - (symcode_config.params.symbol_height >> 1) as f64))
             .collect()
     }
 
@@ -26,7 +33,7 @@ impl<'a> Acute32TransformFitter<'a> {
     /// not fitted in a transform.
     ///
     /// Note that perspective distortion has to be taken into account.
-    fn correct_spatial_arrangement(finder_positions_image: &[PointF64]) -> bool {
+    pub(crate) fn correct_spatial_arrangement(finder_positions_image: &[PointF64]) -> bool {
         clockwise_points_f64(&finder_positions_image[0], &finder_positions_image[1], &finder_positions_image[2]) &&
         clockwise_points_f64(&finder_positions_image[0], &finder_positions_image[3], &finder_positions_image[1]) &&
         clockwise_points_f64(&finder_positions_image[2], &finder_positions_image[1], &finder_positions_image[3])
@@ -50,7 +57,7 @@ impl<'a> Acute32TransformFitter<'a> {
         let center_finder_bot_right = PointF64::new(finders_image[1].right.into(), finders_image[1].bottom.into());
         for &point in &[center_finder_top_left, center_finder_top_right, center_finder_bot_left, center_finder_bot_right] {
             let transformed_point = img_to_obj.transform(point);
-            if !valid_pointf64_on_image(transformed_point, symcode_config.code_width, symcode_config.code_height) {
+            if !valid_pointf64_on_image(transformed_point, symcode_config.params.code_width, symcode_config.params.code_height) {
                 return std::f64::MAX;
             }
         }
@@ -95,9 +102,9 @@ impl<'a> Acute32TransformFitter<'a> {
     ///
     /// Those are points that cannot be sampled.
     fn transform_to_image_out_of_bound(image_width: usize, image_height: usize, image_to_object: &PerspectiveTransform, symcode_config: &Acute32SymcodeConfig) -> bool {
-        let pad = symcode_config.glyph_anchors[0];
-        let w = symcode_config.code_width as f64;
-        let h = symcode_config.code_height as f64;
+        let pad = symcode_config.params.glyph_anchors[0];
+        let w = symcode_config.params.code_width as f64;
+        let h = symcode_config.params.code_height as f64;
         let points_to_test = [
             PointF64::new(pad.x, pad.y), PointF64::new(w - pad.x, pad.y),
             PointF64::new(pad.x, h - pad.y), PointF64::new(w - pad.x, h - pad.y),
@@ -121,8 +128,14 @@ impl<'a> Acute32TransformFitter<'a> {
     /// find the "correct" perspective transform that maps the image space to the object space.
     ///
     /// symcode_config is used to evaluate the potential transforms.
-    fn fit_transform(image_width: usize, image_height: usize, finder_positions_image: Vec<BoundingRect>, symcode_config: &Acute32SymcodeConfig) -> Result<PerspectiveTransform, &str> {
-        let dst_pts = &symcode_config.finder_positions;
+    pub(crate) fn fit_transform(image_width: usize, image_height: usize, finder_positions_image: Vec<BoundingRect>, symcode_config: &Acute32SymcodeConfig, debugger: &dyn Debugger) -> Result<PerspectiveTransform, &'static str> {
+        Self::fit_transform_with_error(image_width, image_height, finder_positions_image, symcode_config, debugger)
+            .map(|(transform, _)| transform)
+    }
+
+    /// Same as fit_transform, also returning the error of the best transform
+    pub(crate) fn fit_transform_with_error(image_width: usize, image_height: usize, finder_positions_image: Vec<BoundingRect>, symcode_config: &Acute32SymcodeConfig, debugger: &dyn Debugger) -> Result<(PerspectiveTransform, f64), &'static str> {
+        let dst_pts = &symcode_config.params.finder_positions;
         let num_finders = dst_pts.len();
 
         if finder_positions_image.len() < num_finders {
@@ -147,12 +160,12 @@ impl<'a> Acute32TransformFitter<'a> {
             });
         });
         debug_min_err_src_pts.into_iter().enumerate().for_each(|(i, point)| {
-            symcode_config.debugger.render_point_i32_to_canvas_with_size_color(
+            debugger.render_point_i32_to_canvas_with_size_color(
                 point.to_point_i32(),
                 4+i,
                 visioncortex::Color::new(0, 255, 0));
         });
-        if min_error > symcode_config.rectify_error_threshold {
+        if min_error > symcode_config.params.rectify_error_threshold {
            return Err("Minimum transform error is larger than rectify error threshold");
         }
         // Check if a "best" transform was found
@@ -161,9 +174,40 @@ impl<'a> Acute32TransformFitter<'a> {
         if Self::transform_to_image_out_of_bound(image_width, image_height, &best_transform, symcode_config) {
             Err("Transform to image out of bound.")
         } else {
-            Ok(best_transform)
+            Ok((best_transform, min_error))
+        }
+
+    }
+
+    /// Fit the transform with finders already in the order of `finder_positions`, e.g. tracked
+    /// from the previous frame, and verify it the same way as the search over all arrangements.
+    pub(crate) fn fit_ordered(image_width: usize, image_height: usize, finders_image: &[BoundingRect], symcode_config: &Acute32SymcodeConfig) -> Result<PerspectiveTransform, &'static str> {
+        if finders_image.len() != symcode_config.params.finder_positions.len() {
+            return Err("Fitter error: Number of finders does not agree with the config.");
+        }
+        let src_pts: Vec<PointF64> = finders_image.iter().map(|rect| rect.center().to_point_f64()).collect();
+        if !Self::correct_spatial_arrangement(&src_pts) {
+            return Err("Fitter error: Spatial arrangement of the finders is not correct.");
+        }
+        let transform = PerspectiveTransform::from_point_f64(&src_pts, &symcode_config.params.finder_positions);
+        let error = Self::evaluate_transform(&transform, finders_image.iter().collect(), image_width, image_height, symcode_config);
+        if error > symcode_config.params.rectify_error_threshold {
+            return Err("Transform error is larger than rectify error threshold");
+        }
+        if Self::transform_to_image_out_of_bound(image_width, image_height, &transform, symcode_config) {
+            Err("Transform to image out of bound.")
+        } else {
+            Ok(transform)
         }
+    }
+}
 
+impl Acute32TransformFitter<'_> {
+    /// Fit the transform and return it with its error, i.e. how far the finders are from the ideal arrangement
+    pub fn fit_with_error(
+        &self, finder_positions_image: Vec<BoundingRect>, raw_image_width: usize, raw_image_height: usize
+    ) -> Result<(PerspectiveTransform, f64), &'static str> {
+        Self::fit_transform_with_error(raw_image_width, raw_image_height, finder_positions_image, self.config, self.debugger)
     }
 }
 
@@ -171,6 +215,6 @@ impl Fitter for Acute32TransformFitter<'_> {
     fn fit(
         &self, finder_positions_image: Vec<BoundingRect>, raw_image_width: usize, raw_image_height: usize
     ) -> Result<PerspectiveTransform, &str> {
-        Self::fit_transform(raw_image_width, raw_image_height, finder_positions_image, &self.config)
+        Self::fit_transform(raw_image_width, raw_image_height, finder_positions_image, self.config, self.debugger)
     }
 }
\ No newline at end of file
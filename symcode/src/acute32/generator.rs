@@ -0,0 +1,97 @@
+use visioncortex::{BinaryImage, PointI32};
+use crate::interfaces::SymcodeGenerator;
+use super::{Acute32SymcodeConfig, GlyphLabel, Polarity, invert_binary_image};
+
+/// Renders the finders and glyphs of a code into an image of size code_width x code_height.
+///
+/// Set pixels are to be printed dark, so the code is inverted if the configured polarity is LightOnDark.
+pub struct Acute32Generator<'a> {
+    config: &'a Acute32SymcodeConfig,
+}
+
+impl<'a> Acute32Generator<'a> {
+    pub fn new(config: &'a Acute32SymcodeConfig) -> Acute32Generator<'a> {
+        Self { config }
+    }
+
+    /// Renders the code with the given polarity, whatever is configured
+    pub fn generate_with_polarity(&self, symcode: Vec<GlyphLabel>, polarity: Polarity) -> BinaryImage {
+        let symcode_image = self.generate_ink(symcode);
+        match polarity {
+            Polarity::DarkOnLight => symcode_image,
+            Polarity::LightOnDark => invert_binary_image(&symcode_image),
+        }
+    }
+
+    /// The finders and glyphs as set pixels
+    fn generate_ink(&self, symcode: Vec<GlyphLabel>) -> BinaryImage {
+        let config = self.config;
+        let mut symcode_image = BinaryImage::new_w_h(config.params.code_width, config.params.code_height);
+
+        // Put in the finders
+        let finder_image = config.finder.to_image(config.params.symbol_width, config.params.symbol_height);
+        config.params.finder_positions.iter().for_each(|finder_center| {
+            let top_left = finder_center.to_point_i32() - PointI32::new((config.params.symbol_width >> 1) as i32, (config.params.symbol_height >> 1) as i32);
+            symcode_image.paste_from(&finder_image, top_left);
+        });
+
+        // Put in the glyphs
+        symcode.iter().enumerate().for_each(|(i, &glyph_label)| {
+            if glyph_label != GlyphLabel::Invalid {
+                let glyph_top_left = config.params.glyph_anchors[i];
+                if let Some(glyph) = config.symbol_library.get_glyph_with_label(glyph_label) {
+                    symcode_image.paste_from(&glyph.image, glyph_top_left.to_point_i32());
+                }
+            }
+        });
+
+        symcode_image
+    }
+}
+
+impl SymcodeGenerator for Acute32Generator<'_> {
+    type SymcodeRepresentation = Vec<GlyphLabel>;
+
+    fn generate(&self, symcode: Self::SymcodeRepresentation) -> BinaryImage {
+        self.generate_with_polarity(symcode, self.config.params.polarity.unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::acute32::{Acute32Recognizer, ReaderMode};
+    use crate::acute32::test_fixtures::{identity, render, use_half_glyph_library};
+    use crate::interfaces::DummyDebugger;
+    use super::*;
+
+    #[test]
+    fn generator_inverted_code_reads_with_detected_polarity() {
+        let mut config = Acute32SymcodeConfig::default();
+        config.params.reader_mode = ReaderMode::AnchorSampling { search_radius: 0, search_step: 1 };
+        config.params.polarity = Some(Polarity::LightOnDark);
+        let (left, right) = use_half_glyph_library(&mut config);
+        let labels = vec![left, right, left, right, right];
+
+        let code = Acute32Generator::new(&config).generate(labels.clone());
+        // The background is set, and the finders are holes in it
+        assert!(code.get_pixel(0, 0));
+        let center = config.params.finder_positions[0].to_point_i32();
+        assert!(!code.get_pixel(center.x as usize, center.y as usize));
+        assert_eq!(code.get_pixel(1, 1), !Acute32Generator::new(&config).generate_with_polarity(labels.clone(), Polarity::DarkOnLight).get_pixel(1, 1));
+
+        let frame = render(&code);
+
+        config.params.polarity = None;
+        assert_eq!(Acute32Recognizer::detect_polarity(&frame, &identity(&config), &config), Polarity::LightOnDark);
+        let output = Acute32Recognizer::read_glyphs_with_details_from_raw_frame(frame.clone(), identity(&config), &config.symbol_library, &config, &DummyDebugger);
+        assert_eq!(output.polarity, Polarity::LightOnDark);
+        assert_eq!(output.labels(), labels);
+        assert!(output.quiet_zone.unwrap().clear);
+
+        // Read as dark on light, the glyphs are the inverse of each other
+        config.params.polarity = Some(Polarity::DarkOnLight);
+        let output = Acute32Recognizer::read_glyphs_with_details_from_raw_frame(frame, identity(&config), &config.symbol_library, &config, &DummyDebugger);
+        assert_ne!(output.labels(), labels);
+        assert!(!output.quiet_zone.unwrap().clear);
+    }
+}
@@ -0,0 +1,256 @@
+use visioncortex::{BoundingRect, ColorImage, PerspectiveTransform};
+use crate::interfaces::{Decoder, DummyDebugger, Finder};
+use super::{Acute32Decoder, Acute32FinderCandidate, Acute32Recognizer, Acute32SymcodeConfig, Acute32TransformFitter, GlyphLabel, RectifiedImage};
+
+/// Print quality grade, A is the best
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum QualityGrade {
+    A,
+    B,
+    C,
+    D,
+    F,
+}
+
+impl QualityGrade {
+    /// thresholds are the minimum values for A, B, C and D
+    fn from_higher_is_better(value: f64, thresholds: [f64; 4]) -> Self {
+        Self::from_rank(thresholds.iter().position(|&threshold| value >= threshold))
+    }
+
+    /// thresholds are the maximum values for A, B, C and D
+    fn from_lower_is_better(value: f64, thresholds: [f64; 4]) -> Self {
+        Self::from_rank(thresholds.iter().position(|&threshold| value <= threshold))
+    }
+
+    fn from_rank(rank: Option<usize>) -> Self {
+        match rank {
+            Some(0) => Self::A,
+            Some(1) => Self::B,
+            Some(2) => Self::C,
+            Some(3) => Self::D,
+            _ => Self::F,
+        }
+    }
+}
+
+/// Grade of each metric in a `QualityReport`
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QualityGrades {
+    pub contrast: QualityGrade,
+    pub modulation: QualityGrade,
+    pub fit: QualityGrade,
+    pub glyph_margin: QualityGrade,
+    pub quiet_zone: QualityGrade,
+    pub decode: QualityGrade,
+}
+
+impl QualityGrades {
+    /// The overall grade is the lowest of all metrics
+    pub fn overall(&self) -> QualityGrade {
+        *[self.contrast, self.modulation, self.fit, self.glyph_margin, self.quiet_zone, self.decode].iter().max().unwrap()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct QualityReport {
+    /// Difference between the light and dark levels (90th and 10th percentiles) of the glyph regions, in [0, 1]
+    pub symbol_contrast: f64,
+    /// The lowest, over the glyphs, mean distance of pixels from the threshold relative to half the contrast
+    pub modulation: f64,
+    /// Error of the transform fitted on the finders, as evaluated by the fitter
+    pub fit_error: f64,
+    /// For each glyph, the difference between the pixel errors of the runner-up and best templates
+    /// as a fraction of the symbol area, 0 if the glyph is not recognized
+    pub glyph_margins: Vec<f64>,
+    /// Fraction of the quiet zone which is dark or out of the frame, 1 if it cannot be measured
+    pub quiet_zone_violation: f64,
+    pub decoded: bool,
+    /// The smallest glyph margin if the code decodes, 0 otherwise
+    pub decode_margin: f64,
+    pub grades: QualityGrades,
+    pub grade: QualityGrade,
+}
+
+/// Grades the print quality of a scanned code, modeled on ISO/IEC 15415
+pub struct Acute32Grader<'a> {
+    config: &'a Acute32SymcodeConfig,
+}
+
+impl<'a> Acute32Grader<'a> {
+    const CONTRAST_THRESHOLDS: [f64; 4] = [0.7, 0.55, 0.4, 0.2];
+    const MODULATION_THRESHOLDS: [f64; 4] = [0.7, 0.6, 0.5, 0.4];
+    /// Fractions of rectify_error_threshold
+    const FIT_ERROR_THRESHOLDS: [f64; 4] = [0.1, 0.2, 0.4, 1.0];
+    const GLYPH_MARGIN_THRESHOLDS: [f64; 4] = [0.1, 0.07, 0.05, 0.02];
+    const QUIET_ZONE_THRESHOLDS: [f64; 4] = [0.01, 0.02, 0.05, 0.1];
+
+    pub fn new(config: &'a Acute32SymcodeConfig) -> Acute32Grader<'a> {
+        Self { config }
+    }
+
+    /// Locates the code in the frame and grades it
+    pub fn grade(&self, frame: ColorImage) -> Result<QualityReport, &'static str> {
+        let finder_candidates = Acute32FinderCandidate::new(self.config).find(&frame)?;
+        let (image_to_object, fit_error) = Acute32TransformFitter::new(self.config)
+            .fit_with_error(finder_candidates, frame.width, frame.height)?;
+        Ok(self.grade_with_transform(frame, image_to_object, fit_error))
+    }
+
+    /// Grades the code with a known transform
+    pub fn grade_with_transform(&self, frame: ColorImage, image_to_object: PerspectiveTransform, fit_error: f64) -> QualityReport {
+        let config = self.config;
+        let rectified = Acute32Recognizer::rectify_image_grayscale(&frame, &image_to_object, config);
+        let dark = rectified.percentile(10).unwrap_or(0) as f64;
+        let light = rectified.percentile(90).unwrap_or(0) as f64;
+        let threshold = (dark + light) / 2.0;
+        let symbol_contrast = (light - dark) / 255.0;
+
+        let polarity = Acute32Recognizer::polarity_of(&frame, &image_to_object, config);
+        let quiet_zone_violation = Acute32Recognizer::measure_quiet_zone(&frame, &image_to_object, threshold, polarity, config).unwrap_or(1.0);
+
+        let output = Acute32Recognizer::read_glyphs_with_details_from_raw_frame(frame, image_to_object, &config.symbol_library, config, &DummyDebugger);
+
+        let modulation = config.params.glyph_anchors.iter().zip(output.glyphs.iter())
+            .map(|(anchor, glyph)| {
+                let rect = glyph.rect.unwrap_or_else(|| BoundingRect::new_x_y_w_h(
+                    anchor.x as i32, anchor.y as i32, config.params.symbol_width as i32, config.params.symbol_height as i32
+                ));
+                Self::glyph_modulation(&rectified, &rect, threshold, light - dark)
+            })
+            .fold(std::f64::MAX, f64::min);
+        let modulation = if modulation == std::f64::MAX { 0.0 } else { modulation };
+
+        let glyph_margins: Vec<f64> = output.glyphs.iter()
+            .map(|glyph| match (&glyph.image, glyph.prediction.label) {
+                (_, GlyphLabel::Invalid) | (None, _) => 0.0,
+                (Some(image), _) => config.symbol_library.match_margin(image, config).unwrap_or(0.0),
+            })
+            .collect();
+        let min_glyph_margin = glyph_margins.iter().cloned().fold(std::f64::MAX, f64::min);
+        let min_glyph_margin = if min_glyph_margin == std::f64::MAX { 0.0 } else { min_glyph_margin };
+
+        let decoded = Acute32Decoder::new(config).decode(output.labels()).is_ok();
+        let decode_margin = if decoded { min_glyph_margin } else { 0.0 };
+
+        let mut fit_thresholds = Self::FIT_ERROR_THRESHOLDS;
+        fit_thresholds.iter_mut().for_each(|threshold| *threshold *= config.params.rectify_error_threshold);
+        let grades = QualityGrades {
+            contrast: QualityGrade::from_higher_is_better(symbol_contrast, Self::CONTRAST_THRESHOLDS),
+            modulation: QualityGrade::from_higher_is_better(modulation, Self::MODULATION_THRESHOLDS),
+            fit: QualityGrade::from_lower_is_better(fit_error, fit_thresholds),
+            glyph_margin: QualityGrade::from_higher_is_better(min_glyph_margin, Self::GLYPH_MARGIN_THRESHOLDS),
+            quiet_zone: QualityGrade::from_lower_is_better(quiet_zone_violation, Self::QUIET_ZONE_THRESHOLDS),
+            decode: if decoded { QualityGrade::A } else { QualityGrade::F },
+        };
+
+        QualityReport {
+            symbol_contrast,
+            modulation,
+            fit_error,
+            glyph_margins,
+            quiet_zone_violation,
+            decoded,
+            decode_margin,
+            grade: grades.overall(),
+            grades,
+        }
+    }
+
+    /// Mean distance of the known pixels in rect from the threshold, relative to half the contrast
+    fn glyph_modulation(rectified: &RectifiedImage, rect: &BoundingRect, threshold: f64, contrast: f64) -> f64 {
+        if contrast <= 0.0 {
+            return 0.0;
+        }
+        let mut sum = 0.0;
+        let mut count = 0;
+        for y in rect.top.max(0)..rect.bottom.min(rectified.height as i32) {
+            for x in rect.left.max(0)..rect.right.min(rectified.width as i32) {
+                if let Some(value) = rectified.get_pixel(x as usize, y as usize) {
+                    sum += (value as f64 - threshold).abs();
+                    count += 1;
+                }
+            }
+        }
+        if count == 0 {
+            return 0.0;
+        }
+        (sum / count as f64 / (contrast / 2.0)).min(1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bit_vec::BitVec;
+    use visioncortex::BinaryImage;
+    use crate::interfaces::{Encoder, SymcodeGenerator};
+    use crate::acute32::{Acute32Encoder, Acute32Generator, ReaderMode};
+    use crate::acute32::test_fixtures::{half_glyph, identity, render, use_half_glyph_library, use_synthetic_library};
+    use super::*;
+
+    #[test]
+    fn grading_grade_from_value() {
+        assert_eq!(QualityGrade::from_higher_is_better(0.8, Acute32Grader::CONTRAST_THRESHOLDS), QualityGrade::A);
+        assert_eq!(QualityGrade::from_higher_is_better(0.4, Acute32Grader::CONTRAST_THRESHOLDS), QualityGrade::C);
+        assert_eq!(QualityGrade::from_higher_is_better(0.1, Acute32Grader::CONTRAST_THRESHOLDS), QualityGrade::F);
+        assert_eq!(QualityGrade::from_lower_is_better(0.0, Acute32Grader::QUIET_ZONE_THRESHOLDS), QualityGrade::A);
+        assert_eq!(QualityGrade::from_lower_is_better(0.03, Acute32Grader::QUIET_ZONE_THRESHOLDS), QualityGrade::C);
+        assert_eq!(QualityGrade::from_lower_is_better(0.5, Acute32Grader::QUIET_ZONE_THRESHOLDS), QualityGrade::F);
+
+        let grades = QualityGrades {
+            contrast: QualityGrade::A, modulation: QualityGrade::B, fit: QualityGrade::A,
+            glyph_margin: QualityGrade::D, quiet_zone: QualityGrade::A, decode: QualityGrade::A,
+        };
+        assert_eq!(grades.overall(), QualityGrade::D);
+    }
+
+    #[test]
+    fn grading_synthetic_code() {
+        let mut config = Acute32SymcodeConfig::default();
+        config.params.reader_mode = ReaderMode::AnchorSampling { search_radius: 0, search_step: 1 };
+        use_half_glyph_library(&mut config);
+
+        let mut code = BinaryImage::new_w_h(config.params.code_width, config.params.code_height);
+        for anchor in config.params.glyph_anchors.iter() {
+            code.paste_from(&half_glyph(&config, true), anchor.to_point_i32());
+        }
+
+        let grader = Acute32Grader::new(&config);
+        let report = grader.grade_with_transform(render(&code), identity(&config), 0.0);
+        assert!((report.symbol_contrast - 210.0 / 255.0).abs() < 1e-9);
+        assert_eq!(report.grades.contrast, QualityGrade::A);
+        assert_eq!(report.grades.modulation, QualityGrade::A);
+        assert_eq!(report.grades.quiet_zone, QualityGrade::A);
+        assert!(report.glyph_margins.iter().all(|margin| *margin > 0.9));
+
+        // Ink in the quiet zone
+        for y in 0..config.params.quiet_zone_width {
+            for x in 0..config.params.code_width / 8 {
+                code.set_pixel(x, y, true);
+            }
+        }
+        let report = grader.grade_with_transform(render(&code), identity(&config), 0.0);
+        assert_eq!(report.grades.quiet_zone, QualityGrade::C);
+    }
+
+    #[test]
+    fn grading_decode_grade() {
+        let mut config = Acute32SymcodeConfig::default();
+        config.params.reader_mode = ReaderMode::AnchorSampling { search_radius: 0, search_step: 1 };
+        use_synthetic_library(&mut config);
+        let mut labels = Acute32Encoder::new(&config).encode(BitVec::from_fn(20, |i| i % 3 == 0), config.params.num_glyphs_in_code()).unwrap();
+
+        let grader = Acute32Grader::new(&config);
+        let report = grader.grade_with_transform(render(&Acute32Generator::new(&config).generate(labels.clone())), identity(&config), 0.0);
+        assert!(report.decoded);
+        assert_eq!(report.grades.decode, QualityGrade::A);
+
+        // Another glyph in place of one of a validly encoded code, its bits no longer agree with the checksum
+        let primitive = GlyphLabel::self_to_primitive(labels[2]).unwrap();
+        labels[2] = GlyphLabel::from_usize_representation((primitive + 1) % GlyphLabel::num_variants());
+        let report = grader.grade_with_transform(render(&Acute32Generator::new(&config).generate(labels)), identity(&config), 0.0);
+        assert!(!report.decoded);
+        assert_eq!(report.grades.decode, QualityGrade::F);
+        assert_eq!(report.grade, QualityGrade::F);
+    }
+}
@@ -0,0 +1,199 @@
+use bit_vec::BitVec;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::math::{into_bitvec, num_bits_to_store};
+
+use super::GlyphLabel;
+
+/// Which bits each glyph label carries in the code.
+///
+/// By default a label carries the bits of its index in `GlyphLabel`, but then two glyphs which look alike
+/// may differ in many bits. A mapping derived from how often the glyphs are confused
+/// (see `from_confusion`) keeps the likely confusions to a single wrong bit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LabelBitMapping {
+    /// The bits of each label, indexed by the primitive of the label. A permutation of 0..num_variants
+    codes: Vec<usize>,
+}
+
+impl Default for LabelBitMapping {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl LabelBitMapping {
+    /// Each label carries the bits of its own index
+    pub fn identity() -> Self {
+        Self { codes: (0..GlyphLabel::num_variants()).collect() }
+    }
+
+    /// codes[i] is the bits of the label of primitive i
+    pub fn new(codes: Vec<usize>) -> Result<Self, &'static str> {
+        let mapping = Self { codes };
+        mapping.validate()?;
+        Ok(mapping)
+    }
+
+    pub fn validate(&self) -> Result<(), &'static str> {
+        let num_variants = GlyphLabel::num_variants();
+        if self.codes.len() != num_variants {
+            return Err("LabelBitMapping error: There must be one code for each label.");
+        }
+        let mut used = vec![false; num_variants];
+        for &code in self.codes.iter() {
+            if code >= num_variants || used[code] {
+                return Err("LabelBitMapping error: The codes must be distinct and less than the number of labels.");
+            }
+            used[code] = true;
+        }
+        Ok(())
+    }
+
+    pub fn codes(&self) -> &[usize] {
+        &self.codes
+    }
+
+    /// Number of bits carried by a label
+    pub fn num_bits(&self) -> usize {
+        num_bits_to_store(self.codes.len())
+    }
+
+    /// None for an invalid label
+    pub fn label_to_bits(&self, label: GlyphLabel) -> Option<BitVec> {
+        GlyphLabel::self_to_primitive(label)
+            .and_then(|primitive| self.codes.get(primitive))
+            .map(|&code| into_bitvec(code, self.num_bits()))
+    }
+
+    /// Invalid if no label carries the bits
+    pub fn bits_to_label(&self, bits: BitVec) -> GlyphLabel {
+        let code = GlyphLabel::bit_vec_to_primitive(bits);
+        match self.codes.iter().position(|&c| c == code) {
+            Some(primitive) => GlyphLabel::from_usize_representation(primitive),
+            None => GlyphLabel::Invalid,
+        }
+    }
+
+    /// The expected number of wrong bits, each pair of labels weighted by how likely they are confused
+    pub fn cost(&self, confusion: &[Vec<f64>]) -> f64 {
+        let mut cost = 0.0;
+        for (a, row) in confusion.iter().enumerate().take(self.codes.len()) {
+            for (b, &weight) in row.iter().enumerate().take(self.codes.len()) {
+                if a != b {
+                    cost += weight * (self.codes[a] ^ self.codes[b]).count_ones() as f64;
+                }
+            }
+        }
+        cost
+    }
+
+    /// A mapping which keeps the likely confusions to few wrong bits.
+    ///
+    /// `confusion[a][b]` is how likely the label of primitive a is read as b, e.g. counted over recognized frames
+    /// against their ground truth, or `Acute32Library::confusion_weights`.
+    /// Starting from the identity, pairs of codes are swapped while that lowers `cost`, which reaches a local minimum.
+    pub fn from_confusion(confusion: &[Vec<f64>]) -> Self {
+        let mut mapping = Self::identity();
+        let num_variants = mapping.codes.len();
+        // Symmetric, as reading a as b costs as many bits as reading b as a
+        let weight = |a: usize, b: usize| {
+            let get = |i: usize, j: usize| confusion.get(i).and_then(|row| row.get(j)).cloned().unwrap_or(0.0);
+            get(a, b) + get(b, a)
+        };
+        let weights: Vec<Vec<f64>> = (0..num_variants)
+            .map(|a| (0..num_variants).map(|b| if a == b { 0.0 } else { weight(a, b) }).collect())
+            .collect();
+        // The cost involving a label with its code, against all the others
+        let label_cost = |codes: &[usize], label: usize, code: usize, other: usize| {
+            (0..num_variants)
+                .filter(|&k| k != label && k != other)
+                .map(|k| weights[label][k] * (code ^ codes[k]).count_ones() as f64)
+                .sum::<f64>()
+        };
+
+        loop {
+            let mut improved = false;
+            for a in 0..num_variants {
+                for b in (a + 1)..num_variants {
+                    let (code_a, code_b) = (mapping.codes[a], mapping.codes[b]);
+                    let before = label_cost(&mapping.codes, a, code_a, b) + label_cost(&mapping.codes, b, code_b, a);
+                    let after = label_cost(&mapping.codes, a, code_b, b) + label_cost(&mapping.codes, b, code_a, a);
+                    if after < before - 1e-9 {
+                        mapping.codes.swap(a, b);
+                        improved = true;
+                    }
+                }
+            }
+            if !improved {
+                return mapping;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::interfaces::{Decoder, Encoder};
+    use super::super::{Acute32Decoder, Acute32Encoder, Acute32SymcodeConfig};
+    use super::*;
+
+    /// Every label, and a code through the encoder and the decoder
+    fn assert_round_trip(mapping: &LabelBitMapping) {
+        for primitive in 0..GlyphLabel::num_variants() {
+            let label = GlyphLabel::from_usize_representation(primitive);
+            let bits = mapping.label_to_bits(label).unwrap();
+            assert_eq!(bits.len(), 5);
+            assert_eq!(mapping.bits_to_label(bits), label);
+        }
+        assert_eq!(mapping.label_to_bits(GlyphLabel::Invalid), None);
+
+        let mut config = Acute32SymcodeConfig::default();
+        config.params.label_bit_mapping = mapping.clone();
+        let payload = BitVec::from_fn(20, |i| i % 4 == 1 || i % 5 == 3);
+        let symcode = Acute32Encoder::new(&config).encode(payload.clone(), 5).unwrap();
+        assert_eq!(Acute32Decoder::new(&config).decode(symcode), Ok(payload));
+    }
+
+    #[test]
+    fn label_mapping_round_trip() {
+        let identity = LabelBitMapping::default();
+        assert_round_trip(&identity);
+        assert_eq!(identity.label_to_bits(GlyphLabel::LongLL), GlyphLabel::self_to_bit_vec(GlyphLabel::LongLL, 5));
+
+        // Any permutation
+        let mut seed = 7;
+        for _ in 0..10 {
+            let mut codes: Vec<usize> = (0..GlyphLabel::num_variants()).collect();
+            for i in (1..codes.len()).rev() {
+                seed = (seed * 1103515245 + 12345) % (1 << 31);
+                codes.swap(i, seed % (i + 1));
+            }
+            assert_round_trip(&LabelBitMapping::new(codes).unwrap());
+        }
+
+        assert!(LabelBitMapping::new(vec![0; 32]).is_err());
+        assert!(LabelBitMapping::new((1..=32).collect()).is_err());
+        assert!(LabelBitMapping::new((0..31).collect()).is_err());
+    }
+
+    #[test]
+    fn label_mapping_from_confusion() {
+        // Each label is confused with its mirror in the enum, whose index differs in all 5 bits
+        let num_variants = GlyphLabel::num_variants();
+        let confusion: Vec<Vec<f64>> = (0..num_variants)
+            .map(|a| (0..num_variants).map(|b| if a + b == num_variants - 1 { 1.0 } else { 0.0 }).collect())
+            .collect();
+        let identity = LabelBitMapping::identity();
+        assert_eq!(identity.cost(&confusion), 5.0 * num_variants as f64);
+
+        let mapping = LabelBitMapping::from_confusion(&confusion);
+        assert_round_trip(&mapping);
+        for a in 0..num_variants {
+            let b = num_variants - 1 - a;
+            assert_eq!((mapping.codes()[a] ^ mapping.codes()[b]).count_ones(), 1);
+        }
+    }
+}
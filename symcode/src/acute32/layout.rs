@@ -0,0 +1,291 @@
+use visioncortex::{BoundingRect, PointF64};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use super::{Acute32SymcodeParams, Acute32TransformFitter, valid_pointf64_on_image};
+
+/// Where the finders and glyphs are placed in a code, in the object space
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Acute32Layout {
+    pub code_width: usize,
+    pub code_height: usize,
+    pub symbol_width: usize,
+    pub symbol_height: usize,
+    pub quiet_zone_width: usize,
+    /// The centers of the finders
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_helpers::points"))]
+    pub finder_positions: Vec<PointF64>,
+    /// The top-left corners of the glyphs
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_helpers::points"))]
+    pub glyph_anchors: Vec<PointF64>,
+}
+
+impl Acute32Layout {
+    /// The fitter looks for this many finders
+    pub const NUM_FINDERS: usize = 4;
+
+    pub fn from_params(params: &Acute32SymcodeParams) -> Self {
+        Self {
+            code_width: params.code_width,
+            code_height: params.code_height,
+            symbol_width: params.symbol_width,
+            symbol_height: params.symbol_height,
+            quiet_zone_width: params.quiet_zone_width,
+            finder_positions: params.finder_positions.clone(),
+            glyph_anchors: params.glyph_anchors.clone(),
+        }
+    }
+
+    pub fn apply_to(&self, params: &mut Acute32SymcodeParams) {
+        params.code_width = self.code_width;
+        params.code_height = self.code_height;
+        params.symbol_width = self.symbol_width;
+        params.symbol_height = self.symbol_height;
+        params.quiet_zone_width = self.quiet_zone_width;
+        params.finder_positions = self.finder_positions.clone();
+        params.glyph_anchors = self.glyph_anchors.clone();
+    }
+
+    pub fn finder_rects(&self) -> Vec<BoundingRect> {
+        self.finder_positions.iter().map(|center| {
+            let left = (center.x - self.symbol_width as f64 / 2.0).round() as i32;
+            let top = (center.y - self.symbol_height as f64 / 2.0).round() as i32;
+            BoundingRect::new_x_y_w_h(left, top, self.symbol_width as i32, self.symbol_height as i32)
+        }).collect()
+    }
+
+    pub fn glyph_rects(&self) -> Vec<BoundingRect> {
+        self.glyph_anchors.iter().map(|anchor| {
+            BoundingRect::new_x_y_w_h(anchor.x.round() as i32, anchor.y.round() as i32, self.symbol_width as i32, self.symbol_height as i32)
+        }).collect()
+    }
+
+    /// Checks that
+    /// + there are 4 finders and at least one glyph,
+    /// + every finder and glyph is within the code and outside of the quiet zone,
+    /// + no two of them overlap,
+    /// + the finders are in the arrangement the fitter expects, and do not look the same when the code is turned
+    ///   by a quarter, half or three quarters, so that the orientation of the code is never ambiguous.
+    pub fn validate(&self) -> Result<(), &'static str> {
+        if self.finder_positions.len() != Self::NUM_FINDERS {
+            return Err("Layout error: There must be 4 finders.");
+        }
+        if self.glyph_anchors.is_empty() {
+            return Err("Layout error: There is no glyph.");
+        }
+        for &finder in self.finder_positions.iter() {
+            if !valid_pointf64_on_image(finder, self.code_width, self.code_height) {
+                return Err("A finder is out of the boundary in the object space.");
+            }
+        }
+
+        let quiet_zone = self.quiet_zone_width as i32;
+        let rects: Vec<BoundingRect> = self.finder_rects().into_iter().chain(self.glyph_rects()).collect();
+        for rect in rects.iter() {
+            if rect.left < quiet_zone || rect.top < quiet_zone ||
+                rect.right > self.code_width as i32 - quiet_zone || rect.bottom > self.code_height as i32 - quiet_zone {
+                return Err("Layout error: A finder or glyph is in the quiet zone or out of the code.");
+            }
+        }
+        for (i, a) in rects.iter().enumerate() {
+            // Touching edges do not count as overlap
+            if rects.iter().skip(i + 1).any(|b| a.left < b.right && b.left < a.right && a.top < b.bottom && b.top < a.bottom) {
+                return Err("Layout error: Two finders or glyphs overlap.");
+            }
+        }
+
+        if !Acute32TransformFitter::correct_spatial_arrangement(&self.finder_positions) {
+            return Err("Layout error: The finders are not in the arrangement expected by the fitter.");
+        }
+        if self.has_rotational_symmetry() {
+            return Err("Layout error: The finder arrangement is the same when the code is turned.");
+        }
+
+        Ok(())
+    }
+
+    /// Whether turning the finders about the center of the code lands them on the finder positions
+    /// (in any order), within half a symbol
+    fn has_rotational_symmetry(&self) -> bool {
+        let center = PointF64::new(self.code_width as f64 / 2.0, self.code_height as f64 / 2.0);
+        let tolerance = std::cmp::min(self.symbol_width, self.symbol_height) as f64 / 2.0;
+        let turns: [fn(PointF64) -> PointF64; 3] = [
+            |v| PointF64::new(-v.y, v.x),
+            |v| PointF64::new(-v.x, -v.y),
+            |v| PointF64::new(v.y, -v.x),
+        ];
+        turns.iter().any(|turn| {
+            self.finder_positions.iter().all(|&finder| {
+                let turned = turn(finder - center) + center;
+                self.finder_positions.iter().any(|&other| (other - turned).norm() <= tolerance)
+            })
+        })
+    }
+}
+
+/// Builds an `Acute32Layout` from a grid of equal cells, each holding a finder or a glyph.
+///
+/// The default builder gives the layout of `Acute32SymcodeParams::default()`.
+#[derive(Clone, Debug)]
+pub struct Acute32LayoutBuilder {
+    pub rows: usize,
+    pub columns: usize,
+    pub symbol_width: usize,
+    pub symbol_height: usize,
+    /// Gap between neighbouring cells
+    pub spacing: usize,
+    /// Distance from the edge of the code to the first cell, the quiet zone included
+    pub margin: usize,
+    pub quiet_zone_width: usize,
+    /// (row, column) of the finders, in the order of `finder_positions`
+    pub finder_cells: Vec<(usize, usize)>,
+    /// (row, column) of the glyphs in reading order. If None, the remaining cells row by row
+    pub glyph_cells: Option<Vec<(usize, usize)>>,
+}
+
+impl Default for Acute32LayoutBuilder {
+    fn default() -> Self {
+        Self {
+            rows: 3,
+            columns: 3,
+            symbol_width: 155,
+            symbol_height: 155,
+            spacing: 78,
+            margin: 82,
+            quiet_zone_width: 10,
+            finder_cells: vec![(0, 1), (1, 1), (2, 0), (2, 2)],
+            glyph_cells: Some(vec![(0, 0), (1, 0), (2, 1), (1, 2), (0, 2)]),
+        }
+    }
+}
+
+impl Acute32LayoutBuilder {
+    pub fn new(rows: usize, columns: usize) -> Self {
+        Self {
+            rows,
+            columns,
+            glyph_cells: None,
+            ..Default::default()
+        }
+    }
+
+    pub fn symbol_size(mut self, symbol_width: usize, symbol_height: usize) -> Self {
+        self.symbol_width = symbol_width;
+        self.symbol_height = symbol_height;
+        self
+    }
+
+    pub fn spacing(mut self, spacing: usize) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    pub fn margin(mut self, margin: usize) -> Self {
+        self.margin = margin;
+        self
+    }
+
+    pub fn quiet_zone_width(mut self, quiet_zone_width: usize) -> Self {
+        self.quiet_zone_width = quiet_zone_width;
+        self
+    }
+
+    pub fn finder_cells(mut self, finder_cells: Vec<(usize, usize)>) -> Self {
+        self.finder_cells = finder_cells;
+        self
+    }
+
+    pub fn glyph_cells(mut self, glyph_cells: Vec<(usize, usize)>) -> Self {
+        self.glyph_cells = Some(glyph_cells);
+        self
+    }
+
+    fn cell_top_left(&self, (row, column): (usize, usize)) -> PointF64 {
+        PointF64::new(
+            (self.margin + column * (self.symbol_width + self.spacing)) as f64,
+            (self.margin + row * (self.symbol_height + self.spacing)) as f64,
+        )
+    }
+
+    /// Places the finders and glyphs, and validates the layout
+    pub fn build(&self) -> Result<Acute32Layout, &'static str> {
+        let glyph_cells = match &self.glyph_cells {
+            Some(glyph_cells) => glyph_cells.clone(),
+            None => (0..self.rows)
+                .flat_map(|row| (0..self.columns).map(move |column| (row, column)))
+                .filter(|cell| !self.finder_cells.contains(cell))
+                .collect(),
+        };
+        if self.finder_cells.iter().chain(glyph_cells.iter()).any(|&(row, column)| row >= self.rows || column >= self.columns) {
+            return Err("Layout error: A cell is out of the grid.");
+        }
+
+        let half_symbol = PointF64::new(self.symbol_width as f64 / 2.0, self.symbol_height as f64 / 2.0);
+        let layout = Acute32Layout {
+            code_width: 2 * self.margin + self.columns * self.symbol_width + self.columns.saturating_sub(1) * self.spacing,
+            code_height: 2 * self.margin + self.rows * self.symbol_height + self.rows.saturating_sub(1) * self.spacing,
+            symbol_width: self.symbol_width,
+            symbol_height: self.symbol_height,
+            quiet_zone_width: self.quiet_zone_width,
+            finder_positions: self.finder_cells.iter().map(|&cell| self.cell_top_left(cell) + half_symbol).collect(),
+            glyph_anchors: glyph_cells.into_iter().map(|cell| self.cell_top_left(cell)).collect(),
+        };
+        layout.validate()?;
+        Ok(layout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layout_default_builder_matches_default_config() {
+        let layout = Acute32LayoutBuilder::default().build().unwrap();
+        let params = Acute32SymcodeParams::default();
+        assert_eq!((layout.code_width, layout.code_height), (params.code_width, params.code_height));
+        // The default layout is on the grid within rounding
+        let points = layout.finder_positions.iter().chain(layout.glyph_anchors.iter());
+        let params_points = params.finder_positions.iter().chain(params.glyph_anchors.iter());
+        for (a, b) in points.zip(params_points) {
+            assert!((*a - *b).norm() <= 1.0);
+        }
+        assert_eq!(Acute32Layout::from_params(&params).validate(), Ok(()));
+    }
+
+    #[test]
+    fn layout_validation() {
+        // A larger grid with the glyphs in the remaining cells
+        let layout = Acute32LayoutBuilder::new(4, 4)
+            .finder_cells(vec![(0, 1), (1, 1), (3, 0), (3, 3)])
+            .build()
+            .unwrap();
+        assert_eq!(layout.glyph_anchors.len(), 12);
+        let mut params = Acute32SymcodeParams::default();
+        layout.apply_to(&mut params);
+        assert_eq!(params.num_glyphs_in_code(), 12);
+
+        // Finders at the corners look the same from every side
+        let symmetric = Acute32LayoutBuilder::default().finder_cells(vec![(0, 0), (0, 2), (2, 0), (2, 2)]).glyph_cells(vec![(1, 1)]);
+        assert!(symmetric.build().is_err());
+        // A finder and a glyph in the same cell
+        assert!(Acute32LayoutBuilder::default().glyph_cells(vec![(1, 1)]).build().is_err());
+        // Cells touch the edge of the code
+        assert!(Acute32LayoutBuilder::default().margin(5).build().is_err());
+        // Touching cells are fine, overlapping ones are not
+        assert!(Acute32LayoutBuilder::default().spacing(0).symbol_size(160, 160).build().is_ok());
+        let mut overlapping = Acute32LayoutBuilder::default().build().unwrap();
+        overlapping.glyph_anchors[0] = PointF64::new(200.0, 82.0);
+        assert!(overlapping.validate().is_err());
+        // Out of the grid
+        assert!(Acute32LayoutBuilder::default().finder_cells(vec![(0, 3), (1, 1), (2, 0), (2, 2)]).build().is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn layout_serde_round_trip() {
+        let layout = Acute32LayoutBuilder::default().build().unwrap();
+        let json = serde_json::to_string(&layout).unwrap();
+        assert_eq!(serde_json::from_str::<Acute32Layout>(&json).unwrap(), layout);
+    }
+}
@@ -3,16 +3,18 @@ use std::collections::HashMap;
 use bit_vec::BitVec;
 use visioncortex::{BinaryImage, Sampler};
 
-use super::{Acute32SymcodeConfig, GlyphLabel, GlyphTrace, Symbol, Trace, image_diff_area};
+use super::{Acute32SymcodeConfig, GlyphLabel, GlyphPrediction, GlyphTrace, Symbol, Trace, TraceIndex, image_diff_area};
 
 #[derive(Debug)]
 pub struct Acute32Library {
     templates: Vec<Symbol>,
+    /// Index over the traces of templates, ids are the indices into templates
+    trace_index: TraceIndex,
 }
 
 impl Default for Acute32Library {
     fn default() -> Self {
-        Self { templates: vec![] }
+        Self { templates: vec![], trace_index: TraceIndex::default() }
     }
 }
 
@@ -72,23 +74,67 @@ impl Acute32Library {
 
     /// Takes the binary image of the template and the usize representation of the label
     pub fn add_template(&mut self, image: BinaryImage, symcode_config: &Acute32SymcodeConfig) {
-        //let image = Sampler::resample_image(&image, symcode_config.symbol_width, symcode_config.symbol_height);
+        //let image = Sampler::resample_image(&image, symcode_config.params.symbol_width, symcode_config.params.symbol_height);
         let label = GlyphLabel::from_usize_representation(self.templates.len());
         //console_log_util(&format!("{:?}\n{}", label, image.to_string()));
-        self.templates.push(Symbol::from_image_label(image, label, symcode_config.stat_tolerance));
+        let template = Symbol::from_image_label(image, label, symcode_config.params.stat_tolerance);
+        self.trace_index.insert(&template.encoding);
+        self.templates.push(template);
+    }
+
+    /// How likely the template of each label is read as each other, the fewer pixels telling them apart the more,
+    /// indexed by the primitives of the labels. For `LabelBitMapping::from_confusion`
+    pub fn confusion_weights(&self) -> Vec<Vec<f64>> {
+        let num_variants = GlyphLabel::num_variants();
+        let mut confusion = vec![vec![0.0; num_variants]; num_variants];
+        for a in self.templates.iter() {
+            for b in self.templates.iter() {
+                if let (Some(i), Some(j)) = (GlyphLabel::self_to_primitive(a.label), GlyphLabel::self_to_primitive(b.label)) {
+                    if i != j && i < num_variants && j < num_variants {
+                        confusion[i][j] = 1.0 / (1 + image_diff_area(&a.image, &b.image)) as f64;
+                    }
+                }
+            }
+        }
+        confusion
+    }
+
+    /// The difference between the pixel errors of the runner-up and the best templates, as a fraction of the symbol area.
+    ///
+    /// All templates are compared, regardless of their traces. None if there are less than 2 templates.
+    pub fn match_margin(&self, image: &BinaryImage, symcode_config: &Acute32SymcodeConfig) -> Option<f64> {
+        if self.templates.len() < 2 {
+            return None;
+        }
+        let image = &Sampler::resample_image(image, symcode_config.params.symbol_width, symcode_config.params.symbol_height);
+        let (best, runner_up) = self.templates.iter()
+            .map(|template| image_diff_area(&template.image, image))
+            .fold((std::u64::MAX, std::u64::MAX), |(best, runner_up), error| {
+                if error < best {
+                    (error, best)
+                } else {
+                    (best, std::cmp::min(runner_up, error))
+                }
+            });
+        let area = (symcode_config.params.symbol_width * symcode_config.params.symbol_height) as f64;
+        Some((runner_up - best) as f64 / area)
     }
 
     pub fn find_most_similar_glyph(&self, image: BinaryImage, symcode_config: &Acute32SymcodeConfig) -> GlyphLabel {
-        let image = &Sampler::resample_image(&image, symcode_config.symbol_width, symcode_config.symbol_height);
-        let input_encoding = &GlyphTrace::from_image(image, symcode_config.stat_tolerance);
+        self.find_most_similar_glyph_with_score(image, symcode_config).label
+    }
+
+    /// The score is the fraction of pixels agreeing with the most similar template
+    pub fn find_most_similar_glyph_with_score(&self, image: BinaryImage, symcode_config: &Acute32SymcodeConfig) -> GlyphPrediction {
+        let image = &Sampler::resample_image(&image, symcode_config.params.symbol_width, symcode_config.params.symbol_height);
+        let input_encoding = &GlyphTrace::from_image(image, symcode_config.params.stat_tolerance);
         //console_log_util(&format!("{:?}", input_encoding));
 
-        self.templates.iter()
+        // Only the templates with similar traces are compared pixelwise
+        let (min_error, min_label) = self.trace_index.query(input_encoding, symcode_config.params.max_encoding_difference).into_iter()
+            .map(|i| &self.templates[i])
             .fold( (std::u64::MAX, GlyphLabel::Invalid),
                 |(min_error, min_label), template| {
-                    if template.encoding.diff(input_encoding) > symcode_config.max_encoding_difference {
-                        return (min_error, min_label);
-                    }
                     let error = image_diff_area(&template.image, image);
                     if error < min_error {
                         (error, template.label)
@@ -96,6 +142,15 @@ impl Acute32Library {
                         (min_error, min_label)
                     }
                 }
-            ).1
+            );
+
+        if min_label == GlyphLabel::Invalid {
+            return GlyphPrediction::default();
+        }
+        let num_pixels = (image.width * image.height).max(1) as f64;
+        GlyphPrediction {
+            label: min_label,
+            score: 1.0 - (min_error as f64 / num_pixels).min(1.0),
+        }
     }
 }
\ No newline at end of file
@@ -1,49 +1,92 @@
+mod accumulator;
 mod alphabet;
+mod classifier;
+mod colored;
+mod dataset;
 mod decoder;
+mod distortion;
 mod encoder;
 mod fitter;
 mod finder;
+mod generator;
+mod grading;
 mod label;
+mod label_mapping;
+mod layout;
 mod library;
+mod polarity;
 mod reader;
+mod rectifier;
+mod refiner;
+mod scanner;
+#[cfg(feature = "serde")]
+mod serde_helpers;
 mod symbol;
 mod symcode_config;
+#[cfg(test)]
+mod test_fixtures;
 mod trace;
+mod trace_index;
+mod tracker;
 mod util;
 
+pub use accumulator::*;
 pub use alphabet::*;
+pub use classifier::*;
+pub use colored::*;
+pub use dataset::*;
 pub use decoder::*;
+pub use distortion::*;
 pub use encoder::*;
 pub use finder::*;
+pub use generator::*;
+pub use grading::*;
 pub use fitter::*;
 pub use label::*;
+pub use label_mapping::*;
+pub use layout::*;
 pub use library::*;
+pub use polarity::*;
 pub use reader::*;
+pub use rectifier::*;
+pub use refiner::*;
+pub use scanner::*;
 pub use symbol::*;
 pub use symcode_config::*;
 pub use trace::*;
+pub use trace_index::*;
+pub use tracker::*;
 use util::*;
 
+use crate::interfaces::{Debugger, DummyDebugger};
+
 pub struct Acute32<'a> {
     config: &'a Acute32SymcodeConfig,
+    debugger: &'a dyn Debugger,
 }
 
 impl<'a> Acute32<'a> {
 
     pub fn new(config: &'a Acute32SymcodeConfig) -> Acute32<'a> {
-        Self { config }
+        Self { config, debugger: &DummyDebugger }
+    }
+
+    /// Renders the intermediate results of the finder, fitter and reader
+    pub fn debugger(mut self, debugger: &'a dyn Debugger) -> Self {
+        self.debugger = debugger;
+        self
     }
 
 	pub fn get_finder(&self) -> Acute32FinderCandidate {
-		Acute32FinderCandidate::new(self.config)
+		Acute32FinderCandidate::new(self.config).debugger(self.debugger)
 	}
 
 	pub fn get_fitter(&self) -> Acute32TransformFitter {
-		Acute32TransformFitter::new(self.config)
+		Acute32TransformFitter::new(self.config).debugger(self.debugger)
 	}
 
 	pub fn get_reader(&self) -> Acute32Recognizer {
-		Acute32Recognizer::new(self.config)
+		Acute32Recognizer::new(self.config).debugger(self.debugger)
 	}
 
 	pub fn get_decoder(&self) -> Acute32Decoder {
@@ -53,4 +96,28 @@ impl<'a> Acute32<'a> {
 	pub fn get_encoder(&self) -> Acute32Encoder {
 		Acute32Encoder::new(self.config)
 	}
+
+	pub fn get_generator(&self) -> Acute32Generator {
+		Acute32Generator::new(self.config)
+	}
+
+	pub fn get_grader(&self) -> Acute32Grader {
+		Acute32Grader::new(self.config)
+	}
+
+	pub fn get_color_encoder(&self) -> Acute32ColorEncoder {
+		Acute32ColorEncoder::new(self.config)
+	}
+
+	pub fn get_color_decoder(&self) -> Acute32ColorDecoder {
+		Acute32ColorDecoder::new(self.config)
+	}
+
+	pub fn get_color_generator(&self) -> Acute32ColorGenerator {
+		Acute32ColorGenerator::new(self.config)
+	}
+
+	pub fn get_color_reader(&self) -> Acute32ColorRecognizer {
+		Acute32ColorRecognizer::new(self.config).debugger(self.debugger)
+	}
 }
\ No newline at end of file
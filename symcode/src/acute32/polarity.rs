@@ -0,0 +1,59 @@
+use visioncortex::BinaryImage;
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Whether the code is printed with dark ink on a light background, or the reverse
+/// (e.g. etched on metal or displayed on a dark screen)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Polarity {
+    #[default]
+    DarkOnLight,
+    LightOnDark,
+}
+
+impl Polarity {
+    /// Whether a luminance value is on the ink side of the threshold, for pixel values or fractional levels alike
+    #[inline]
+    pub fn is_ink<T: PartialOrd>(self, value: T, threshold: T) -> bool {
+        match self {
+            Self::DarkOnLight => value < threshold,
+            Self::LightOnDark => value > threshold,
+        }
+    }
+
+    pub fn inverted(self) -> Self {
+        match self {
+            Self::DarkOnLight => Self::LightOnDark,
+            Self::LightOnDark => Self::DarkOnLight,
+        }
+    }
+}
+
+/// Flips every pixel of the image
+pub(crate) fn invert_binary_image(image: &BinaryImage) -> BinaryImage {
+    let mut result = BinaryImage::new_w_h(image.width, image.height);
+    for y in 0..image.height {
+        for x in 0..image.width {
+            result.set_pixel(x, y, !image.get_pixel(x, y));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn polarity_is_ink() {
+        assert!(Polarity::DarkOnLight.is_ink(20, 128));
+        assert!(!Polarity::DarkOnLight.is_ink(200, 128));
+        assert!(Polarity::LightOnDark.is_ink(200, 128));
+        assert!(!Polarity::LightOnDark.is_ink(20, 128));
+        // The threshold itself is background in both
+        assert!(!Polarity::DarkOnLight.is_ink(128, 128) && !Polarity::LightOnDark.is_ink(128, 128));
+        assert!(Polarity::DarkOnLight.is_ink(127.0, 127.5) && Polarity::LightOnDark.is_ink(128.0, 127.5));
+        assert_eq!(Polarity::default().inverted(), Polarity::LightOnDark);
+    }
+}
@@ -1,48 +1,276 @@
 use visioncortex::{BinaryImage, BoundingRect, ColorImage, PerspectiveTransform, PointF64, PointI32};
-use crate::interfaces::Reader;
-use super::{Acute32Library, Acute32SymcodeConfig, GlyphLabel, util::global_adaptive_threshold};
+use crate::interfaces::{Debugger, DummyDebugger, Reader};
+use super::{Acute32Library, Acute32SymcodeConfig, GlyphClassifier, GlyphLabel, GlyphPrediction, Polarity, RectifiedImage, RefinementReport, rectify_regions, refine_transform, set_pixels_bounding_rect};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// How the recognizer locates the glyphs in the rectified code
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum ReaderMode {
+    /// Group the connected clusters by glyph regions, and crop at the center of each merged group
+    #[default]
+    ClusterGrouping,
+    /// Crop directly at the glyph anchors, without depending on connected-component segmentation.
+    ///
+    /// If search_radius > 0, offsets up to search_radius pixels (in steps of search_step) are tried
+    /// and the crop the classifier is the most confident about is taken.
+    AnchorSampling { search_radius: usize, search_step: usize },
+}
+
+/// What the recognizer does when the quiet zone around the code contains ink
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum QuietZonePolicy {
+    /// Do not measure the quiet zone
+    Ignore,
+    /// Measure and log a warning
+    #[default]
+    Warn,
+    /// Measure and fail the read
+    Reject,
+}
+
+/// Measurement of the quiet zone of a code
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct QuietZoneReport {
+    /// Fraction of the quiet zone which is ink or out of the frame
+    pub violation: f64,
+    /// Whether the violation is within max_quiet_zone_violation
+    pub clear: bool,
+}
+
+/// A glyph read from a glyph region of the rectified code
+pub struct RecognizedGlyph {
+    /// The cropped glyph image, or None if nothing is found in the region
+    pub image: Option<BinaryImage>,
+    /// Where the glyph image is cropped in the rectified code
+    pub rect: Option<BoundingRect>,
+    pub prediction: GlyphPrediction,
+}
+
+/// The intermediate images and the predictions of reading a frame
+pub struct RecognizerOutput {
+    /// Ink pixels are set, whatever the polarity
+    pub rectified_image: BinaryImage,
+    /// The configured polarity, or the detected one
+    pub polarity: Polarity,
+    /// One for each glyph anchor, in order
+    pub glyphs: Vec<RecognizedGlyph>,
+    /// Set if the glyphs are read again with a refined transform
+    pub refinement: Option<RefinementReport>,
+    /// None if the quiet zone policy is Ignore, or it cannot be measured
+    pub quiet_zone: Option<QuietZoneReport>,
+}
+
+impl RecognizerOutput {
+    pub fn labels(&self) -> Vec<GlyphLabel> {
+        self.glyphs.iter().map(|glyph| glyph.prediction.label).collect()
+    }
+
+    /// Fails if the quiet zone is not clear and the policy is Reject
+    pub fn check_quiet_zone(&self, symcode_config: &Acute32SymcodeConfig) -> Result<(), &'static str> {
+        match (symcode_config.params.quiet_zone_policy, &self.quiet_zone) {
+            (QuietZonePolicy::Reject, Some(report)) if !report.clear => Err("Reader error: Quiet zone is not clear."),
+            _ => Ok(()),
+        }
+    }
+}
 
 pub struct Acute32Recognizer<'a> {
     config: &'a Acute32SymcodeConfig,
+    debugger: &'a dyn Debugger,
 }
 
 impl<'a> Acute32Recognizer<'a> {
 
     pub fn new(config: &'a Acute32SymcodeConfig) -> Acute32Recognizer<'a> {
-        Self { config }
+        Self { config, debugger: &DummyDebugger }
+    }
+
+    /// Renders the rectified code and the glyph crops
+    pub fn debugger(mut self, debugger: &'a dyn Debugger) -> Self {
+        self.debugger = debugger;
+        self
     }
 
     pub fn rectify_image(raw_image: ColorImage, image_to_object: PerspectiveTransform, symcode_config: &Acute32SymcodeConfig) -> BinaryImage {
-        let width = symcode_config.code_width;
-        let height = symcode_config.code_height;
-        let mut rectified_image = ColorImage::new_w_h(width, height);
-        for y in symcode_config.quiet_zone_width..(height - symcode_config.quiet_zone_width) {
-            for x in symcode_config.quiet_zone_width..(width - symcode_config.quiet_zone_width) {
-                let sample_point = image_to_object.transform_inverse(PointF64::new(x as f64, y as f64)).to_point_f32();
-                let interpolated_color = match raw_image.sample_pixel_at_safe(sample_point) {
-                    Some(color) => color,
-                    None => visioncortex::Color::color(&visioncortex::ColorName::White),
+        let polarity = Self::polarity_of(&raw_image, &image_to_object, symcode_config);
+        Self::rectify_image_grayscale(&raw_image, &image_to_object, symcode_config).to_binary_image_with_polarity(polarity)
+    }
+
+    /// The configured polarity, or the detected one if it is not configured
+    pub fn polarity_of(raw_image: &ColorImage, image_to_object: &PerspectiveTransform, symcode_config: &Acute32SymcodeConfig) -> Polarity {
+        symcode_config.params.polarity.unwrap_or_else(|| Self::detect_polarity(raw_image, image_to_object, symcode_config))
+    }
+
+    /// Compares the luminance of the finders where their shape is ink against where it is background,
+    /// which works for any finder design. DarkOnLight if the finders are not in the frame.
+    pub fn detect_polarity(raw_image: &ColorImage, image_to_object: &PerspectiveTransform, symcode_config: &Acute32SymcodeConfig) -> Polarity {
+        let (width, height) = (symcode_config.params.symbol_width, symcode_config.params.symbol_height);
+        let finder_image = symcode_config.finder.to_image(width, height);
+        let finder_rects: Vec<BoundingRect> = symcode_config.params.finder_positions.iter().map(|center| {
+            let top_left = center.to_point_i32() - PointI32::new((width >> 1) as i32, (height >> 1) as i32);
+            BoundingRect::new_x_y_w_h(top_left.x, top_left.y, width as i32, height as i32)
+        }).collect();
+        let rectified = rectify_regions(
+            raw_image, image_to_object, symcode_config.params.lens_distortion.as_ref(),
+            symcode_config.params.code_width, symcode_config.params.code_height, &finder_rects, symcode_config.params.interpolation
+        );
+
+        // (sum, count) of the pixels under the ink and the background of the finder shape
+        let mut ink = (0u64, 0u64);
+        let mut background = (0u64, 0u64);
+        for rect in finder_rects.iter() {
+            for y in 0..height {
+                for x in 0..width {
+                    let (rx, ry) = (rect.left + x as i32, rect.top + y as i32);
+                    if rx < 0 || ry < 0 {
+                        continue;
+                    }
+                    if let Some(value) = rectified.get_pixel(rx as usize, ry as usize) {
+                        let total = if finder_image.get_pixel(x, y) { &mut ink } else { &mut background };
+                        total.0 += value as u64;
+                        total.1 += 1;
+                    }
+                }
+            }
+        }
+        if ink.1 == 0 || background.1 == 0 {
+            return Polarity::default();
+        }
+        if ink.0 as f64 / ink.1 as f64 > background.0 as f64 / background.1 as f64 {
+            Polarity::LightOnDark
+        } else {
+            Polarity::DarkOnLight
+        }
+    }
+
+    /// Rectify the glyph regions (with margins for locating the glyphs) into grayscale,
+    /// pixels outside of them or mapped out of bound of the raw image are unknown.
+    pub fn rectify_image_grayscale(raw_image: &ColorImage, image_to_object: &PerspectiveTransform, symcode_config: &Acute32SymcodeConfig) -> RectifiedImage {
+        rectify_regions(
+            raw_image, image_to_object, symcode_config.params.lens_distortion.as_ref(),
+            symcode_config.params.code_width, symcode_config.params.code_height,
+            &Self::sampling_regions(symcode_config),
+            symcode_config.params.interpolation
+        )
+    }
+
+    /// The glyph regions expanded by the tolerance of locating the glyphs, within the quiet zone
+    fn sampling_regions(symcode_config: &Acute32SymcodeConfig) -> Vec<BoundingRect> {
+        let search_radius = match symcode_config.params.reader_mode {
+            ReaderMode::ClusterGrouping => 0,
+            ReaderMode::AnchorSampling { search_radius, .. } => search_radius as i32,
+        };
+        let margin_x = ((symcode_config.params.symbol_width >> 3) + 5) as i32 + search_radius;
+        let margin_y = ((symcode_config.params.symbol_height >> 3) + 5) as i32 + search_radius;
+        let quiet_zone = symcode_config.params.quiet_zone_width as i32;
+        let (width, height) = (symcode_config.params.code_width as i32, symcode_config.params.code_height as i32);
+
+        let mut regions: Vec<BoundingRect> = symcode_config.params.glyph_anchors.iter().map(|anchor| {
+            let left = std::cmp::max(anchor.x as i32 - margin_x, quiet_zone);
+            let top = std::cmp::max(anchor.y as i32 - margin_y, quiet_zone);
+            let right = std::cmp::min(anchor.x as i32 + symcode_config.params.symbol_width as i32 + margin_x, width - quiet_zone);
+            let bottom = std::cmp::min(anchor.y as i32 + symcode_config.params.symbol_height as i32 + margin_y, height - quiet_zone);
+            BoundingRect { left, top, right, bottom }
+        }).collect();
+        if symcode_config.params.refine_transform {
+            regions.extend(symcode_config.params.finder_positions.iter().map(|center| Self::finder_window(*center, symcode_config)));
+        }
+        regions
+    }
+
+    /// The region around a finder in which it is located for refinement
+    fn finder_window(center: PointF64, symcode_config: &Acute32SymcodeConfig) -> BoundingRect {
+        let half_width = ((symcode_config.params.symbol_width >> 1) + (symcode_config.params.symbol_width >> 3)) as i32;
+        let half_height = ((symcode_config.params.symbol_height >> 1) + (symcode_config.params.symbol_height >> 3)) as i32;
+        let center = center.to_point_i32();
+        BoundingRect {
+            left: std::cmp::max(center.x - half_width, 0),
+            top: std::cmp::max(center.y - half_height, 0),
+            right: std::cmp::min(center.x + half_width, symcode_config.params.code_width as i32),
+            bottom: std::cmp::min(center.y + half_height, symcode_config.params.code_height as i32),
+        }
+    }
+
+    /// Pairs of feature points located on the rectified image and their expected positions in the object space:
+    /// the centers and edge midpoints of the finders, and the centers of the recognized glyphs.
+    fn refinement_correspondences(output: &RecognizerOutput, glyph_library: &Acute32Library, symcode_config: &Acute32SymcodeConfig) -> (Vec<PointF64>, Vec<PointF64>) {
+        let offset = |rect: BoundingRect, origin: PointI32| BoundingRect {
+            left: rect.left + origin.x, top: rect.top + origin.y, right: rect.right + origin.x, bottom: rect.bottom + origin.y,
+        };
+        let mut observed = vec![];
+        let mut expected = vec![];
+
+        let finder_image = symcode_config.finder.to_image(symcode_config.params.symbol_width, symcode_config.params.symbol_height);
+        if let Some(finder_rect) = set_pixels_bounding_rect(&finder_image) {
+            for center in symcode_config.params.finder_positions.iter() {
+                let window = Self::finder_window(*center, symcode_config);
+                let window_image = output.rectified_image.crop_with_rect(window);
+                if window_image.area() < symcode_config.params.absolute_empty_cluster_threshold(finder_image.width, finder_image.height) {
+                    continue;
+                }
+                let observed_rect = match set_pixels_bounding_rect(&window_image) {
+                    // Touching the window means the finder is merged with something else
+                    Some(rect) if rect.left > 0 && rect.top > 0 && rect.right < window.width() && rect.bottom < window.height() => rect,
+                    _ => continue,
                 };
-                rectified_image.set_pixel(x, y, &interpolated_color);
+                let top_left = center.to_point_i32() - PointI32::new((symcode_config.params.symbol_width >> 1) as i32, (symcode_config.params.symbol_height >> 1) as i32);
+                observed.extend(Self::rect_feature_points(&offset(observed_rect, PointI32::new(window.left, window.top))));
+                expected.extend(Self::rect_feature_points(&offset(finder_rect, top_left)));
+            }
+        }
+
+        for (glyph, anchor) in output.glyphs.iter().zip(symcode_config.params.glyph_anchors.iter()) {
+            if glyph.prediction.label == GlyphLabel::Invalid {
+                continue;
+            }
+            let (image, rect) = match (&glyph.image, &glyph.rect) {
+                (Some(image), Some(rect)) => (image, rect),
+                _ => continue,
+            };
+            let template_rect = glyph_library.get_glyph_with_label(glyph.prediction.label)
+                .and_then(|template| set_pixels_bounding_rect(&template.image));
+            if let (Some(observed_rect), Some(template_rect)) = (set_pixels_bounding_rect(image), template_rect) {
+                observed.push(Self::rect_center(&offset(observed_rect, PointI32::new(rect.left, rect.top))));
+                expected.push(Self::rect_center(&offset(template_rect, anchor.to_point_i32())));
             }
         }
-        global_adaptive_threshold(&rectified_image)
+
+        (observed, expected)
+    }
+
+    fn rect_center(rect: &BoundingRect) -> PointF64 {
+        PointF64::new((rect.left + rect.right) as f64 / 2.0, (rect.top + rect.bottom) as f64 / 2.0)
+    }
+
+    /// The center and the midpoints of the edges
+    fn rect_feature_points(rect: &BoundingRect) -> [PointF64; 5] {
+        let center = Self::rect_center(rect);
+        [
+            center,
+            PointF64::new(rect.left as f64, center.y),
+            PointF64::new(rect.right as f64, center.y),
+            PointF64::new(center.x, rect.top as f64),
+            PointF64::new(center.x, rect.bottom as f64),
+        ]
     }
 
     /// Validates the size of a cluster in rectified image
     pub fn validate_cluster_by_rect_size(cluster_rect: &BoundingRect, symcode_config: &Acute32SymcodeConfig) -> bool {
-        let height_tolerance = ((symcode_config.symbol_height >> 3) + 5) as i32;
-        let width_tolerance = ((symcode_config.symbol_width >> 3) + 5) as i32;
-        cluster_rect.width() <= symcode_config.symbol_width as i32 + width_tolerance &&
-        cluster_rect.height() <= symcode_config.symbol_height as i32 + height_tolerance &&
+        let height_tolerance = ((symcode_config.params.symbol_height >> 3) + 5) as i32;
+        let width_tolerance = ((symcode_config.params.symbol_width >> 3) + 5) as i32;
+        cluster_rect.width() <= symcode_config.params.symbol_width as i32 + width_tolerance &&
+        cluster_rect.height() <= symcode_config.params.symbol_height as i32 + height_tolerance &&
         cluster_rect.width() >= width_tolerance &&
         cluster_rect.height() >= height_tolerance
     }
     
     /// For each rect in cluster_rects, classify it into the group of rects that overlap with the glyph region
     pub fn group_cluster_rects_by_glyph_regions(mut cluster_rects: Vec<BoundingRect>, symcode_config: &Acute32SymcodeConfig) -> Vec<Vec<BoundingRect>> {
-        let glyph_rects: Vec<BoundingRect> = symcode_config.glyph_anchors.iter().map(|top_left| {
-            BoundingRect::new_x_y_w_h(top_left.x as i32, top_left.y as i32, symcode_config.symbol_width as i32, symcode_config.symbol_height as i32)
+        let glyph_rects: Vec<BoundingRect> = symcode_config.params.glyph_anchors.iter().map(|top_left| {
+            BoundingRect::new_x_y_w_h(top_left.x as i32, top_left.y as i32, symcode_config.params.symbol_width as i32, symcode_config.params.symbol_height as i32)
         }).collect();
 
         let mut grouped_rects = vec![vec![]; glyph_rects.len()];
@@ -81,33 +309,141 @@ impl<'a> Acute32Recognizer<'a> {
 
     /// Crop an image of a glyph at the specified center position
     pub fn crop_glyph_at_center(image: &BinaryImage, center: PointI32, symcode_config: &Acute32SymcodeConfig) -> BinaryImage {
-        let width = symcode_config.symbol_width;
-        let height = symcode_config.symbol_height;
+        image.crop_with_rect(Self::glyph_rect_at_center(center, symcode_config))
+    }
+
+    fn glyph_rect_at_center(center: PointI32, symcode_config: &Acute32SymcodeConfig) -> BoundingRect {
+        let width = symcode_config.params.symbol_width;
+        let height = symcode_config.params.symbol_height;
         let top_left = center - PointI32::new((width >> 1) as i32, (height >> 1) as i32);
-        let rect = BoundingRect::new_x_y_w_h(top_left.x, top_left.y, width as i32, height as i32);
-        symcode_config.debugger.render_bounding_rect_to_canvas(&rect);
-        image.crop_with_rect(rect)
+        BoundingRect::new_x_y_w_h(top_left.x, top_left.y, width as i32, height as i32)
     }
 
     /// Finds the most similar glyph in the library based on given config
     pub fn find_most_similar_glyph(image: BinaryImage, glyph_library: &Acute32Library, symcode_config: &Acute32SymcodeConfig) -> GlyphLabel {
-        glyph_library.find_most_similar_glyph(
-            image,
-            symcode_config
-        )
+        Self::classify_glyph(&image, glyph_library, symcode_config).label
+    }
+
+    /// Classifies with the classifier in config if there is one, otherwise by template matching with the library
+    pub fn classify_glyph(image: &BinaryImage, glyph_library: &Acute32Library, symcode_config: &Acute32SymcodeConfig) -> GlyphPrediction {
+        match &symcode_config.glyph_classifier {
+            Some(classifier) => classifier.classify(image, symcode_config),
+            None => glyph_library.classify(image, symcode_config),
+        }
     }
     
     /// Read all glyphs at the anchors on the input image
-    pub fn read_glyphs_from_raw_frame(image: ColorImage, image_to_object: PerspectiveTransform, glyph_library: &Acute32Library, symcode_config: &crate::acute32::Acute32SymcodeConfig) -> Vec<GlyphLabel> {
-        let rectified_image = Self::rectify_image(image, image_to_object, symcode_config);
-        if symcode_config.debugger.render_binary_image_to_canvas(&rectified_image).is_err() {
+    pub fn read_glyphs_from_raw_frame(image: ColorImage, image_to_object: PerspectiveTransform, glyph_library: &Acute32Library, symcode_config: &crate::acute32::Acute32SymcodeConfig, debugger: &dyn Debugger) -> Vec<GlyphLabel> {
+        Self::read_glyphs_with_details_from_raw_frame(image, image_to_object, glyph_library, symcode_config, debugger).labels()
+    }
+
+    /// Read all glyphs at the anchors on the input image, keeping the rectified image and the glyph crops
+    ///
+    /// If `refine_transform` is set, the transform is refined with the finders and glyphs located
+    /// in the first read, and the glyphs are read again if the reprojection error is reduced.
+    pub fn read_glyphs_with_details_from_raw_frame(image: ColorImage, image_to_object: PerspectiveTransform, glyph_library: &Acute32Library, symcode_config: &crate::acute32::Acute32SymcodeConfig, debugger: &dyn Debugger) -> RecognizerOutput {
+        Self::read_glyphs_with_final_transform(&image, image_to_object, glyph_library, symcode_config, debugger).0
+    }
+
+    /// Same as `read_glyphs_with_details_from_raw_frame`, also returning the transform of the final read
+    pub(crate) fn read_glyphs_with_final_transform(image: &ColorImage, image_to_object: PerspectiveTransform, glyph_library: &Acute32Library, symcode_config: &Acute32SymcodeConfig, debugger: &dyn Debugger) -> (RecognizerOutput, PerspectiveTransform) {
+        let output = Self::read_glyphs_with_transform(image, &image_to_object, glyph_library, symcode_config, debugger);
+        if !symcode_config.params.refine_transform {
+            return (output, image_to_object);
+        }
+
+        let (observed, expected) = Self::refinement_correspondences(&output, glyph_library, symcode_config);
+        match refine_transform(&image_to_object, &observed, &expected, &symcode_config.params.finder_positions) {
+            Some((refined_transform, report)) => {
+                let mut refined_output = Self::read_glyphs_with_transform(image, &refined_transform, glyph_library, symcode_config, debugger);
+                refined_output.refinement = Some(report);
+                (refined_output, refined_transform)
+            },
+            None => (output, image_to_object),
+        }
+    }
+
+    fn read_glyphs_with_transform(image: &ColorImage, image_to_object: &PerspectiveTransform, glyph_library: &Acute32Library, symcode_config: &Acute32SymcodeConfig, debugger: &dyn Debugger) -> RecognizerOutput {
+        let polarity = Self::polarity_of(image, image_to_object, symcode_config);
+        let rectified_grayscale = Self::rectify_image_grayscale(image, image_to_object, symcode_config);
+        let rectified_image = rectified_grayscale.to_binary_image_with_polarity(polarity);
+        if debugger.render_binary_image_to_canvas(&rectified_image).is_err() {
             log::error!("Cannot render rectified code image to debug canvas.");
         }
+        let mut glyphs = match symcode_config.params.reader_mode {
+            ReaderMode::ClusterGrouping => Self::read_glyphs_by_cluster_grouping(&rectified_image, glyph_library, symcode_config, debugger),
+            ReaderMode::AnchorSampling { search_radius, search_step } => Self::read_glyphs_at_anchors(&rectified_image, search_radius, search_step, glyph_library, symcode_config, debugger),
+        };
+
+        // A glyph partly out of the frame cannot be read reliably
+        glyphs.iter_mut().for_each(|glyph| {
+            if let Some(rect) = &glyph.rect {
+                if rectified_grayscale.unknown_ratio(rect) > symcode_config.params.max_unknown_ratio {
+                    glyph.prediction = GlyphPrediction::default();
+                }
+            }
+        });
+
+        let quiet_zone = match (symcode_config.params.quiet_zone_policy, rectified_grayscale.threshold()) {
+            (QuietZonePolicy::Ignore, _) | (_, None) => None,
+            (policy, Some(threshold)) => Self::measure_quiet_zone(image, image_to_object, threshold as f64, polarity, symcode_config).map(|violation| {
+                let clear = violation <= symcode_config.params.max_quiet_zone_violation;
+                if !clear && policy == QuietZonePolicy::Warn {
+                    log::warn!("Quiet zone is not clear, {:.1}% of it is ink or out of frame.", violation * 100.0);
+                }
+                QuietZoneReport { violation, clear }
+            }),
+        };
+
+        RecognizerOutput { rectified_image, polarity, glyphs, refinement: None, quiet_zone }
+    }
+
+    /// Fraction of the quiet zone which is on the ink side of threshold or out of the frame.
+    ///
+    /// The quiet zone is sampled separately, as only the glyph regions are rectified for reading.
+    /// None if the quiet zone is at least half the code wide or high, as nothing is left inside it.
+    pub fn measure_quiet_zone(raw_image: &ColorImage, image_to_object: &PerspectiveTransform, threshold: f64, polarity: Polarity, symcode_config: &Acute32SymcodeConfig) -> Option<f64> {
+        let (w, h, q) = (symcode_config.params.code_width as i32, symcode_config.params.code_height as i32, symcode_config.params.quiet_zone_width as i32);
+        if 2 * q >= w || 2 * q >= h {
+            return None;
+        }
+        if q == 0 {
+            return Some(0.0);
+        }
+        let bands = [
+            BoundingRect::new_x_y_w_h(0, 0, w, q),
+            BoundingRect::new_x_y_w_h(0, h - q, w, q),
+            BoundingRect::new_x_y_w_h(0, q, q, h - 2 * q),
+            BoundingRect::new_x_y_w_h(w - q, q, q, h - 2 * q),
+        ];
+        let rectified = rectify_regions(
+            raw_image, image_to_object, symcode_config.params.lens_distortion.as_ref(),
+            symcode_config.params.code_width, symcode_config.params.code_height, &bands, symcode_config.params.interpolation
+        );
+        let mut violations = 0;
+        let mut total = 0;
+        for band in bands.iter() {
+            for y in band.top..band.bottom {
+                for x in band.left..band.right {
+                    total += 1;
+                    match rectified.get_pixel(x as usize, y as usize) {
+                        Some(value) if !polarity.is_ink(value as f64, threshold) => {},
+                        // Ink, or out of the frame and cannot be verified
+                        _ => violations += 1,
+                    }
+                }
+            }
+        }
+        Some(violations as f64 / total as f64)
+    }
+
+    /// Read the glyphs at the centers of the clusters grouped by glyph regions
+    fn read_glyphs_by_cluster_grouping(rectified_image: &BinaryImage, glyph_library: &Acute32Library, symcode_config: &Acute32SymcodeConfig, debugger: &dyn Debugger) -> Vec<RecognizedGlyph> {
         let cluster_rects: Vec<BoundingRect> = rectified_image.to_clusters(true).clusters.into_iter()
             .filter_map(|cluster| {
                 let rect = cluster.rect;
                 // Checks the number of solid points within this cluster
-                if cluster.size() < symcode_config.absolute_empty_cluster_threshold(rect.width() as usize, rect.height() as usize) as usize {
+                if cluster.size() < symcode_config.params.absolute_empty_cluster_threshold(rect.width() as usize, rect.height() as usize) as usize {
                     return None;
                 }
                 // Checks the size of bounding box
@@ -119,32 +455,200 @@ impl<'a> Acute32Recognizer<'a> {
             })
             .collect();
 
-        cluster_rects.iter().for_each(|rect| symcode_config.debugger.render_bounding_rect_to_canvas_with_color(rect, visioncortex::Color::new(0, 0, 255)));
+        cluster_rects.iter().for_each(|rect| debugger.render_bounding_rect_to_canvas_with_color(rect, visioncortex::Color::new(0, 0, 255)));
         let grouped_cluster_rects = Self::group_cluster_rects_by_glyph_regions(cluster_rects, symcode_config);
         let centers_of_groups = Self::centers_of_merged_clusters_in_glyph_regions(grouped_cluster_rects);
         centers_of_groups.into_iter().map(|center| {
             if let Some(center) = center {
-                let glyph_image = Self::crop_glyph_at_center(&rectified_image, center, symcode_config);
-                if glyph_image.area() < symcode_config.absolute_empty_cluster_threshold(glyph_image.width, glyph_image.height) {
-                    GlyphLabel::Invalid
+                let glyph_image = Self::crop_glyph_at_center(rectified_image, center, symcode_config);
+                let prediction = if glyph_image.area() < symcode_config.params.absolute_empty_cluster_threshold(glyph_image.width, glyph_image.height) {
+                    GlyphPrediction::default()
                 } else {
-                    Self::find_most_similar_glyph(glyph_image, glyph_library, symcode_config)
-                }
+                    Self::classify_glyph(&glyph_image, glyph_library, symcode_config)
+                };
+                let rect = Self::glyph_rect_at_center(center, symcode_config);
+                debugger.render_bounding_rect_to_canvas(&rect);
+                RecognizedGlyph { image: Some(glyph_image), rect: Some(rect), prediction }
             } else {
-                GlyphLabel::Invalid
+                RecognizedGlyph { image: None, rect: None, prediction: GlyphPrediction::default() }
             }
         })
         .collect()
     }
+
+    /// Read the glyphs at the anchors, searching the neighbourhood for the best aligned crop
+    fn read_glyphs_at_anchors(rectified_image: &BinaryImage, search_radius: usize, search_step: usize, glyph_library: &Acute32Library, symcode_config: &Acute32SymcodeConfig, debugger: &dyn Debugger) -> Vec<RecognizedGlyph> {
+        let half_size = PointI32::new((symcode_config.params.symbol_width >> 1) as i32, (symcode_config.params.symbol_height >> 1) as i32);
+        let radius = search_radius as i32;
+        let step = std::cmp::max(search_step, 1);
+
+        symcode_config.params.glyph_anchors.iter().map(|anchor| {
+            let anchor_center = anchor.to_point_i32() + half_size;
+            let mut best: Option<(BoundingRect, RecognizedGlyph)> = None;
+            for dy in (-radius..=radius).step_by(step) {
+                for dx in (-radius..=radius).step_by(step) {
+                    let rect = Self::glyph_rect_at_center(anchor_center + PointI32::new(dx, dy), symcode_config);
+                    let glyph_image = rectified_image.crop_with_rect(rect);
+                    if glyph_image.area() < symcode_config.params.absolute_empty_cluster_threshold(glyph_image.width, glyph_image.height) {
+                        continue;
+                    }
+                    let prediction = Self::classify_glyph(&glyph_image, glyph_library, symcode_config);
+                    if best.as_ref().is_none_or(|(_, glyph)| prediction.score > glyph.prediction.score) {
+                        best = Some((rect, RecognizedGlyph { image: Some(glyph_image), rect: Some(rect), prediction }));
+                    }
+                }
+            }
+            match best {
+                Some((rect, glyph)) => {
+                    debugger.render_bounding_rect_to_canvas(&rect);
+                    glyph
+                },
+                None => {
+                    let rect = Self::glyph_rect_at_center(anchor_center, symcode_config);
+                    RecognizedGlyph {
+                        image: Some(rectified_image.crop_with_rect(rect)),
+                        rect: Some(rect),
+                        prediction: GlyphPrediction::default(),
+                    }
+                },
+            }
+        })
+        .collect()
+    }
+
+    /// Same as `read`, but keeps the intermediate images
+    pub fn read_with_details(&self, raw_frame: ColorImage, image_to_object: PerspectiveTransform) -> RecognizerOutput {
+        let glyph_library = self.config.symbol_library.as_ref();
+        Self::read_glyphs_with_details_from_raw_frame(raw_frame, image_to_object, glyph_library, self.config, self.debugger)
+    }
 }
 
 impl Reader for Acute32Recognizer<'_> {
     type Symbol = GlyphLabel;
 
     fn read(&self, raw_frame: ColorImage, image_to_object: PerspectiveTransform) -> Result<Vec<GlyphLabel>, &'static str> {
-        let glyph_library = self.config.symbol_library.as_ref();
-        let glyphs = Self::read_glyphs_from_raw_frame(raw_frame, image_to_object, glyph_library, self.config);
+        let output = self.read_with_details(raw_frame, image_to_object);
+        output.check_quiet_zone(self.config)?;
+        let glyphs = output.labels();
         //log::error!(&format!("Recognized glyphs: {:?}", glyphs));
         Ok(glyphs)
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use bit_vec::BitVec;
+    use crate::interfaces::{Encoder, SymcodeGenerator};
+    use super::super::{Acute32Encoder, Acute32Generator, Acute32Library};
+    use super::super::test_fixtures::{identity, render, use_synthetic_library};
+    use super::*;
+
+    #[test]
+    fn reader_quiet_zone_policy() {
+        let output = |clear| RecognizerOutput {
+            rectified_image: BinaryImage::new_w_h(1, 1),
+            polarity: Polarity::DarkOnLight,
+            glyphs: vec![],
+            refinement: None,
+            quiet_zone: Some(QuietZoneReport { violation: if clear { 0.0 } else { 0.2 }, clear }),
+        };
+        let mut config = Acute32SymcodeConfig::default();
+        assert!(output(false).check_quiet_zone(&config).is_ok());
+        config.params.quiet_zone_policy = QuietZonePolicy::Reject;
+        assert!(output(false).check_quiet_zone(&config).is_err());
+        assert!(output(true).check_quiet_zone(&config).is_ok());
+    }
+
+    #[test]
+    fn reader_quiet_zone_covering_code_is_not_measured() {
+        let mut config = Acute32SymcodeConfig::default();
+        let frame = render(&BinaryImage::new_w_h(config.params.code_width, config.params.code_height));
+        let measure = |config: &Acute32SymcodeConfig| Acute32Recognizer::measure_quiet_zone(&frame, &identity(config), 128.0, Polarity::DarkOnLight, config);
+        assert_eq!(measure(&config), Some(0.0));
+
+        config.params.quiet_zone_width = std::cmp::min(config.params.code_width, config.params.code_height).div_ceil(2);
+        assert_eq!(measure(&config), None);
+        config.params.quiet_zone_width = config.params.code_width;
+        assert_eq!(measure(&config), None);
+    }
+
+    /// A bar across the middle of the glyph, centered so that the clusters are too
+    fn bar_glyph(config: &Acute32SymcodeConfig, horizontal: bool) -> BinaryImage {
+        let (width, height) = (config.params.symbol_width, config.params.symbol_height);
+        let mut image = BinaryImage::new_w_h(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let across = if horizontal { y } else { x };
+                image.set_pixel(x, y, across >= width / 3 && across < width - width / 3);
+            }
+        }
+        image
+    }
+
+    #[test]
+    fn reader_anchor_sampling_reads_fragmented_offset_glyph() {
+        let mut config = Acute32SymcodeConfig::default();
+        let mut library = Acute32Library::default();
+        library.add_template(bar_glyph(&config, true), &config);
+        library.add_template(bar_glyph(&config, false), &config);
+        let (across, down) = (library.get_glyph_at(0).unwrap().label, library.get_glyph_at(1).unwrap().label);
+        config.symbol_library = std::sync::Arc::new(library);
+        let labels = vec![across, down, across, down, down];
+        let mut code = Acute32Generator::new(&config).generate(vec![GlyphLabel::Invalid, down, across, down, down]);
+
+        // The horizontal bar broken into blocks smaller than a cluster, and printed off its anchor
+        let offset = PointI32::new(8, 6);
+        let top_left = config.params.glyph_anchors[0].to_point_i32() + offset;
+        let bar = bar_glyph(&config, true);
+        for y in 0..bar.height {
+            for x in 0..bar.width {
+                if bar.get_pixel(x, y) && x % 20 < 18 && y % 20 < 18 {
+                    code.set_pixel((top_left.x + x as i32) as usize, (top_left.y + y as i32) as usize, true);
+                }
+            }
+        }
+        let frame = render(&code);
+
+        // No fragment is large enough to be taken as the glyph
+        config.params.reader_mode = ReaderMode::ClusterGrouping;
+        let output = Acute32Recognizer::read_glyphs_with_details_from_raw_frame(frame.clone(), identity(&config), &config.symbol_library, &config, &DummyDebugger);
+        assert_ne!(output.labels()[0], across);
+        assert_eq!(output.labels()[1..], labels[1..]);
+
+        config.params.reader_mode = ReaderMode::AnchorSampling { search_radius: 8, search_step: 2 };
+        let output = Acute32Recognizer::read_glyphs_with_details_from_raw_frame(frame, identity(&config), &config.symbol_library, &config, &DummyDebugger);
+        assert_eq!(output.labels(), labels);
+        // The crop is found where the glyph is printed
+        let rect = output.glyphs[0].rect.unwrap();
+        assert_eq!(PointI32::new(rect.left, rect.top), top_left);
+    }
+
+    #[test]
+    fn reader_refines_perturbed_transform() {
+        let mut config = Acute32SymcodeConfig::default();
+        config.params.reader_mode = ReaderMode::AnchorSampling { search_radius: 0, search_step: 1 };
+        config.params.refine_transform = true;
+        use_synthetic_library(&mut config);
+        let labels = Acute32Encoder::new(&config).encode(BitVec::from_fn(20, |i| i % 3 != 2), config.params.num_glyphs_in_code()).unwrap();
+        let frame = render(&Acute32Generator::new(&config).generate(labels.clone()));
+
+        // The frame is the code itself, but the transform is off by a few pixels at each corner
+        let (w, h) = (config.params.code_width as f64, config.params.code_height as f64);
+        let perturbed = || PerspectiveTransform::from_point_f64(
+            &[PointF64::new(4.0, -3.0), PointF64::new(w - 2.0, 5.0), PointF64::new(w + 3.0, h + 4.0), PointF64::new(-5.0, h - 2.0)],
+            &[PointF64::new(0.0, 0.0), PointF64::new(w, 0.0), PointF64::new(w, h), PointF64::new(0.0, h)],
+        );
+        let (output, refined) = Acute32Recognizer::read_glyphs_with_final_transform(&frame, perturbed(), &config.symbol_library, &config, &DummyDebugger);
+        let report = output.refinement.unwrap();
+        // 5 feature points on each finder and the center of each glyph
+        assert_eq!(report.num_correspondences, 4 * 5 + labels.len());
+        assert!(report.refined_error < report.initial_error / 2.0);
+        assert_eq!(output.labels(), labels);
+
+        // The refined transform is closer to the true one at the finders
+        let truth = identity(&config);
+        for center in config.params.finder_positions.iter() {
+            let error = |transform: &PerspectiveTransform| (transform.transform_inverse(*center) - truth.transform_inverse(*center)).norm();
+            assert!(error(&refined) < error(&perturbed()));
+        }
+    }
+}
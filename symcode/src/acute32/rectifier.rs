@@ -0,0 +1,298 @@
+use visioncortex::{BinaryImage, BoundingRect, ColorImage, PerspectiveTransform, PointF64};
+use super::{LensDistortion, Polarity};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// How the raw frame is sampled when rectifying
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Interpolation {
+    Nearest,
+    #[default]
+    Bilinear,
+    /// Catmull-Rom spline over the 4x4 neighbourhood
+    Bicubic,
+    /// Averages the footprint of the object space pixel when downsampling, bilinear otherwise
+    Area,
+}
+
+/// A grayscale image in the object space.
+///
+/// Pixels which are not sampled, or mapped to out of bound of the raw frame, are unknown.
+pub struct RectifiedImage {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<u8>,
+    /// Set for known pixels
+    known: BinaryImage,
+}
+
+impl RectifiedImage {
+    pub fn new_w_h(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![0; width * height],
+            known: BinaryImage::new_w_h(width, height),
+        }
+    }
+
+    /// None if the pixel is unknown
+    pub fn get_pixel(&self, x: usize, y: usize) -> Option<u8> {
+        if self.known.get_pixel(x, y) {
+            Some(self.pixels[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, value: u8) {
+        self.pixels[y * self.width + x] = value;
+        self.known.set_pixel(x, y, true);
+    }
+
+    /// The mask of known pixels
+    pub fn known_mask(&self) -> &BinaryImage {
+        &self.known
+    }
+
+    /// Fraction of pixels in rect that are unknown, the part of rect out of bound is counted as unknown
+    pub fn unknown_ratio(&self, rect: &BoundingRect) -> f64 {
+        let total = (rect.width().max(0) * rect.height().max(0)) as usize;
+        if total == 0 {
+            return 0.0;
+        }
+        let mut known = 0;
+        for y in rect.top.max(0)..rect.bottom.min(self.height as i32) {
+            for x in rect.left.max(0)..rect.right.min(self.width as i32) {
+                if self.known.get_pixel(x as usize, y as usize) {
+                    known += 1;
+                }
+            }
+        }
+        1.0 - known as f64 / total as f64
+    }
+
+    /// The p-th percentile of the known pixels
+    pub fn percentile(&self, p: usize) -> Option<u8> {
+        let mut histogram = [0usize; 256];
+        let mut count = 0;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if let Some(value) = self.get_pixel(x, y) {
+                    histogram[value as usize] += 1;
+                    count += 1;
+                }
+            }
+        }
+        if count == 0 {
+            return None;
+        }
+        let rank = std::cmp::min(count * p / 100, count - 1);
+        let mut acc = 0;
+        histogram.iter().position(|n| {
+            acc += n;
+            acc > rank
+        }).map(|value| value as u8)
+    }
+
+    /// The midpoint of the 10th and 90th percentiles of the known pixels
+    pub fn threshold(&self) -> Option<u8> {
+        let dark = self.percentile(10)? as usize;
+        let light = self.percentile(90)? as usize;
+        Some(((dark + light) / 2) as u8)
+    }
+
+    /// Binarize by the global threshold, pixels darker than the threshold are set.
+    /// Unknown pixels are never set.
+    pub fn to_binary_image(&self) -> BinaryImage {
+        self.to_binary_image_with_polarity(Polarity::DarkOnLight)
+    }
+
+    /// Binarize by the global threshold, pixels on the ink side of the threshold are set.
+    /// Unknown pixels are never set.
+    pub fn to_binary_image_with_polarity(&self, polarity: Polarity) -> BinaryImage {
+        let mut result = BinaryImage::new_w_h(self.width, self.height);
+        if let Some(threshold) = self.threshold() {
+            for y in 0..self.height {
+                for x in 0..self.width {
+                    if let Some(value) = self.get_pixel(x, y) {
+                        result.set_pixel(x, y, polarity.is_ink(value, threshold));
+                    }
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Rectify the regions (in object space) of the raw frame into a grayscale image of size width x height.
+///
+/// `image_to_object` maps from the undistorted image space if `lens_distortion` is given.
+pub fn rectify_regions(raw_image: &ColorImage, image_to_object: &PerspectiveTransform, lens_distortion: Option<&LensDistortion>, width: usize, height: usize, regions: &[BoundingRect], interpolation: Interpolation) -> RectifiedImage {
+    let to_image = |p: PointF64| {
+        let p = image_to_object.transform_inverse(p);
+        lens_distortion.map_or(p, |lens| lens.distort(p))
+    };
+    let mut rectified_image = RectifiedImage::new_w_h(width, height);
+    for region in regions.iter() {
+        for y in region.top.max(0)..region.bottom.min(height as i32) {
+            for x in region.left.max(0)..region.right.min(width as i32) {
+                let (x, y) = (x as usize, y as usize);
+                if rectified_image.known.get_pixel(x, y) {
+                    continue; // overlapping regions
+                }
+                if let Some(value) = sample_object_pixel(raw_image, &to_image, x, y, interpolation) {
+                    rectified_image.set_pixel(x, y, value);
+                }
+            }
+        }
+    }
+    rectified_image
+}
+
+fn sample_object_pixel(raw_image: &ColorImage, to_image: &impl Fn(PointF64) -> PointF64, x: usize, y: usize, interpolation: Interpolation) -> Option<u8> {
+    let (xf, yf) = (x as f64, y as f64);
+    let point = to_image(PointF64::new(xf, yf));
+    let value = match interpolation {
+        Interpolation::Nearest => sample_nearest(raw_image, point),
+        Interpolation::Bilinear => sample_bilinear(raw_image, point),
+        Interpolation::Bicubic => sample_bicubic(raw_image, point),
+        Interpolation::Area => {
+            // Size of the footprint of one object pixel in the image space
+            let dx = (to_image(PointF64::new(xf + 1.0, yf)) - point).norm();
+            let dy = (to_image(PointF64::new(xf, yf + 1.0)) - point).norm();
+            let n = dx.max(dy).ceil().min(8.0) as usize;
+            if n <= 1 {
+                sample_bilinear(raw_image, point)
+            } else {
+                let mut sum = 0.0;
+                let mut count = 0;
+                for j in 0..n {
+                    for i in 0..n {
+                        let sub_point = PointF64::new(
+                            xf + (i as f64 + 0.5) / n as f64 - 0.5,
+                            yf + (j as f64 + 0.5) / n as f64 - 0.5,
+                        );
+                        if let Some(value) = sample_bilinear(raw_image, to_image(sub_point)) {
+                            sum += value;
+                            count += 1;
+                        }
+                    }
+                }
+                if count > 0 { Some(sum / count as f64) } else { None }
+            }
+        },
+    };
+    value.map(|v| v.round().clamp(0.0, 255.0) as u8)
+}
+
+#[inline]
+fn luminance(image: &ColorImage, x: usize, y: usize) -> f64 {
+    let c = image.get_pixel(x, y);
+    (c.r as u32 + c.g as u32 + c.b as u32) as f64 / 3.0
+}
+
+fn within(image: &ColorImage, point: PointF64) -> bool {
+    0.0 <= point.x && point.x <= (image.width - 1) as f64 &&
+    0.0 <= point.y && point.y <= (image.height - 1) as f64
+}
+
+fn sample_nearest(image: &ColorImage, point: PointF64) -> Option<f64> {
+    if !within(image, point) {
+        return None;
+    }
+    Some(luminance(image, point.x.round() as usize, point.y.round() as usize))
+}
+
+fn sample_bilinear(image: &ColorImage, point: PointF64) -> Option<f64> {
+    if !within(image, point) {
+        return None;
+    }
+    let (x0, y0) = (point.x.floor() as usize, point.y.floor() as usize);
+    let (x1, y1) = ((x0 + 1).min(image.width - 1), (y0 + 1).min(image.height - 1));
+    let (fx, fy) = (point.x - x0 as f64, point.y - y0 as f64);
+    let top = luminance(image, x0, y0) * (1.0 - fx) + luminance(image, x1, y0) * fx;
+    let bottom = luminance(image, x0, y1) * (1.0 - fx) + luminance(image, x1, y1) * fx;
+    Some(top * (1.0 - fy) + bottom * fy)
+}
+
+fn sample_bicubic(image: &ColorImage, point: PointF64) -> Option<f64> {
+    if !within(image, point) {
+        return None;
+    }
+    let (x0, y0) = (point.x.floor() as i32, point.y.floor() as i32);
+    let (fx, fy) = (point.x - x0 as f64, point.y - y0 as f64);
+    let clamp_x = |x: i32| x.clamp(0, image.width as i32 - 1) as usize;
+    let clamp_y = |y: i32| y.clamp(0, image.height as i32 - 1) as usize;
+
+    let mut rows = [0.0; 4];
+    for (j, row) in rows.iter_mut().enumerate() {
+        let y = clamp_y(y0 + j as i32 - 1);
+        let mut p = [0.0; 4];
+        for (i, value) in p.iter_mut().enumerate() {
+            *value = luminance(image, clamp_x(x0 + i as i32 - 1), y);
+        }
+        *row = catmull_rom(p[0], p[1], p[2], p[3], fx);
+    }
+    Some(catmull_rom(rows[0], rows[1], rows[2], rows[3], fy))
+}
+
+/// Interpolates between p1 and p2
+fn catmull_rom(p0: f64, p1: f64, p2: f64, p3: f64, t: f64) -> f64 {
+    p1 + 0.5 * t * (p2 - p0 + t * (2.0*p0 - 5.0*p1 + 4.0*p2 - p3 + t * (3.0*(p1 - p2) + p3 - p0)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rectifier_catmull_rom_endpoints() {
+        assert!((catmull_rom(1.0, 2.0, 3.0, 4.0, 0.0) - 2.0).abs() < 1e-9);
+        assert!((catmull_rom(1.0, 2.0, 3.0, 4.0, 1.0) - 3.0).abs() < 1e-9);
+        // Linear data is reproduced exactly
+        assert!((catmull_rom(1.0, 2.0, 3.0, 4.0, 0.25) - 2.25).abs() < 1e-9);
+    }
+
+    #[test]
+    fn rectifier_bicubic_on_gradient() {
+        let mut image = ColorImage::new_w_h(8, 8);
+        for y in 0..8 {
+            for x in 0..8 {
+                let value = (10 * x + 20 * y) as u8;
+                image.set_pixel(x, y, &visioncortex::Color::new(value, value, value));
+            }
+        }
+        let sample = |x, y| sample_bicubic(&image, PointF64::new(x, y)).unwrap();
+        assert!((sample(3.25, 2.5) - 82.5).abs() < 1e-9);
+        // The neighbourhood is clamped at the border
+        assert!((sample(0.0, 0.0) - 0.0).abs() < 1e-9);
+        assert_eq!(sample_bicubic(&image, PointF64::new(7.5, 0.0)), None);
+    }
+
+    #[test]
+    fn rectified_image_unknown_and_threshold() {
+        let mut image = RectifiedImage::new_w_h(4, 2);
+        assert_eq!(image.threshold(), None);
+        for x in 0..4 {
+            image.set_pixel(x, 0, if x < 2 { 0 } else { 200 });
+        }
+        assert_eq!(image.get_pixel(0, 1), None);
+        assert_eq!(image.unknown_ratio(&BoundingRect::new_x_y_w_h(0, 0, 4, 2)), 0.5);
+        assert_eq!(image.unknown_ratio(&BoundingRect::new_x_y_w_h(2, 0, 4, 1)), 0.5);
+        assert_eq!(image.threshold(), Some(100));
+        assert_eq!(image.percentile(0), Some(0));
+        assert_eq!(image.percentile(100), Some(200));
+
+        let binary = image.to_binary_image();
+        assert!(binary.get_pixel(0, 0) && binary.get_pixel(1, 0));
+        assert!(!binary.get_pixel(2, 0) && !binary.get_pixel(3, 0));
+        // unknown pixels are not ink even if they were dark
+        assert!(!binary.get_pixel(0, 1));
+
+        let inverted = image.to_binary_image_with_polarity(Polarity::LightOnDark);
+        assert!(!inverted.get_pixel(0, 0) && inverted.get_pixel(2, 0));
+        assert!(!inverted.get_pixel(0, 1));
+    }
+}
@@ -0,0 +1,323 @@
+use visioncortex::{PerspectiveTransform, PointF64};
+
+/// A 3x3 projective transform in row-major order
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Homography {
+    pub m: [f64; 9],
+}
+
+/// How much the refinement reduced the reprojection error, in pixels of the object space
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RefinementReport {
+    pub num_correspondences: usize,
+    /// RMS error of the correspondences under the initial transform
+    pub initial_error: f64,
+    /// RMS error of the correspondences under the refined transform
+    pub refined_error: f64,
+}
+
+impl Homography {
+    const IDENTITY: [f64; 9] = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0];
+    const MAX_ITERATIONS: usize = 20;
+
+    pub fn identity() -> Self {
+        Self { m: Self::IDENTITY }
+    }
+
+    pub fn transform(&self, p: PointF64) -> PointF64 {
+        let m = &self.m;
+        let w = m[6] * p.x + m[7] * p.y + m[8];
+        PointF64::new(
+            (m[0] * p.x + m[1] * p.y + m[2]) / w,
+            (m[3] * p.x + m[4] * p.y + m[5]) / w,
+        )
+    }
+
+    pub fn inverse(&self) -> Option<Self> {
+        let m = &self.m;
+        let cofactors = [
+            m[4] * m[8] - m[5] * m[7], m[2] * m[7] - m[1] * m[8], m[1] * m[5] - m[2] * m[4],
+            m[5] * m[6] - m[3] * m[8], m[0] * m[8] - m[2] * m[6], m[2] * m[3] - m[0] * m[5],
+            m[3] * m[7] - m[4] * m[6], m[1] * m[6] - m[0] * m[7], m[0] * m[4] - m[1] * m[3],
+        ];
+        let det = m[0] * cofactors[0] + m[1] * cofactors[3] + m[2] * cofactors[6];
+        if det.abs() < std::f64::EPSILON {
+            return None;
+        }
+        let mut result = [0.0; 9];
+        result.iter_mut().zip(cofactors.iter()).for_each(|(r, c)| *r = c / det);
+        Some(Self { m: result })
+    }
+
+    /// self after other
+    fn compose(&self, other: &Self) -> Self {
+        let (a, b) = (&self.m, &other.m);
+        let mut m = [0.0; 9];
+        for row in 0..3 {
+            for col in 0..3 {
+                m[row * 3 + col] = (0..3).map(|k| a[row * 3 + k] * b[k * 3 + col]).sum();
+            }
+        }
+        Self { m }
+    }
+
+    fn normalized(mut self) -> Self {
+        let scale = self.m[8];
+        if scale.abs() > std::f64::EPSILON {
+            self.m.iter_mut().for_each(|v| *v /= scale);
+        }
+        self
+    }
+
+    /// Root mean square distance between the transformed source points and the destination points
+    pub fn rms_error(&self, correspondences: &[(PointF64, PointF64)]) -> f64 {
+        rms_error(correspondences.iter().map(|(src, dst)| (self.transform(*src), *dst)))
+    }
+
+    /// Estimates the transform mapping the source points to the destination points, minimizing
+    /// the reprojection error in the destination space. At least 4 correspondences are needed.
+    ///
+    /// The linear estimate is refined by Levenberg-Marquardt iterations.
+    pub fn from_correspondences(correspondences: &[(PointF64, PointF64)]) -> Option<Self> {
+        if correspondences.len() < 4 {
+            return None;
+        }
+        // Condition the problem by normalizing both point sets
+        let src_norm = Self::normalizing_transform(correspondences.iter().map(|(src, _)| *src))?;
+        let dst_norm = Self::normalizing_transform(correspondences.iter().map(|(_, dst)| *dst))?;
+        let normalized: Vec<(PointF64, PointF64)> = correspondences.iter()
+            .map(|(src, dst)| (src_norm.transform(*src), dst_norm.transform(*dst)))
+            .collect();
+
+        let mut h = Self::direct_linear_transform(&normalized)?;
+        h.levenberg_marquardt(&normalized);
+
+        Some(dst_norm.inverse()?.compose(&h).compose(&src_norm).normalized())
+    }
+
+    /// Translates the centroid to the origin and scales the mean distance from it to sqrt(2)
+    fn normalizing_transform(points: impl Iterator<Item = PointF64> + Clone) -> Option<Self> {
+        let n = points.clone().count() as f64;
+        let centroid = points.clone().fold(PointF64::new(0.0, 0.0), |acc, p| acc + p);
+        let (cx, cy) = (centroid.x / n, centroid.y / n);
+        let mean_dist = points.map(|p| (p - PointF64::new(cx, cy)).norm()).sum::<f64>() / n;
+        if mean_dist < std::f64::EPSILON {
+            return None;
+        }
+        let s = std::f64::consts::SQRT_2 / mean_dist;
+        Some(Self { m: [s, 0.0, -s * cx, 0.0, s, -s * cy, 0.0, 0.0, 1.0] })
+    }
+
+    /// Least squares solution of the linear equations with the last element fixed to 1
+    fn direct_linear_transform(correspondences: &[(PointF64, PointF64)]) -> Option<Self> {
+        let mut ata = [[0.0; 8]; 8];
+        let mut atb = [0.0; 8];
+        for (src, dst) in correspondences.iter() {
+            let (x, y, u, v) = (src.x, src.y, dst.x, dst.y);
+            let rows = [
+                ([x, y, 1.0, 0.0, 0.0, 0.0, -x * u, -y * u], u),
+                ([0.0, 0.0, 0.0, x, y, 1.0, -x * v, -y * v], v),
+            ];
+            for (a, b) in rows.iter() {
+                for i in 0..8 {
+                    atb[i] += a[i] * b;
+                    for j in 0..8 {
+                        ata[i][j] += a[i] * a[j];
+                    }
+                }
+            }
+        }
+        let h = solve_linear_system(ata, atb)?;
+        Some(Self { m: [h[0], h[1], h[2], h[3], h[4], h[5], h[6], h[7], 1.0] })
+    }
+
+    /// Minimizes the geometric reprojection error, with the last element fixed to 1
+    fn levenberg_marquardt(&mut self, correspondences: &[(PointF64, PointF64)]) {
+        let cost = |h: &Self| correspondences.iter()
+            .map(|(src, dst)| {
+                let p = h.transform(*src);
+                (p.x - dst.x).powi(2) + (p.y - dst.y).powi(2)
+            })
+            .sum::<f64>();
+
+        let mut lambda = 1e-3;
+        let mut current_cost = cost(self);
+        for _ in 0..Self::MAX_ITERATIONS {
+            let mut jtj = [[0.0; 8]; 8];
+            let mut jtr = [0.0; 8];
+            for (src, dst) in correspondences.iter() {
+                let (x, y) = (src.x, src.y);
+                let m = &self.m;
+                let w = m[6] * x + m[7] * y + 1.0;
+                let p = self.transform(*src);
+                let rows = [
+                    ([x / w, y / w, 1.0 / w, 0.0, 0.0, 0.0, -x * p.x / w, -y * p.x / w], p.x - dst.x),
+                    ([0.0, 0.0, 0.0, x / w, y / w, 1.0 / w, -x * p.y / w, -y * p.y / w], p.y - dst.y),
+                ];
+                for (j, r) in rows.iter() {
+                    for a in 0..8 {
+                        jtr[a] += j[a] * r;
+                        for b in 0..8 {
+                            jtj[a][b] += j[a] * j[b];
+                        }
+                    }
+                }
+            }
+
+            let mut improved = false;
+            while lambda < 1e10 {
+                let mut damped = jtj;
+                (0..8).for_each(|i| damped[i][i] += lambda * jtj[i][i].max(std::f64::EPSILON));
+                let delta = match solve_linear_system(damped, jtr) {
+                    Some(delta) => delta,
+                    None => break,
+                };
+                let mut candidate = *self;
+                (0..8).for_each(|i| candidate.m[i] -= delta[i]);
+                let candidate_cost = cost(&candidate);
+                if candidate_cost < current_cost {
+                    let converged = current_cost - candidate_cost < 1e-12 * current_cost.max(1.0);
+                    *self = candidate;
+                    current_cost = candidate_cost;
+                    lambda = (lambda / 10.0).max(1e-12);
+                    improved = !converged;
+                    break;
+                }
+                lambda *= 10.0;
+            }
+            if !improved {
+                break;
+            }
+        }
+    }
+}
+
+/// Root mean square distance of the point pairs
+pub(crate) fn rms_error(pairs: impl Iterator<Item = (PointF64, PointF64)>) -> f64 {
+    let (sum, count) = pairs.fold((0.0, 0), |(sum, count), (a, b)| {
+        (sum + (a.x - b.x).powi(2) + (a.y - b.y).powi(2), count + 1)
+    });
+    if count == 0 { 0.0 } else { (sum / count as f64).sqrt() }
+}
+
+/// Gaussian elimination with partial pivoting. None if the system is singular or not finite.
+fn solve_linear_system(mut a: [[f64; 8]; 8], mut b: [f64; 8]) -> Option<[f64; 8]> {
+    const N: usize = 8;
+    if a.iter().flatten().chain(b.iter()).any(|value| !value.is_finite()) {
+        return None;
+    }
+    for col in 0..N {
+        let pivot = (col..N).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        if a[pivot][col].abs() < 1e-12 {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+        for row in (col + 1)..N {
+            let pivot_row = a[col];
+            let factor = a[row][col] / pivot_row[col];
+            for (value, pivot_value) in a[row][col..].iter_mut().zip(pivot_row[col..].iter()) {
+                *value -= factor * pivot_value;
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+    let mut x = [0.0; N];
+    for row in (0..N).rev() {
+        let sum: f64 = ((row + 1)..N).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
+}
+
+/// Refines the image to object transform with correspondences observed in the rectified (object) space.
+///
+/// `observed` are feature points located on the image rectified by `image_to_object`, and `expected`
+/// are where they should be in the object space. Returns None if there are too few correspondences
+/// or the refinement does not reduce the error.
+pub fn refine_transform(image_to_object: &PerspectiveTransform, observed: &[PointF64], expected: &[PointF64], finder_positions: &[PointF64]) -> Option<(PerspectiveTransform, RefinementReport)> {
+    // Need more than the 4 finder centers to be over-determined
+    if observed.len() != expected.len() || observed.len() < 8 || finder_positions.len() < 4 {
+        return None;
+    }
+    let correspondences: Vec<(PointF64, PointF64)> = observed.iter().zip(expected.iter())
+        .map(|(obs, exp)| (image_to_object.transform_inverse(*obs), *exp))
+        .collect();
+    let initial_error = rms_error(observed.iter().copied().zip(expected.iter().copied()));
+
+    let homography = Homography::from_correspondences(&correspondences)?;
+    let refined_error = homography.rms_error(&correspondences);
+    if !refined_error.is_finite() || refined_error >= initial_error {
+        return None;
+    }
+
+    // Rebuild the transform from the finder positions mapped back to the image space
+    let object_to_image = homography.inverse()?;
+    let image_points: Vec<PointF64> = finder_positions.iter().take(4).map(|p| object_to_image.transform(*p)).collect();
+    let transform = PerspectiveTransform::from_point_f64(&image_points, &finder_positions[0..4]);
+
+    Some((transform, RefinementReport { num_correspondences: correspondences.len(), initial_error, refined_error }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ground_truth() -> Homography {
+        Homography { m: [1.2, 0.1, 30.0, -0.05, 0.9, 12.0, 0.0004, -0.0002, 1.0] }
+    }
+
+    fn grid() -> Vec<PointF64> {
+        (0..4).flat_map(|j| (0..4).map(move |i| PointF64::new(i as f64 * 100.0 + 20.0, j as f64 * 80.0 + 10.0))).collect()
+    }
+
+    #[test]
+    fn refiner_recovers_exact_homography() {
+        let h = ground_truth();
+        let correspondences: Vec<(PointF64, PointF64)> = grid().into_iter().map(|p| (p, h.transform(p))).collect();
+        let estimate = Homography::from_correspondences(&correspondences).unwrap();
+        estimate.m.iter().zip(h.m.iter()).for_each(|(a, b)| assert!((a - b).abs() < 1e-6, "{:?}", estimate));
+        assert!(estimate.rms_error(&correspondences) < 1e-6);
+    }
+
+    #[test]
+    fn refiner_least_squares_with_noise() {
+        let h = ground_truth();
+        // Deterministic perturbation of up to half a pixel
+        let correspondences: Vec<(PointF64, PointF64)> = grid().into_iter().enumerate()
+            .map(|(i, p)| {
+                let q = h.transform(p);
+                let noise = PointF64::new(((i * 7) % 5) as f64 * 0.25 - 0.5, ((i * 3) % 5) as f64 * 0.25 - 0.5);
+                (p, q + noise)
+            })
+            .collect();
+        let estimate = Homography::from_correspondences(&correspondences).unwrap();
+        assert!(estimate.rms_error(&correspondences) <= h.rms_error(&correspondences));
+        assert!((estimate.transform(PointF64::new(150.0, 150.0)) - h.transform(PointF64::new(150.0, 150.0))).norm() < 1.0);
+    }
+
+    #[test]
+    fn refiner_inverse() {
+        let h = ground_truth();
+        let inverse = h.inverse().unwrap();
+        let p = PointF64::new(37.0, 91.0);
+        assert!((inverse.transform(h.transform(p)) - p).norm() < 1e-9);
+        assert!(Homography { m: [0.0; 9] }.inverse().is_none());
+        assert_eq!(Homography::identity().transform(p), p);
+    }
+
+    #[test]
+    fn refiner_degenerate_input() {
+        let p = PointF64::new(1.0, 1.0);
+        assert!(Homography::from_correspondences(&[(p, p); 3]).is_none());
+        assert!(Homography::from_correspondences(&[(p, p); 6]).is_none());
+    }
+
+    #[test]
+    fn refiner_non_finite_input() {
+        let h = ground_truth();
+        let mut correspondences: Vec<(PointF64, PointF64)> = grid().into_iter().map(|p| (p, h.transform(p))).collect();
+        correspondences[5].1.x = f64::NAN;
+        assert!(Homography::from_correspondences(&correspondences).is_none());
+    }
+}
@@ -0,0 +1,130 @@
+use std::sync::Arc;
+use bit_vec::BitVec;
+use visioncortex::ColorImage;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use crate::interfaces::{Decoder, DummyDebugger, Finder, FinderElement, Reader, SymcodeScanner};
+use super::{Acute32, Acute32Library, Acute32SymcodeConfig, Acute32SymcodeParams, Acute32TransformFitter, CircleFinder, GlyphClassifier, GlyphLabel};
+
+/// The whole pipeline, from a frame to the payload, owning its params and sharing its library.
+///
+/// Unlike the stages, which borrow an `Acute32SymcodeConfig` and a (possibly thread-bound) debugger,
+/// a scanner is `Send + Sync`, so that it can be put in an `Arc` and decode many frames at once.
+/// The stages run without a debugger.
+pub struct Acute32Scanner {
+    params: Acute32SymcodeParams,
+    symbol_library: Arc<Acute32Library>,
+    glyph_classifier: Option<Arc<dyn GlyphClassifier + Send + Sync>>,
+    finder: Arc<dyn FinderElement + Send + Sync>,
+}
+
+impl Acute32Scanner {
+    /// With circle finders and no classifier. The params are validated
+    pub fn new(params: Acute32SymcodeParams, symbol_library: Arc<Acute32Library>) -> Result<Self, &'static str> {
+        params.validate()?;
+        Ok(Self {
+            params,
+            symbol_library,
+            glyph_classifier: None,
+            finder: Arc::new(CircleFinder),
+        })
+    }
+
+    /// Takes over the params, library, classifier and finder of the config
+    pub fn from_config(config: Acute32SymcodeConfig) -> Result<Self, &'static str> {
+        let Acute32SymcodeConfig { params, symbol_library, glyph_classifier, finder, .. } = config;
+        Ok(Self { glyph_classifier, finder, ..Self::new(params, symbol_library)? })
+    }
+
+    pub fn glyph_classifier(mut self, glyph_classifier: Arc<dyn GlyphClassifier + Send + Sync>) -> Self {
+        self.glyph_classifier = Some(glyph_classifier);
+        self
+    }
+
+    pub fn finder(mut self, finder: Arc<dyn FinderElement + Send + Sync>) -> Self {
+        self.finder = finder;
+        self
+    }
+
+    pub fn params(&self) -> &Acute32SymcodeParams {
+        &self.params
+    }
+
+    pub fn symbol_library(&self) -> &Acute32Library {
+        &self.symbol_library
+    }
+
+    /// A config for the stages, sharing the library, classifier and finder of the scanner
+    pub fn to_config(&self) -> Acute32SymcodeConfig {
+        Acute32SymcodeConfig {
+            params: self.params.clone(),
+            symbol_library: Arc::clone(&self.symbol_library),
+            glyph_classifier: self.glyph_classifier.clone(),
+            finder: Arc::clone(&self.finder),
+        }
+    }
+
+    fn scan_with_config(image: ColorImage, config: &Acute32SymcodeConfig) -> Result<Vec<GlyphLabel>, &'static str> {
+        if config.symbol_library.is_empty() && config.glyph_classifier.is_none() {
+            return Err("Acute32Scanner: No templates loaded into the library.");
+        }
+        let acute32 = Acute32::new(config);
+        let finder_positions = acute32.get_finder().find(&image)?;
+        let image_to_object = Acute32TransformFitter::fit_transform(image.width, image.height, finder_positions, config, &DummyDebugger)?;
+        acute32.get_reader().read(image, image_to_object)
+    }
+
+    /// Scans and decodes every frame, concurrently with the `rayon` feature.
+    /// The results are in the order of the frames
+    pub fn scan_and_decode_batch(&self, images: Vec<ColorImage>) -> Vec<Result<BitVec, &'static str>> {
+        #[cfg(feature = "rayon")]
+        let images = images.into_par_iter();
+        #[cfg(not(feature = "rayon"))]
+        let images = images.into_iter();
+
+        images.map(|image| self.scan_and_decode(image)).collect()
+    }
+}
+
+impl SymcodeScanner for Acute32Scanner {
+    type SymcodeRepresentation = Vec<GlyphLabel>;
+
+    type Err = &'static str;
+
+    fn scan(&self, image: ColorImage) -> Result<Self::SymcodeRepresentation, Self::Err> {
+        Self::scan_with_config(image, &self.to_config())
+    }
+
+    fn decode(&self, symcode: Self::SymcodeRepresentation) -> Result<BitVec, Self::Err> {
+        Acute32::new(&self.to_config()).get_decoder().decode(symcode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn scanner_shared_across_threads() {
+        assert_send_sync::<Acute32Scanner>();
+
+        let mut params = Acute32SymcodeParams::default();
+        assert!(Acute32Scanner::new(Acute32SymcodeParams { stat_tolerance: 2.0, ..params.clone() }, Arc::default()).is_err());
+        params.max_encoding_difference = 2;
+        let scanner = Arc::new(Acute32Scanner::new(params, Arc::default()).unwrap());
+        assert_eq!(scanner.to_config().params.max_encoding_difference, 2);
+
+        let handles: Vec<_> = (0..2).map(|_| {
+            let scanner = Arc::clone(&scanner);
+            std::thread::spawn(move || scanner.scan_and_decode_batch(vec![ColorImage::new_w_h(64, 64); 3]))
+        }).collect();
+        for handle in handles {
+            let results = handle.join().unwrap();
+            // One result per frame, and nothing can be read with an empty library
+            assert_eq!(results.len(), 3);
+            assert!(results.iter().all(|result| result.is_err()));
+        }
+    }
+}
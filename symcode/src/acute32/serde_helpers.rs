@@ -0,0 +1,42 @@
+//! (De)serialization of the visioncortex types in the params, which do not implement serde
+
+pub(crate) mod points {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use visioncortex::PointF64;
+
+    pub fn serialize<S: Serializer>(points: &[PointF64], serializer: S) -> Result<S::Ok, S::Error> {
+        points.iter().map(|p| (p.x, p.y)).collect::<Vec<(f64, f64)>>().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<PointF64>, D::Error> {
+        Ok(Vec::<(f64, f64)>::deserialize(deserializer)?.into_iter().map(|(x, y)| PointF64::new(x, y)).collect())
+    }
+}
+
+/// Colors as [r, g, b]
+pub(crate) mod color {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use visioncortex::Color;
+
+    pub fn serialize<S: Serializer>(color: &Color, serializer: S) -> Result<S::Ok, S::Error> {
+        [color.r, color.g, color.b].serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Color, D::Error> {
+        let [r, g, b] = <[u8; 3]>::deserialize(deserializer)?;
+        Ok(Color::new(r, g, b))
+    }
+}
+
+pub(crate) mod colors {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use visioncortex::Color;
+
+    pub fn serialize<S: Serializer>(colors: &[Color], serializer: S) -> Result<S::Ok, S::Error> {
+        colors.iter().map(|c| [c.r, c.g, c.b]).collect::<Vec<[u8; 3]>>().serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Color>, D::Error> {
+        Ok(Vec::<[u8; 3]>::deserialize(deserializer)?.into_iter().map(|[r, g, b]| Color::new(r, g, b)).collect())
+    }
+}
@@ -1,11 +1,18 @@
+use std::sync::Arc;
+use bit_vec::BitVec;
 use visioncortex::PointF64;
-use crate::interfaces::{Debugger, DummyDebugger};
-use super::{Acute32Library, CircleFinder};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use crate::interfaces::FinderElement;
+use super::{Acute32Layout, Acute32Library, CircleFinder, GlyphClassifier, InkPalette, Interpolation, LabelBitMapping, LensDistortion, Polarity, QuietZonePolicy, ReaderMode};
 
-pub struct Acute32SymcodeConfig {
-    pub symbol_library: Box<Acute32Library>, // To be referenced in RecognizerInput
-    pub finder: CircleFinder,
-    
+/// The tunables of the scanner and the layout of the code, as plain data.
+///
+/// With the `serde` feature, a set of params can be kept as a versioned profile (e.g. in JSON or TOML),
+/// and combined with a library into an `Acute32SymcodeConfig` at runtime.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]
+pub struct Acute32SymcodeParams {
     pub code_width: usize,
     pub code_height: usize,
 
@@ -13,8 +20,10 @@ pub struct Acute32SymcodeConfig {
     pub symbol_height: usize,
 
     /// The centers of the finders
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_helpers::points"))]
     pub finder_positions: Vec<PointF64>,
     /// The top-left corners of the glyphs
+    #[cfg_attr(feature = "serde", serde(with = "super::serde_helpers::points"))]
     pub glyph_anchors: Vec<PointF64>,
 
     pub max_extra_finder_candidates: usize,
@@ -23,11 +32,32 @@ pub struct Acute32SymcodeConfig {
     pub max_encoding_difference: usize,
     pub empty_cluster_threshold: f64,
     pub quiet_zone_width: usize,
-
-    pub debugger: Box<dyn Debugger>,
+    pub quiet_zone_policy: QuietZonePolicy,
+    /// Largest fraction of the quiet zone that may be dark or out of the frame
+    pub max_quiet_zone_violation: f64,
+    /// If set, finder positions are undistorted before fitting and the frame is sampled through the lens model
+    pub lens_distortion: Option<LensDistortion>,
+    /// Whether the ink is darker or lighter than the background, detected on each frame if None
+    pub polarity: Option<Polarity>,
+    /// The inks of the colored variant, read and written by the Acute32Color* stages. Unused by the monochrome ones
+    pub ink_palette: Option<InkPalette>,
+    pub reader_mode: ReaderMode,
+    pub interpolation: Interpolation,
+    /// Glyphs with a larger fraction of pixels mapped to out of bound of the frame are not read
+    pub max_unknown_ratio: f64,
+    /// Refine the transform with all finder and glyph correspondences after the first read
+    pub refine_transform: bool,
+    /// If the checksum fails, the decoder flips back a single wrong bit when only one bit explains it.
+    /// Some errors of more bits are then taken for a single bit error and decoded wrongly
+    pub correct_single_bit_errors: bool,
+    /// Checksum the payload padded to whole bytes, as symcode 0.1 did, to read and write the codes printed by it.
+    /// Single bit errors are not corrected then
+    pub legacy_checksum: bool,
+    /// The bits carried by each glyph label in the code
+    pub label_bit_mapping: LabelBitMapping,
 }
 
-impl Default for Acute32SymcodeConfig {
+impl Default for Acute32SymcodeParams {
     fn default() -> Self {
         Self {
             code_width: 785,
@@ -52,15 +82,24 @@ impl Default for Acute32SymcodeConfig {
             stat_tolerance: 0.36,
             max_encoding_difference: 3,
             empty_cluster_threshold: 0.15,
-            symbol_library: Box::new(Acute32Library::default()),
-            finder: CircleFinder::default(),
             quiet_zone_width: 10,
-            debugger: Box::new(DummyDebugger),
+            quiet_zone_policy: QuietZonePolicy::default(),
+            max_quiet_zone_violation: 0.05,
+            lens_distortion: None,
+            polarity: Some(Polarity::DarkOnLight),
+            ink_palette: None,
+            reader_mode: ReaderMode::default(),
+            interpolation: Interpolation::default(),
+            max_unknown_ratio: 0.1,
+            refine_transform: false,
+            correct_single_bit_errors: false,
+            legacy_checksum: false,
+            label_bit_mapping: LabelBitMapping::default(),
         }
     }
 }
 
-impl Acute32SymcodeConfig {
+impl Acute32SymcodeParams {
     #[inline]
     pub fn max_finder_candidates(&self) -> usize {
         self.finder_positions.len() + self.max_extra_finder_candidates
@@ -75,4 +114,113 @@ impl Acute32SymcodeConfig {
     pub fn num_glyphs_in_code(&self) -> usize {
         self.glyph_anchors.len()
     }
+
+    /// The CRC5 checksum of the payload, see `legacy_checksum`
+    pub fn payload_checksum(&self, payload: &BitVec) -> u8 {
+        if self.legacy_checksum {
+            crate::math::crc5_of_padded_bytes(payload)
+        } else {
+            crate::math::crc5_of_bits(payload)
+        }
+    }
+
+    /// Checks the layout (see `Acute32Layout::validate`) and the ranges of the tunables,
+    /// e.g. after loading a profile
+    pub fn validate(&self) -> Result<(), &'static str> {
+        Acute32Layout::from_params(self).validate()?;
+
+        let fraction = |value: f64| (0.0..=1.0).contains(&value);
+        let positive = |value: f64| value > 0.0;
+        if !positive(self.rectify_error_threshold) {
+            return Err("Params error: rectify_error_threshold must be positive.");
+        }
+        if !fraction(self.stat_tolerance) || !fraction(self.empty_cluster_threshold) ||
+            !fraction(self.max_quiet_zone_violation) || !fraction(self.max_unknown_ratio) {
+            return Err("Params error: A ratio is not within 0 and 1.");
+        }
+        if let ReaderMode::AnchorSampling { search_radius, search_step } = self.reader_mode {
+            if search_radius > 0 && search_step == 0 {
+                return Err("Params error: search_step must be positive.");
+            }
+        }
+        if let Some(lens_distortion) = &self.lens_distortion {
+            if !positive(lens_distortion.fx) || !positive(lens_distortion.fy) {
+                return Err("Params error: The focal lengths of the lens must be positive.");
+            }
+        }
+        if let Some(ink_palette) = &self.ink_palette {
+            ink_palette.validate()?;
+        }
+        self.label_bit_mapping.validate()?;
+        Ok(())
+    }
+}
+
+/// The params and the runtime parts of the scanner, which cannot be serialized.
+///
+/// The library, classifier and finder are shared, so that a config is cheap to clone and can be handed to an `Acute32Scanner`.
+/// A debugger is given to the stages separately, see `Acute32::debugger`.
+#[derive(Clone)]
+pub struct Acute32SymcodeConfig {
+    pub params: Acute32SymcodeParams,
+
+    pub symbol_library: Arc<Acute32Library>, // To be referenced in RecognizerInput
+    /// Classifies the glyphs in place of template matching with symbol_library, if set
+    pub glyph_classifier: Option<Arc<dyn GlyphClassifier + Send + Sync>>,
+    /// The shape of the finders, which should not be mistaken for anything else in the scene
+    pub finder: Arc<dyn FinderElement + Send + Sync>,
+}
+
+impl Default for Acute32SymcodeConfig {
+    fn default() -> Self {
+        Self::new(Acute32SymcodeParams::default(), Arc::new(Acute32Library::default()))
+    }
+}
+
+impl Acute32SymcodeConfig {
+    /// With circle finders and no classifier
+    pub fn new(params: Acute32SymcodeParams, symbol_library: Arc<Acute32Library>) -> Self {
+        Self {
+            params,
+            symbol_library,
+            glyph_classifier: None,
+            finder: Arc::new(CircleFinder),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn params_validate() {
+        let params = Acute32SymcodeParams::default();
+        assert_eq!(params.validate(), Ok(()));
+        assert!(Acute32SymcodeParams { stat_tolerance: 1.5, ..params.clone() }.validate().is_err());
+        assert!(Acute32SymcodeParams { rectify_error_threshold: 0.0, ..params.clone() }.validate().is_err());
+        assert!(Acute32SymcodeParams {
+            reader_mode: ReaderMode::AnchorSampling { search_radius: 4, search_step: 0 },
+            ..params.clone()
+        }.validate().is_err());
+        assert!(Acute32SymcodeParams { glyph_anchors: vec![], ..params }.validate().is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn params_serde_round_trip() {
+        let params = Acute32SymcodeParams {
+            lens_distortion: Some(LensDistortion::new(300.0, 300.0, 200.0, 200.0).radial(-0.25, 0.05, 0.0)),
+            polarity: None,
+            ink_palette: Some(InkPalette::default()),
+            reader_mode: ReaderMode::AnchorSampling { search_radius: 4, search_step: 2 },
+            ..Default::default()
+        };
+        let json = serde_json::to_string(&params).unwrap();
+        assert_eq!(serde_json::from_str::<Acute32SymcodeParams>(&json).unwrap(), params);
+
+        // Missing fields take the defaults
+        let partial: Acute32SymcodeParams = serde_json::from_str(r#"{"stat_tolerance": 0.3}"#).unwrap();
+        assert_eq!(partial, Acute32SymcodeParams { stat_tolerance: 0.3, ..Default::default() });
+    }
 }
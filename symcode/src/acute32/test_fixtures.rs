@@ -0,0 +1,69 @@
+//! Synthetic glyphs and frames shared by the tests
+
+use visioncortex::{BinaryImage, Color, ColorImage, PerspectiveTransform, PointF64};
+use super::{Acute32Library, Acute32SymcodeConfig, GlyphLabel};
+
+/// One half of the glyph is set
+pub fn half_glyph(config: &Acute32SymcodeConfig, left_half: bool) -> BinaryImage {
+    let mut image = BinaryImage::new_w_h(config.params.symbol_width, config.params.symbol_height);
+    for y in 0..config.params.symbol_height {
+        for x in 0..config.params.symbol_width {
+            image.set_pixel(x, y, (x < config.params.symbol_width / 2) == left_half);
+        }
+    }
+    image
+}
+
+/// Sets a library of the left and right half glyphs, returning their labels
+pub fn use_half_glyph_library(config: &mut Acute32SymcodeConfig) -> (GlyphLabel, GlyphLabel) {
+    let mut library = Acute32Library::default();
+    library.add_template(half_glyph(config, true), config);
+    library.add_template(half_glyph(config, false), config);
+    let labels = (library.get_glyph_at(0).unwrap().label, library.get_glyph_at(1).unwrap().label);
+    config.symbol_library = std::sync::Arc::new(library);
+    labels
+}
+
+/// A distinct blocky pattern for each label, each bit picks one of two cells in a 4x4 grid.
+///
+/// The cells are L-shaped, as a lone square cell would pass for a circle finder
+pub fn synthetic_glyph(label: usize, config: &Acute32SymcodeConfig) -> BinaryImage {
+    let mut image = BinaryImage::new_w_h(config.params.symbol_width, config.params.symbol_height);
+    let cell = config.params.symbol_width / 4;
+    for bit in 0..5 {
+        let index = if label >> bit & 1 == 1 { bit } else { bit + 5 };
+        let (cx, cy) = (index % 4, index / 4);
+        for y in cy * cell..(cy + 1) * cell {
+            for x in cx * cell..(cx + 1) * cell {
+                image.set_pixel(x, y, x - cx * cell < cell / 2 || y - cy * cell < cell / 2);
+            }
+        }
+    }
+    image
+}
+
+/// Sets a library of a synthetic glyph for every label, so that any encoded code can be generated
+pub fn use_synthetic_library(config: &mut Acute32SymcodeConfig) {
+    let mut library = Acute32Library::default();
+    (0..GlyphLabel::num_variants()).for_each(|label| library.add_template(synthetic_glyph(label, config), config));
+    config.symbol_library = std::sync::Arc::new(library);
+}
+
+/// Set pixels are dark ink on a light background
+pub fn render(code: &BinaryImage) -> ColorImage {
+    let mut frame = ColorImage::new_w_h(code.width, code.height);
+    for y in 0..code.height {
+        for x in 0..code.width {
+            let color = if code.get_pixel(x, y) { Color::new(20, 20, 20) } else { Color::new(230, 230, 230) };
+            frame.set_pixel(x, y, &color);
+        }
+    }
+    frame
+}
+
+/// The transform of a frame which is the code itself
+pub fn identity(config: &Acute32SymcodeConfig) -> PerspectiveTransform {
+    let (w, h) = (config.params.code_width as f64, config.params.code_height as f64);
+    let corners = [PointF64::new(0.0, 0.0), PointF64::new(w, 0.0), PointF64::new(w, h), PointF64::new(0.0, h)];
+    PerspectiveTransform::from_point_f64(&corners, &corners)
+}
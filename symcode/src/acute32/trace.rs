@@ -8,17 +8,20 @@ pub trait Trace {
 
     /// the default implementation is to XOR the two bit strings and count the number of 1s
     fn diff(&self, other: &Self) -> usize {
-        let (mut self_clone, mut other_clone) = (self.bits().clone(), other.bits().clone());
-        self_clone.difference(&other.bits());
-        other_clone.difference(&self.bits());
-        self_clone.or(&other_clone);
-        self_clone.into_iter().filter(|bit| *bit).count()
+        hamming_distance(self.bits(), other.bits())
     }
 
-
     fn from_image(image: &BinaryImage, tolerance: f64) -> Self;
 }
 
+/// Count the number of differing bits block by block, without cloning either bit string
+pub(crate) fn hamming_distance(a: &BitVec, b: &BitVec) -> usize {
+    debug_assert_eq!(a.len(), b.len());
+    a.blocks().zip(b.blocks())
+        .map(|(block_a, block_b)| (block_a ^ block_b).count_ones() as usize)
+        .sum()
+}
+
 #[derive(Debug)]
 pub struct GlyphTrace {
     pub bits: BitVec,
@@ -0,0 +1,114 @@
+use bit_vec::BitVec;
+
+use super::{GlyphTrace, Trace, hamming_distance};
+
+/// A BK-tree over glyph traces, keyed by the Hamming distance between trace bits.
+///
+/// Querying only visits the branches that can contain traces within the given distance,
+/// so that only a handful of templates have to be compared pixelwise.
+#[derive(Debug, Default)]
+pub struct TraceIndex {
+    nodes: Vec<TraceIndexNode>,
+}
+
+#[derive(Debug)]
+struct TraceIndexNode {
+    bits: BitVec,
+    /// (distance to this node, index of the child node)
+    children: Vec<(usize, usize)>,
+}
+
+impl TraceIndex {
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Inserts a trace and returns its id, which is the insertion order
+    pub fn insert(&mut self, trace: &GlyphTrace) -> usize {
+        let id = self.nodes.len();
+        self.nodes.push(TraceIndexNode { bits: trace.bits().clone(), children: vec![] });
+        if id == 0 {
+            return id;
+        }
+
+        let mut current = 0;
+        loop {
+            let distance = hamming_distance(&self.nodes[current].bits, trace.bits());
+            match self.nodes[current].children.iter().find(|(d, _)| *d == distance) {
+                Some(&(_, child)) => current = child,
+                None => {
+                    self.nodes[current].children.push((distance, id));
+                    return id;
+                }
+            }
+        }
+    }
+
+    /// Returns the ids of all traces within `max_difference` of `trace`, in insertion order
+    pub fn query(&self, trace: &GlyphTrace, max_difference: usize) -> Vec<usize> {
+        let mut result = vec![];
+        if self.nodes.is_empty() {
+            return result;
+        }
+
+        let mut stack = vec![0];
+        while let Some(current) = stack.pop() {
+            let node = &self.nodes[current];
+            let distance = hamming_distance(&node.bits, trace.bits());
+            if distance <= max_difference {
+                result.push(current);
+            }
+            // By triangle inequality, only children within this band can be close enough
+            let lower = distance.saturating_sub(max_difference);
+            let upper = distance + max_difference;
+            stack.extend(
+                node.children.iter()
+                    .filter(|(d, _)| lower <= *d && *d <= upper)
+                    .map(|(_, child)| *child)
+            );
+        }
+        result.sort_unstable();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trace_from_primitive(n: usize) -> GlyphTrace {
+        GlyphTrace { bits: crate::math::into_bitvec(n, 10) }
+    }
+
+    #[test]
+    fn trace_index_query_agrees_with_linear_scan() {
+        let traces: Vec<GlyphTrace> = (0..200).map(|i| trace_from_primitive((i * 37) % 1024)).collect();
+        let mut index = TraceIndex::default();
+        traces.iter().enumerate().for_each(|(i, trace)| assert_eq!(index.insert(trace), i));
+
+        for query in (0..1024).step_by(7).map(trace_from_primitive) {
+            for max_difference in 0..4 {
+                let expected: Vec<usize> = traces.iter().enumerate()
+                    .filter(|(_, trace)| trace.diff(&query) <= max_difference)
+                    .map(|(i, _)| i)
+                    .collect();
+                assert_eq!(index.query(&query, max_difference), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn trace_index_duplicate_traces() {
+        let mut index = TraceIndex::default();
+        index.insert(&trace_from_primitive(5));
+        index.insert(&trace_from_primitive(5));
+        index.insert(&trace_from_primitive(6));
+        assert_eq!(index.query(&trace_from_primitive(5), 0), vec![0, 1]);
+        assert_eq!(index.query(&trace_from_primitive(5), 2), vec![0, 1, 2]);
+        assert!(TraceIndex::default().query(&trace_from_primitive(5), 3).is_empty());
+    }
+}
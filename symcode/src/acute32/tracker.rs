@@ -0,0 +1,322 @@
+use bit_vec::BitVec;
+use visioncortex::{BoundingRect, ColorImage, PerspectiveTransform, PointF64};
+use crate::interfaces::{Decoder, DummyDebugger, Finder};
+use super::{AccumulatorParams, Acute32Decoder, Acute32FinderCandidate, Acute32Recognizer, Acute32SymcodeConfig, Acute32TransformFitter, GlyphAccumulator, GlyphLabel, Polarity, local_adaptive_threshold};
+
+/// Parameters of `Acute32Tracker`
+#[derive(Clone, Copy, Debug)]
+pub struct TrackerParams {
+    /// The search window around each previous finder extends this multiple of the finder size on each side
+    pub search_margin: f64,
+    /// The size of a tracked finder may change at most by this factor between frames
+    pub max_scale_change: f64,
+    /// Number of consecutive frames the code can be missing before the track is dropped
+    pub max_lost_frames: usize,
+    /// How the glyph labels are voted over the frames
+    pub accumulator: AccumulatorParams,
+}
+
+impl Default for TrackerParams {
+    fn default() -> Self {
+        Self {
+            search_margin: 1.0,
+            max_scale_change: 2.0,
+            max_lost_frames: 2,
+            accumulator: AccumulatorParams::default(),
+        }
+    }
+}
+
+impl TrackerParams {
+    pub fn search_margin(mut self, search_margin: f64) -> Self {
+        self.search_margin = search_margin;
+        self
+    }
+
+    pub fn max_scale_change(mut self, max_scale_change: f64) -> Self {
+        self.max_scale_change = max_scale_change;
+        self
+    }
+
+    pub fn max_lost_frames(mut self, max_lost_frames: usize) -> Self {
+        self.max_lost_frames = max_lost_frames;
+        self
+    }
+
+    pub fn accumulator(mut self, accumulator: AccumulatorParams) -> Self {
+        self.accumulator = accumulator;
+        self
+    }
+}
+
+/// Result of tracking one frame
+#[derive(Debug)]
+pub struct TrackerOutput {
+    /// Labels voted over the recent frames of the same track
+    pub labels: Vec<GlyphLabel>,
+    /// The voted labels, if every glyph is confident enough
+    pub consensus: Option<Vec<GlyphLabel>>,
+    /// Whether the code was located by searching around the finders of the previous frame
+    pub tracked: bool,
+    /// Number of frames that took part in the vote
+    pub num_frames: usize,
+}
+
+/// Scans a video stream, reusing the finder positions of the previous frame as a prior.
+///
+/// Each finder is searched for in a window around its previous position, and the transform is
+/// fitted without trying all arrangements. When that fails, full detection is run on the frame.
+/// The glyph labels are voted over the recent frames before decoding.
+///
+/// The tracker only keeps the state between frames, so that it can live beside the config it is used with.
+/// The stages run without a debugger.
+pub struct Acute32Tracker {
+    params: TrackerParams,
+    /// Finders in the image space of the last located frame, in the order of `finder_positions`
+    finders: Option<Vec<BoundingRect>>,
+    /// Polarity of the last read, to search the finders with if it is not configured
+    polarity: Polarity,
+    lost_frames: usize,
+    accumulator: GlyphAccumulator,
+}
+
+impl Default for Acute32Tracker {
+    fn default() -> Self {
+        Self::new(TrackerParams::default())
+    }
+}
+
+impl Acute32Tracker {
+    pub fn new(params: TrackerParams) -> Self {
+        Self {
+            params,
+            finders: None,
+            polarity: Polarity::default(),
+            lost_frames: 0,
+            accumulator: GlyphAccumulator::new(params.accumulator),
+        }
+    }
+
+    pub fn is_tracking(&self) -> bool {
+        self.finders.is_some()
+    }
+
+    /// Drops the track and the votes, e.g. when the camera is switched
+    pub fn reset(&mut self) {
+        self.finders = None;
+        self.polarity = Polarity::default();
+        self.lost_frames = 0;
+        self.accumulator.clear();
+    }
+
+    /// Locates the code in the frame and reads the glyphs, returning the labels voted over the recent frames
+    pub fn track(&mut self, frame: ColorImage, symcode_config: &Acute32SymcodeConfig) -> Result<TrackerOutput, &'static str> {
+        let (image_width, image_height) = (frame.width, frame.height);
+
+        let tracked_transform = self.finders.as_ref().and_then(|finders| {
+            let polarity = symcode_config.params.polarity.unwrap_or(self.polarity);
+            let finders = Self::search_finders(&frame, finders, polarity, &self.params, symcode_config)?;
+            Acute32TransformFitter::fit_ordered(image_width, image_height, &finders, symcode_config).ok()
+        });
+        let tracked = tracked_transform.is_some();
+        let image_to_object = match tracked_transform {
+            Some(transform) => transform,
+            None => match Self::detect(&frame, symcode_config) {
+                Ok(transform) => transform,
+                Err(e) => {
+                    self.lose_track();
+                    return Err(e);
+                }
+            },
+        };
+
+        if !tracked {
+            // A code detected anew, even while a track is kept, may be a different code
+            self.accumulator.clear();
+        }
+        self.finders = Some(Self::project_finders(&image_to_object, symcode_config));
+        self.lost_frames = 0;
+
+        let output = Acute32Recognizer::read_glyphs_with_details_from_raw_frame(
+            frame, image_to_object, &symcode_config.symbol_library, symcode_config, &DummyDebugger
+        );
+        self.polarity = output.polarity;
+        output.check_quiet_zone(symcode_config)?;
+        let consensus = self.accumulator.push_output(&output);
+
+        Ok(TrackerOutput {
+            labels: self.accumulator.best_labels(),
+            consensus,
+            tracked,
+            num_frames: self.accumulator.num_frames(),
+        })
+    }
+
+    /// Tracks the frame and decodes the consensus, or the best voted labels before it is reached
+    pub fn track_and_decode(&mut self, frame: ColorImage, symcode_config: &Acute32SymcodeConfig) -> Result<BitVec, &'static str> {
+        let output = self.track(frame, symcode_config)?;
+        Acute32Decoder::new(symcode_config).decode(output.consensus.unwrap_or(output.labels))
+    }
+
+    fn lose_track(&mut self) {
+        self.lost_frames += 1;
+        if self.lost_frames > self.params.max_lost_frames {
+            self.reset();
+        }
+    }
+
+    /// Full detection, as in scanning a single image
+    fn detect(frame: &ColorImage, symcode_config: &Acute32SymcodeConfig) -> Result<PerspectiveTransform, &'static str> {
+        let finder_candidates = Acute32FinderCandidate::new(symcode_config).find(frame)?;
+        Acute32TransformFitter::fit_transform(frame.width, frame.height, finder_candidates, symcode_config, &DummyDebugger)
+    }
+
+    /// Looks for each finder within a window around its previous position
+    fn search_finders(frame: &ColorImage, previous: &[BoundingRect], polarity: Polarity, params: &TrackerParams, symcode_config: &Acute32SymcodeConfig) -> Option<Vec<BoundingRect>> {
+        previous.iter().map(|prev| {
+            // The previous finders are in the undistorted image space
+            let prior = symcode_config.params.lens_distortion.map_or(*prev, |lens| lens.distort_rect(prev));
+            let window = Self::search_window(&prior, params.search_margin, frame.width, frame.height)?;
+            let window_image = Self::crop_color_image(frame, &window);
+            // The patch size of full detection, as a patch smaller than the finder would hollow it out
+            let binary_window = local_adaptive_threshold(
+                &window_image, std::cmp::min(frame.width, frame.height) / 4, 0.2, polarity
+            );
+            let prev_center = prev.center().to_point_f64();
+            Acute32FinderCandidate::extract_finder_positions(&binary_window, symcode_config.finder.as_ref()).into_iter()
+                .map(|rect| BoundingRect {
+                    left: rect.left + window.left,
+                    top: rect.top + window.top,
+                    right: rect.right + window.left,
+                    bottom: rect.bottom + window.top,
+                })
+                .map(|rect| symcode_config.params.lens_distortion.map_or(rect, |lens| lens.undistort_rect(&rect)))
+                .filter(|rect| Self::similar_size(prev, rect, params.max_scale_change))
+                .min_by(|a, b| {
+                    let dist_a = (a.center().to_point_f64() - prev_center).norm();
+                    let dist_b = (b.center().to_point_f64() - prev_center).norm();
+                    dist_a.partial_cmp(&dist_b).unwrap()
+                })
+        }).collect()
+    }
+
+    /// The rect expanded by `margin` times its size on each side, clipped to the frame
+    fn search_window(rect: &BoundingRect, margin: f64, image_width: usize, image_height: usize) -> Option<BoundingRect> {
+        let pad = (std::cmp::max(rect.width(), rect.height()) as f64 * margin).round() as i32;
+        let window = BoundingRect {
+            left: std::cmp::max(rect.left - pad, 0),
+            top: std::cmp::max(rect.top - pad, 0),
+            right: std::cmp::min(rect.right + pad, image_width as i32),
+            bottom: std::cmp::min(rect.bottom + pad, image_height as i32),
+        };
+        if window.width() > 0 && window.height() > 0 {
+            Some(window)
+        } else {
+            None
+        }
+    }
+
+    fn similar_size(a: &BoundingRect, b: &BoundingRect, max_scale_change: f64) -> bool {
+        let size = |rect: &BoundingRect| std::cmp::max(rect.width(), rect.height()) as f64;
+        let (size_a, size_b) = (size(a), size(b));
+        size_a > 0.0 && size_b > 0.0 &&
+        size_a.max(size_b) <= size_a.min(size_b) * max_scale_change
+    }
+
+    fn crop_color_image(image: &ColorImage, rect: &BoundingRect) -> ColorImage {
+        let mut cropped = ColorImage::new_w_h(rect.width() as usize, rect.height() as usize);
+        for y in 0..cropped.height {
+            for x in 0..cropped.width {
+                let color = image.get_pixel(x + rect.left as usize, y + rect.top as usize);
+                cropped.set_pixel(x, y, &color);
+            }
+        }
+        cropped
+    }
+
+    /// The bounding boxes of the finders in the image space, as predicted by the transform
+    fn project_finders(image_to_object: &PerspectiveTransform, symcode_config: &Acute32SymcodeConfig) -> Vec<BoundingRect> {
+        let half_width = (symcode_config.params.symbol_width >> 1) as f64;
+        let half_height = (symcode_config.params.symbol_height >> 1) as f64;
+        symcode_config.params.finder_positions.iter().map(|center| {
+            let corners: Vec<PointF64> = [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)].iter()
+                .map(|(sx, sy)| image_to_object.transform_inverse(PointF64::new(center.x + sx * half_width, center.y + sy * half_height)))
+                .collect();
+            BoundingRect {
+                left: corners.iter().map(|p| p.x).fold(std::f64::MAX, f64::min).floor() as i32,
+                top: corners.iter().map(|p| p.y).fold(std::f64::MAX, f64::min).floor() as i32,
+                right: corners.iter().map(|p| p.x).fold(std::f64::MIN, f64::max).ceil() as i32,
+                bottom: corners.iter().map(|p| p.y).fold(std::f64::MIN, f64::max).ceil() as i32,
+            }
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use visioncortex::{BinaryImage, PointI32};
+    use crate::interfaces::{Encoder, SymcodeGenerator};
+    use super::super::{Acute32Encoder, Acute32Generator, ReaderMode};
+    use super::super::test_fixtures::{render, use_synthetic_library};
+    use super::*;
+
+    #[test]
+    fn tracker_search_window_is_clipped() {
+        let rect = BoundingRect::new_x_y_w_h(10, 20, 10, 10);
+        let window = Acute32Tracker::search_window(&rect, 1.0, 100, 35).unwrap();
+        assert_eq!((window.left, window.top, window.right, window.bottom), (0, 10, 30, 35));
+        assert!(Acute32Tracker::search_window(&BoundingRect::new_x_y_w_h(200, 0, 10, 10), 1.0, 100, 100).is_none());
+    }
+
+    #[test]
+    fn tracker_similar_size() {
+        let rect = BoundingRect::new_x_y_w_h(0, 0, 10, 10);
+        assert!(Acute32Tracker::similar_size(&rect, &BoundingRect::new_x_y_w_h(5, 5, 18, 12), 2.0));
+        assert!(!Acute32Tracker::similar_size(&rect, &BoundingRect::new_x_y_w_h(5, 5, 25, 12), 2.0));
+        assert!(!Acute32Tracker::similar_size(&rect, &BoundingRect::new_x_y_w_h(5, 5, 0, 0), 2.0));
+    }
+
+    /// The code pasted into a larger frame with its top left corner at offset
+    fn frame_with_code(code: &BinaryImage, offset: PointI32) -> ColorImage {
+        let mut frame = BinaryImage::new_w_h(1200, 1200);
+        frame.paste_from(code, offset);
+        render(&frame)
+    }
+
+    #[test]
+    fn tracker_tracks_and_restarts_votes_for_another_code() {
+        let mut config = Acute32SymcodeConfig::default();
+        config.params.reader_mode = ReaderMode::AnchorSampling { search_radius: 0, search_step: 1 };
+        use_synthetic_library(&mut config);
+        let num_glyphs = config.params.num_glyphs_in_code();
+        let (payload_a, payload_b) = (BitVec::from_fn(20, |i| i % 3 == 0), BitVec::from_fn(20, |i| i % 4 != 1));
+        let labels_a = Acute32Encoder::new(&config).encode(payload_a.clone(), num_glyphs).unwrap();
+        let labels_b = Acute32Encoder::new(&config).encode(payload_b.clone(), num_glyphs).unwrap();
+        let code_a = Acute32Generator::new(&config).generate(labels_a.clone());
+        let code_b = Acute32Generator::new(&config).generate(labels_b.clone());
+
+        let mut tracker = Acute32Tracker::new(TrackerParams::default().accumulator(AccumulatorParams::default().min_votes(2)));
+        let output = tracker.track(frame_with_code(&code_a, PointI32::new(40, 40)), &config).unwrap();
+        assert!(!output.tracked);
+        assert_eq!((output.labels, output.consensus, output.num_frames), (labels_a.clone(), None, 1));
+        assert!(tracker.is_tracking());
+
+        // Moved a little, the code is found around its previous finders
+        let output = tracker.track(frame_with_code(&code_a, PointI32::new(52, 31)), &config).unwrap();
+        assert!(output.tracked);
+        assert_eq!((output.consensus, output.num_frames), (Some(labels_a), 2));
+
+        // Another code far from the previous finders is detected anew, and its votes start over
+        let output = tracker.track(frame_with_code(&code_b, PointI32::new(380, 360)), &config).unwrap();
+        assert!(!output.tracked);
+        assert_eq!((output.labels, output.num_frames), (labels_b, 1));
+        assert_eq!(tracker.track_and_decode(frame_with_code(&code_b, PointI32::new(380, 360)), &config), Ok(payload_b));
+
+        // Lost for longer than allowed, the track is dropped
+        let empty = render(&BinaryImage::new_w_h(1200, 1200));
+        for _ in 0..=TrackerParams::default().max_lost_frames {
+            assert!(tracker.track(empty.clone(), &config).is_err());
+        }
+        assert!(!tracker.is_tracking());
+    }
+}
@@ -1,7 +1,9 @@
-use visioncortex::{BinaryImage, ColorImage, PointF64, PointI32, SampleStatBuilder, SummedAreaTable};
+use visioncortex::{BinaryImage, BoundingRect, ColorImage, PointF64, PointI32, SampleStatBuilder, SummedAreaTable};
+use std::collections::VecDeque;
+use super::Polarity;
 
-// Local adaptive thresholding by finding patch mean around each pixel
-pub(crate) fn local_adaptive_threshold(color_image: &ColorImage, patch_size: usize, offset_percentage: f64) -> BinaryImage {
+// Local adaptive thresholding by finding patch mean around each pixel, pixels of the ink polarity are set
+pub(crate) fn local_adaptive_threshold(color_image: &ColorImage, patch_size: usize, offset_percentage: f64, polarity: Polarity) -> BinaryImage {
     let offset = offset_for(color_image, offset_percentage);
     let sat = SummedAreaTable::from_color_image(color_image);
 
@@ -10,12 +12,22 @@ pub(crate) fn local_adaptive_threshold(color_image: &ColorImage, patch_size: usi
     let half_patch_size = patch_size >> 1;
     for y in 0..result.height {
         for x in 0..result.width {
-            let top_left = PointI32::new(std::cmp::max(0, (x - half_patch_size) as i32), std::cmp::max(0, (y - half_patch_size) as i32));
-            let threshold = std::cmp::max(0, sat.get_region_mean_x_y_w_h(top_left.x as usize, top_left.y as usize, patch_size, patch_size) as i32 - offset) as u8;
+            // The patch is clipped to the image, as the table has no sums beyond it
+            let top_left = PointI32::new(x.saturating_sub(half_patch_size) as i32, y.saturating_sub(half_patch_size) as i32);
+            let bot_right = PointI32::new(
+                std::cmp::min(top_left.x as usize + patch_size, result.width) as i32 - 1,
+                std::cmp::min(top_left.y as usize + patch_size, result.height) as i32 - 1,
+            );
+            let patch_mean = sat.get_region_mean_top_left_bot_right(top_left, bot_right) as i32;
+            // Ink has to differ from the patch mean by the offset towards its side
+            let threshold = match polarity {
+                Polarity::DarkOnLight => std::cmp::max(0, patch_mean - offset),
+                Polarity::LightOnDark => std::cmp::min(255, patch_mean + offset),
+            } as u8;
 
             let c = color_image.get_pixel(x, y);
             let c_mean = ((c.r as u32 + c.g as u32 + c.b as u32) / 3) as u8;
-            result.set_pixel(x, y, c_mean < threshold);
+            result.set_pixel(x, y, polarity.is_ink(c_mean, threshold));
         }
     }
 
@@ -37,30 +49,6 @@ fn offset_for(image: &ColorImage, percentage: f64) -> i32 {
     (dynamic_range as f64 * percentage) as i32
 }
 
-pub(crate) fn global_adaptive_threshold(color_image: &ColorImage) -> BinaryImage {
-    let threshold = threshold_for(color_image);
-    color_image.to_binary_image(move |c| {
-        let r = c.r as u32;
-        let g = c.g as u32;
-        let b = c.b as u32;
-
-        r + g + b < 3*threshold
-    })
-}
-
-fn threshold_for(image: &ColorImage) -> u32 {
-    let mut stat = SampleStatBuilder::new();
-    for y in 0..image.height {
-        for x in 0..image.width {
-            let c = image.get_pixel(x, y);
-            let c_sum = (c.r as u32 + c.g as u32 + c.b as u32) as i32;
-            stat.add(c_sum);
-        }
-    }
-    stat.build();
-    (stat.percentile(10) + stat.percentile(90)) as u32 / 6
-}
-
 pub(crate) fn valid_pointi32_on_image(point: PointI32, image_width: usize, image_height: usize) -> bool {
     let w_upper = image_width as i32;
     let h_upper = image_height as i32;
@@ -78,5 +66,67 @@ pub(crate) fn valid_pointf64_on_image(point: PointF64, image_width: usize, image
 }
 
 pub(crate) fn image_diff_area(img1: &BinaryImage, img2: &BinaryImage) -> u64 {
-    img1.diff(img2).area()
+    if img1.width == img2.width && img1.height == img2.height {
+        // Same pixel layout, count the differing pixels without allocating the diff image
+        img1.pixels.blocks().zip(img2.pixels.blocks())
+            .map(|(block1, block2)| (block1 ^ block2).count_ones() as u64)
+            .sum()
+    } else {
+        img1.diff(img2).area()
+    }
+}
+
+/// The bounding rect of the set pixels, None if the image is empty
+pub(crate) fn set_pixels_bounding_rect(image: &BinaryImage) -> Option<BoundingRect> {
+    let mut rect: Option<BoundingRect> = None;
+    for y in 0..image.height {
+        for x in 0..image.width {
+            if image.get_pixel(x, y) {
+                let (x, y) = (x as i32, y as i32);
+                rect = Some(match rect {
+                    Some(r) => BoundingRect {
+                        left: r.left.min(x), top: r.top.min(y), right: r.right.max(x + 1), bottom: r.bottom.max(y + 1),
+                    },
+                    None => BoundingRect { left: x, top: y, right: x + 1, bottom: y + 1 },
+                });
+            }
+        }
+    }
+    rect
+}
+
+/// The pixels of the cluster, and the pixels of the frame (cropped at the cluster) in the holes of the cluster
+pub(crate) fn fill_cluster_holes(cluster_image: &BinaryImage, frame_crop: &BinaryImage) -> BinaryImage {
+    let (width, height) = (cluster_image.width, cluster_image.height);
+    // Flood the background of the cluster from the border, what is not reached is in a hole
+    let mut outside = BinaryImage::new_w_h(width, height);
+    let mut queue = VecDeque::new();
+    for y in 0..height {
+        for x in 0..width {
+            if (x == 0 || y == 0 || x + 1 == width || y + 1 == height) && !cluster_image.get_pixel(x, y) {
+                outside.set_pixel(x, y, true);
+                queue.push_back((x, y));
+            }
+        }
+    }
+    while let Some((x, y)) = queue.pop_front() {
+        let neighbours = [
+            (x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1),
+        ];
+        for &(nx, ny) in neighbours.iter() {
+            if nx < width && ny < height && !cluster_image.get_pixel(nx, ny) && !outside.get_pixel(nx, ny) {
+                outside.set_pixel(nx, ny, true);
+                queue.push_back((nx, ny));
+            }
+        }
+    }
+
+    let mut result = BinaryImage::new_w_h(width, height);
+    for y in 0..height {
+        for x in 0..width {
+            let inside = !outside.get_pixel(x, y) && frame_crop.get_pixel(x, y);
+            result.set_pixel(x, y, cluster_image.get_pixel(x, y) || inside);
+        }
+    }
+    result
 }
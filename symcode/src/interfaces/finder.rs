@@ -12,4 +12,9 @@ pub trait FinderElement {
 	fn to_image(&self, width: usize, height: usize) -> BinaryImage; // to be used by SymcodeGenerator
 
 	fn is_finder(&self, shape: Shape) -> bool; // to be used by SymcodeScanner
+
+	/// Whether the finder is made of nested parts, so that a cluster is tested together with what is inside its holes
+	fn has_nested_parts(&self) -> bool {
+		false
+	}
 }
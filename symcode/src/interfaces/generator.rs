@@ -1,4 +1,4 @@
-use visioncortex::BinaryImage;
+use visioncortex::{BinaryImage, ColorImage};
 
 /// To generate a Symcode image for a given Symcode representation
 pub trait SymcodeGenerator {
@@ -6,4 +6,11 @@ pub trait SymcodeGenerator {
     type SymcodeRepresentation;
 
     fn generate(&self, symcode: Self::SymcodeRepresentation) -> BinaryImage;
-}
\ No newline at end of file
+}
+/// To generate a Symcode image in color, for variants which encode information in the ink color
+pub trait ColorSymcodeGenerator {
+
+    type SymcodeRepresentation;
+
+    fn generate(&self, symcode: Self::SymcodeRepresentation) -> Result<ColorImage, &'static str>;
+}
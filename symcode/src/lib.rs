@@ -1,3 +1,4 @@
 pub mod acute32;
 pub mod interfaces;
-pub mod math;
\ No newline at end of file
+pub mod math;
+pub mod payload;
\ No newline at end of file
@@ -74,6 +74,16 @@ pub fn into_bitvec(mut n: usize, len: usize) -> BitVec {
     bitvec 
 }
 
+/// The CRC5 checksum of exactly the bits of `bits`, which are not padded to whole bytes
+pub fn crc5_of_bits(bits: &BitVec) -> u8 {
+    crczoo::CRC5_PARAMS.checksum_bits(bits.iter())
+}
+
+/// The CRC5 checksum of the bytes of `bits`, padded with zero bits, as symcode 0.1 checksummed the payload
+pub fn crc5_of_padded_bytes(bits: &BitVec) -> u8 {
+    crczoo::crc5(&bits.to_bytes())
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -153,4 +163,15 @@ mod tests {
         let n = 20;
         assert!(into_bitvec(n, 5).eq_vec(&[true, false, true, false, false])); 
     }
+
+    #[test]
+    fn math_crc5_of_bits() {
+        let payload = BitVec::from_fn(20, |i| i % 3 == 0);
+        let mut padded = payload.clone();
+        padded.grow(4, false);
+        // Padding to whole bytes is no longer the same checksum
+        assert_eq!(payload.to_bytes(), padded.to_bytes());
+        assert_ne!(crc5_of_bits(&payload), crc5_of_bits(&padded));
+        assert!(crc5_of_bits(&payload) < 32);
+    }
 }
\ No newline at end of file
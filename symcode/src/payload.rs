@@ -0,0 +1,410 @@
+use std::fmt;
+use std::str::FromStr;
+use bit_vec::BitVec;
+
+/// Number of bits of the mode indicator, which starts every encoded payload
+pub const MODE_INDICATOR_BITS: usize = 3;
+
+/// The characters of `Payload::Alphanumeric`, as in QR codes
+pub const ALPHANUMERIC_CHARSET: &str = "0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+/// The characters of `Payload::Text`, 5 bits each, enough for lowercase domains and paths
+pub const TEXT_CHARSET: &str = "abcdefghijklmnopqrstuvwxyz.-_~/:";
+
+/// A GS1 style element: an application identifier of 2 to 4 digits, and its data of digits or alphanumeric characters.
+/// e.g. (01) is a GTIN and (10) a batch number
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ApplicationIdentifier {
+    pub ai: String,
+    pub data: String,
+}
+
+/// A typed payload, to be encoded into the payload bits of a code and decoded back.
+///
+/// The bits start with the mode indicator, followed by the content of the mode and padded with zeros:
+///
+/// - Numeric: the number in the rest of the bits
+/// - Alphanumeric: the number of characters, then 11 bits for each pair of characters and 6 bits for the last odd one
+/// - Text: the number of characters, then 5 bits each
+/// - Bytes: the number of bytes, then 8 bits each
+/// - ApplicationIdentifiers: each element is a 1 bit, the number of digits of the identifier less 2 in 2 bits,
+///   the digits, 1 bit for alphanumeric data, the number of characters and the characters. A 0 bit or the end ends the list
+///
+/// Digits are packed 10 bits for 3, 7 bits for 2 and 4 bits for 1. The number of characters takes
+/// as many bits as to count the most characters which could fit after the mode indicator.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Payload {
+    Numeric(u64),
+    Alphanumeric(String),
+    Text(String),
+    Bytes(Vec<u8>),
+    ApplicationIdentifiers(Vec<ApplicationIdentifier>),
+}
+
+impl Payload {
+    pub fn mode_indicator(&self) -> u64 {
+        match self {
+            Self::Numeric(_) => 0,
+            Self::Alphanumeric(_) => 1,
+            Self::Text(_) => 2,
+            Self::Bytes(_) => 3,
+            Self::ApplicationIdentifiers(_) => 4,
+        }
+    }
+
+    /// Exactly `num_bits` bits, e.g. the payload length of the encoder
+    pub fn encode(&self, num_bits: usize) -> Result<BitVec, &'static str> {
+        if num_bits < MODE_INDICATOR_BITS {
+            return Err("Payload error: Not enough bits for the mode indicator.");
+        }
+        let capacity = num_bits - MODE_INDICATOR_BITS;
+        let mut writer = BitWriter::default();
+        writer.push(self.mode_indicator(), MODE_INDICATOR_BITS);
+        match self {
+            Self::Numeric(number) => {
+                let len = capacity.min(64);
+                if len < 64 && number >> len != 0 {
+                    return Err("Payload error: The number is too large.");
+                }
+                writer.push(0, capacity - len);
+                writer.push(*number, len);
+            },
+            Self::Alphanumeric(text) => {
+                let indices = charset_indices(text, ALPHANUMERIC_CHARSET)?;
+                writer.push_count(indices.len(), max_alphanumeric(capacity))?;
+                writer.push_alphanumeric(&indices);
+            },
+            Self::Text(text) => {
+                let indices = charset_indices(text, TEXT_CHARSET)?;
+                writer.push_count(indices.len(), capacity / 5)?;
+                indices.iter().for_each(|&index| writer.push(index as u64, 5));
+            },
+            Self::Bytes(bytes) => {
+                writer.push_count(bytes.len(), capacity / 8)?;
+                bytes.iter().for_each(|&byte| writer.push(byte as u64, 8));
+            },
+            Self::ApplicationIdentifiers(elements) => {
+                for element in elements.iter() {
+                    if element.ai.len() < 2 || element.ai.len() > 4 {
+                        return Err("Payload error: An application identifier must have 2 to 4 digits.");
+                    }
+                    writer.push(1, 1);
+                    writer.push(element.ai.len() as u64 - 2, 2);
+                    writer.push_digits(&digits(&element.ai)?);
+                    let is_numeric = element.data.bytes().all(|byte| byte.is_ascii_digit());
+                    writer.push(!is_numeric as u64, 1);
+                    if is_numeric {
+                        writer.push_count(element.data.len(), max_digits(capacity))?;
+                        writer.push_digits(&digits(&element.data)?);
+                    } else {
+                        let indices = charset_indices(&element.data, ALPHANUMERIC_CHARSET)?;
+                        writer.push_count(indices.len(), max_alphanumeric(capacity))?;
+                        writer.push_alphanumeric(&indices);
+                    }
+                }
+            },
+        }
+
+        let mut bits = writer.bits;
+        if bits.len() > num_bits {
+            return Err("Payload error: Too long for the code.");
+        }
+        bits.grow(num_bits - bits.len(), false);
+        Ok(bits)
+    }
+
+    /// Reads the mode indicator and the content, ignoring the padding
+    pub fn decode(bits: &BitVec) -> Result<Self, &'static str> {
+        let mut reader = BitReader { bits, position: 0 };
+        let mode = reader.read(MODE_INDICATOR_BITS)?;
+        let capacity = reader.remaining();
+        match mode {
+            0 => {
+                let len = capacity.min(64);
+                reader.read(capacity - len)?;
+                Ok(Self::Numeric(reader.read(len)?))
+            },
+            1 => {
+                let count = reader.read_count(max_alphanumeric(capacity))?;
+                Ok(Self::Alphanumeric(reader.read_alphanumeric(count)?))
+            },
+            2 => {
+                let count = reader.read_count(capacity / 5)?;
+                let text = (0..count)
+                    .map(|_| reader.read(5).map(|index| TEXT_CHARSET.as_bytes()[index as usize] as char))
+                    .collect::<Result<String, &'static str>>()?;
+                Ok(Self::Text(text))
+            },
+            3 => {
+                let count = reader.read_count(capacity / 8)?;
+                let bytes = (0..count)
+                    .map(|_| reader.read(8).map(|byte| byte as u8))
+                    .collect::<Result<Vec<u8>, &'static str>>()?;
+                Ok(Self::Bytes(bytes))
+            },
+            4 => {
+                let mut elements = vec![];
+                while reader.remaining() > 0 && reader.read(1)? == 1 {
+                    let ai_len = reader.read(2)? as usize + 2;
+                    if ai_len > 4 {
+                        return Err("Payload error: An application identifier must have 2 to 4 digits.");
+                    }
+                    let ai = reader.read_digits(ai_len)?;
+                    let data = if reader.read(1)? == 0 {
+                        let count = reader.read_count(max_digits(capacity))?;
+                        reader.read_digits(count)?
+                    } else {
+                        let count = reader.read_count(max_alphanumeric(capacity))?;
+                        reader.read_alphanumeric(count)?
+                    };
+                    elements.push(ApplicationIdentifier { ai, data });
+                }
+                Ok(Self::ApplicationIdentifiers(elements))
+            },
+            _ => Err("Payload error: Unknown mode."),
+        }
+    }
+}
+
+impl FromStr for Payload {
+    type Err = &'static str;
+
+    /// The most compact mode for the text: a number without leading zeros, element strings such as
+    /// "(01)09501101530003(10)AB12", a text of `TEXT_CHARSET` or of `ALPHANUMERIC_CHARSET`, or else its UTF-8 bytes
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with('(') {
+            return parse_application_identifiers(s).map(Self::ApplicationIdentifiers);
+        }
+        let is_number = !s.is_empty() && s.bytes().all(|byte| byte.is_ascii_digit()) && (s == "0" || !s.starts_with('0'));
+        if let (true, Ok(number)) = (is_number, s.parse()) {
+            Ok(Self::Numeric(number))
+        } else if s.chars().all(|c| TEXT_CHARSET.contains(c)) {
+            Ok(Self::Text(s.to_owned()))
+        } else if s.chars().all(|c| ALPHANUMERIC_CHARSET.contains(c)) {
+            Ok(Self::Alphanumeric(s.to_owned()))
+        } else {
+            Ok(Self::Bytes(s.as_bytes().to_vec()))
+        }
+    }
+}
+
+impl fmt::Display for Payload {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Numeric(number) => write!(f, "{}", number),
+            Self::Alphanumeric(text) | Self::Text(text) => write!(f, "{}", text),
+            Self::Bytes(bytes) => write!(f, "{}", String::from_utf8_lossy(bytes)),
+            Self::ApplicationIdentifiers(elements) => {
+                elements.iter().try_for_each(|element| write!(f, "({}){}", element.ai, element.data))
+            },
+        }
+    }
+}
+
+fn parse_application_identifiers(s: &str) -> Result<Vec<ApplicationIdentifier>, &'static str> {
+    s.split('(').skip(1).map(|element| {
+        let mut parts = element.splitn(2, ')');
+        match (parts.next(), parts.next()) {
+            (Some(ai), Some(data)) if (2..=4).contains(&ai.len()) && ai.bytes().all(|byte| byte.is_ascii_digit()) => {
+                Ok(ApplicationIdentifier { ai: ai.to_owned(), data: data.to_owned() })
+            },
+            _ => Err("Payload error: Element strings must be like (01)09501101530003."),
+        }
+    }).collect()
+}
+
+fn charset_indices(text: &str, charset: &str) -> Result<Vec<usize>, &'static str> {
+    text.chars()
+        .map(|c| charset.find(c).ok_or("Payload error: A character is not in the charset of the mode."))
+        .collect()
+}
+
+fn digits(text: &str) -> Result<Vec<u8>, &'static str> {
+    text.bytes()
+        .map(|byte| if byte.is_ascii_digit() { Ok(byte - b'0') } else { Err("Payload error: Not a digit.") })
+        .collect()
+}
+
+/// The most digits which could fit in the bits
+fn max_digits(num_bits: usize) -> usize {
+    3 * (num_bits / 10) + match num_bits % 10 { 7..=9 => 2, 4..=6 => 1, _ => 0 }
+}
+
+/// The most alphanumeric characters which could fit in the bits
+fn max_alphanumeric(num_bits: usize) -> usize {
+    2 * (num_bits / 11) + if num_bits % 11 >= 6 { 1 } else { 0 }
+}
+
+/// Bits to count up to max_count
+fn count_bits(max_count: usize) -> usize {
+    crate::math::num_significant_bits(max_count)
+}
+
+#[derive(Default)]
+struct BitWriter {
+    bits: BitVec,
+}
+
+impl BitWriter {
+    /// The lowest len bits of value, from the most significant
+    fn push(&mut self, value: u64, len: usize) {
+        for i in (0..len).rev() {
+            self.bits.push(i < 64 && (value >> i) & 1 == 1);
+        }
+    }
+
+    fn push_count(&mut self, count: usize, max_count: usize) -> Result<(), &'static str> {
+        if count > max_count {
+            return Err("Payload error: Too long for the code.");
+        }
+        self.push(count as u64, count_bits(max_count));
+        Ok(())
+    }
+
+    fn push_digits(&mut self, digits: &[u8]) {
+        for group in digits.chunks(3) {
+            let value = group.iter().fold(0, |acc, &digit| acc * 10 + digit as u64);
+            self.push(value, [0, 4, 7, 10][group.len()]);
+        }
+    }
+
+    fn push_alphanumeric(&mut self, indices: &[usize]) {
+        for pair in indices.chunks(2) {
+            match pair {
+                [a, b] => self.push((a * 45 + b) as u64, 11),
+                [a] => self.push(*a as u64, 6),
+                _ => unreachable!(),
+            }
+        }
+    }
+}
+
+struct BitReader<'a> {
+    bits: &'a BitVec,
+    position: usize,
+}
+
+impl BitReader<'_> {
+    fn remaining(&self) -> usize {
+        self.bits.len() - self.position
+    }
+
+    fn read(&mut self, len: usize) -> Result<u64, &'static str> {
+        if len > self.remaining() {
+            return Err("Payload error: Not enough bits.");
+        }
+        let value = (self.position..self.position + len)
+            .fold(0, |acc: u64, i| acc.wrapping_shl(1) | self.bits[i] as u64);
+        self.position += len;
+        Ok(value)
+    }
+
+    fn read_count(&mut self, max_count: usize) -> Result<usize, &'static str> {
+        let count = self.read(count_bits(max_count))? as usize;
+        if count > max_count {
+            return Err("Payload error: Too many characters.");
+        }
+        Ok(count)
+    }
+
+    fn read_digits(&mut self, count: usize) -> Result<String, &'static str> {
+        let mut text = String::with_capacity(count);
+        let mut left = count;
+        while left > 0 {
+            let group = left.min(3);
+            let value = self.read([0, 4, 7, 10][group])?;
+            if value >= 10_u64.pow(group as u32) {
+                return Err("Payload error: Invalid digits.");
+            }
+            text.push_str(&format!("{:0width$}", value, width = group));
+            left -= group;
+        }
+        Ok(text)
+    }
+
+    fn read_alphanumeric(&mut self, count: usize) -> Result<String, &'static str> {
+        let charset = ALPHANUMERIC_CHARSET.as_bytes();
+        let mut text = String::with_capacity(count);
+        for _ in 0..count / 2 {
+            let value = self.read(11)? as usize;
+            if value >= 45 * 45 {
+                return Err("Payload error: Invalid alphanumeric characters.");
+            }
+            text.push(charset[value / 45] as char);
+            text.push(charset[value % 45] as char);
+        }
+        if count % 2 == 1 {
+            let value = self.read(6)? as usize;
+            if value >= 45 {
+                return Err("Payload error: Invalid alphanumeric characters.");
+            }
+            text.push(charset[value] as char);
+        }
+        Ok(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::acute32::{Acute32Decoder, Acute32Encoder, Acute32SymcodeConfig};
+    use crate::interfaces::{Decoder, Encoder};
+    use super::*;
+
+    fn assert_round_trip(payload: Payload, num_bits: usize) {
+        let bits = payload.encode(num_bits).unwrap();
+        assert_eq!(bits.len(), num_bits);
+        assert_eq!(Payload::decode(&bits), Ok(payload));
+    }
+
+    #[test]
+    fn payload_round_trip() {
+        // The 20 payload bits of the 3x3 layout
+        assert_round_trip(Payload::Numeric(0), 20);
+        assert_round_trip(Payload::Numeric((1 << 17) - 1), 20);
+        assert_round_trip(Payload::Alphanumeric("A1".to_owned()), 20);
+        assert_round_trip(Payload::Text("sym".to_owned()), 20);
+        assert_round_trip(Payload::Bytes(vec![0xA5]), 20);
+        assert_round_trip(Payload::ApplicationIdentifiers(vec![]), 20);
+
+        assert_round_trip(Payload::Numeric(u64::MAX), 75);
+        assert_round_trip(Payload::Alphanumeric("HTTP://SYM.CODE/1".to_owned()), 120);
+        assert_round_trip(Payload::Text("visioncortex.org/".to_owned()), 120);
+        assert_round_trip(Payload::Bytes("Hé!".as_bytes().to_vec()), 120);
+        assert_round_trip("(01)09501101530003(10)AB-12".parse().unwrap(), 120);
+        assert_round_trip("(253)012(7003)2106301200".parse().unwrap(), 120);
+    }
+
+    #[test]
+    fn payload_too_long() {
+        assert!(Payload::Numeric(1 << 17).encode(20).is_err());
+        assert!(Payload::Text("symc".to_owned()).encode(20).is_err());
+        assert!(Payload::Bytes(vec![1, 2]).encode(20).is_err());
+        assert!(Payload::Text("UPPER".to_owned()).encode(120).is_err());
+        assert!(Payload::Numeric(1).encode(2).is_err());
+        assert!(Payload::decode(&BitVec::from_elem(20, true)).is_err());
+    }
+
+    #[test]
+    fn payload_from_str() {
+        assert_eq!("12345".parse(), Ok(Payload::Numeric(12345)));
+        // Leading zeros are kept
+        assert_eq!("007".parse(), Ok(Payload::Alphanumeric("007".to_owned())));
+        assert_eq!("symcode.org".parse(), Ok(Payload::Text("symcode.org".to_owned())));
+        assert_eq!("SYMCODE 2".parse(), Ok(Payload::Alphanumeric("SYMCODE 2".to_owned())));
+        assert_eq!("Hi!".parse(), Ok(Payload::Bytes(b"Hi!".to_vec())));
+        assert!("(1)23".parse::<Payload>().is_err());
+
+        for text in ["12345", "007", "symcode.org", "SYMCODE 2", "Hi!", "(01)09501101530003(10)AB12"].iter() {
+            assert_eq!(&text.parse::<Payload>().unwrap().to_string(), text);
+        }
+    }
+
+    #[test]
+    fn payload_through_code() {
+        let config = Acute32SymcodeConfig::default();
+        let payload: Payload = "kit".parse().unwrap();
+        let symcode = Acute32Encoder::new(&config).encode(payload.encode(20).unwrap(), 5).unwrap();
+        let bits = Acute32Decoder::new(&config).decode(symcode).unwrap();
+        assert_eq!(Payload::decode(&bits), Ok(payload));
+    }
+}
@@ -1,11 +1,13 @@
+use std::sync::Arc;
 use bit_vec::BitVec;
 use rand::{RngCore, SeedableRng, rngs::StdRng};
-use visioncortex::{BinaryImage, ColorImage, PointI32};
+use visioncortex::{BinaryImage, ColorImage, PerspectiveTransform};
 use wasm_bindgen::prelude::*;
 
-use symcode::acute32::{Acute32, Acute32SymcodeConfig, AlphabetReader, AlphabetReaderParams, GlyphLabel};
-use symcode::interfaces::{Decoder, Finder, FinderElement, Fitter, Reader, Encoder, SymcodeScanner, SymcodeGenerator};
-use symcode::math::{into_bitvec, num_bits_to_store};
+use symcode::acute32::{AccumulatorParams, Acute32, Acute32Recognizer, Acute32SymcodeConfig, AlphabetReader, AlphabetReaderParams, BullseyeFinder, CircleFinder, ConcentricRingsFinder, GlyphAccumulator, GlyphLabel, Polarity, SquareHoleFinder};
+use symcode::interfaces::{Debugger as DebuggerInterface, DummyDebugger, Decoder, Finder, Fitter, Reader, Encoder, SymcodeScanner, SymcodeGenerator};
+use symcode::math::num_bits_to_store;
+use symcode::payload::Payload;
 use crate::{canvas::Canvas, util::console_log_util};
 use crate::debugger::{Debugger, render_binary_image_to_canvas};
 use super::helper::is_black_hsv;
@@ -13,7 +15,9 @@ use super::helper::is_black_hsv;
 #[wasm_bindgen]
 pub struct Acute32SymcodeMain {
     config: Acute32SymcodeConfig,
+    debugger: Box<dyn DebuggerInterface>,
     rng: StdRng,
+    accumulator: GlyphAccumulator,
 }
 
 impl Default for Acute32SymcodeMain {
@@ -28,7 +32,9 @@ impl Acute32SymcodeMain {
     pub fn from_config(config: Acute32SymcodeConfig, seed: u64) -> Self {
         Self {
             config,
+            debugger: Box::new(DummyDebugger),
             rng: StdRng::seed_from_u64(seed),
+            accumulator: GlyphAccumulator::default(),
         }
     }
 }
@@ -37,11 +43,11 @@ impl Acute32SymcodeMain {
 impl Acute32SymcodeMain {
 
     pub fn new() -> Self {
-        let mut config = Acute32SymcodeConfig::default();
+        let mut main = Self::from_config(Acute32SymcodeConfig::default(), 125);
         if let Some(debug_canvas) = Canvas::new_from_id("debug") {
-            config.debugger = Box::new(Debugger{ debug_canvas });
+            main.debugger = Box::new(Debugger{ debug_canvas });
         }
-        Self::from_config(config, 125)
+        main
     }
 
     pub fn seed_rng(&mut self, seed: u64) {
@@ -58,8 +64,8 @@ impl Acute32SymcodeMain {
         let image = canvas
             .get_image_data_as_color_image(0, 0, canvas.width() as u32, canvas.height() as u32)
             .to_binary_image(|c| is_black_hsv(&c.to_hsv()));
-        match AlphabetReader::read_alphabet_to_library(image, params, &self.config) {
-            Ok(library) => self.config.symbol_library = Box::new(library),
+        match AlphabetReader::read_alphabet_to_library(image, params, &self.config, self.debugger.as_ref()) {
+            Ok(library) => self.config.symbol_library = Arc::new(library),
             Err(e) => console_log_util(e),
         }
     }
@@ -86,16 +92,124 @@ impl Acute32SymcodeMain {
         Ok(format!("{:?}", decoded_bit_string))
     }
 
-    pub fn generate_symcode_to_canvas(&self, canvas_id: &str, payload: &str) -> Result<String, JsValue> {
-        if payload.len() > Self::PAYLOAD_BITS {
-            return Err("Payload has too many bits!".into());
+    /// Scans the frame and decodes the typed payload, e.g. a number or a text
+    pub fn scan_payload_from_canvas_id(&self, canvas_id: &str) -> Result<String, JsValue> {
+        if self.config.symbol_library.is_empty() {
+            return Err("No templates loaded into the SymcodeScanner instance yet!".into());
         }
 
-        let result = usize::from_str_radix(payload, 2);
-        if result.is_err() {
-            return Err("Failed to parse payload as binary number".into());
+        let raw_frame = if let Some(canvas) = &Canvas::new_from_id(canvas_id) {
+            canvas.get_image_data_as_color_image(0, 0, canvas.width() as u32, canvas.height() as u32)
+        } else {
+            return Err("Cannot read input image from canvas.".into());
+        };
+
+        let symcode = self.scan(raw_frame)?;
+        let decoded_bits = self.decode(symcode)?;
+        Ok(Payload::decode(&decoded_bits)?.to_string())
+    }
+
+    /// Scans the frame and votes on the glyphs with the previous frames of the same code.
+    /// Returns the decoded bit string once the glyphs reach consensus.
+    pub fn scan_with_accumulation_from_canvas_id(&mut self, canvas_id: &str) -> Result<String, JsValue> {
+        if self.config.symbol_library.is_empty() {
+            return Err("No templates loaded into the SymcodeScanner instance yet!".into());
+        }
+
+        let raw_frame = if let Some(canvas) = &Canvas::new_from_id(canvas_id) {
+            canvas.get_image_data_as_color_image(0, 0, canvas.width() as u32, canvas.height() as u32)
+        } else {
+            return Err("Cannot read input image from canvas.".into());
+        };
+
+        let image_to_object = self.locate(&raw_frame)?;
+        let output = Acute32Recognizer::read_glyphs_with_details_from_raw_frame(
+            raw_frame, image_to_object, &self.config.symbol_library, &self.config, self.debugger.as_ref()
+        );
+        output.check_quiet_zone(&self.config)?;
+        let consensus = match self.accumulator.push_output(&output) {
+            Some(consensus) => consensus,
+            None => {
+                return Err(format!("Accumulated {} frames, no consensus yet.", self.accumulator.num_frames()).into());
+            }
+        };
+
+        let decoded_bit_string = self.decode(consensus)?;
+        self.accumulator.clear();
+        Ok(format!("{:?}", decoded_bit_string))
+    }
+
+    /// Scans the frame and reports what is measured along the way, without decoding
+    pub fn scan_diagnostics_from_canvas_id(&self, canvas_id: &str) -> Result<String, JsValue> {
+        let raw_frame = if let Some(canvas) = &Canvas::new_from_id(canvas_id) {
+            canvas.get_image_data_as_color_image(0, 0, canvas.width() as u32, canvas.height() as u32)
+        } else {
+            return Err("Cannot read input image from canvas.".into());
+        };
+
+        let image_to_object = self.locate(&raw_frame)?;
+        let output = Acute32Recognizer::read_glyphs_with_details_from_raw_frame(
+            raw_frame, image_to_object, &self.config.symbol_library, &self.config, self.debugger.as_ref()
+        );
+
+        let mut lines = vec![format!("Glyphs: {:?}", output.labels()), format!("Polarity: {:?}", output.polarity)];
+        match &output.quiet_zone {
+            Some(quiet_zone) => lines.push(format!(
+                "Quiet zone: {:.1}% violation, {}",
+                quiet_zone.violation * 100.0, if quiet_zone.clear { "clear" } else { "not clear" }
+            )),
+            None => lines.push("Quiet zone: not measured".to_owned()),
+        }
+        if let Some(refinement) = &output.refinement {
+            lines.push(format!(
+                "Refinement: {} correspondences, error {:.3} -> {:.3}",
+                refinement.num_correspondences, refinement.initial_error, refinement.refined_error
+            ));
         }
-        let payload = result.unwrap();
+        Ok(lines.join("\n"))
+    }
+
+    /// Discards the frames accumulated by scan_with_accumulation_from_canvas_id
+    pub fn reset_accumulator(&mut self) {
+        self.accumulator.clear();
+    }
+
+    pub fn set_accumulator_params(&mut self, window: usize, min_votes: usize, min_agreement: f64, early_exit: bool) {
+        let params = AccumulatorParams::default()
+            .window(window)
+            .min_votes(min_votes)
+            .min_agreement(min_agreement)
+            .early_exit(early_exit);
+        self.accumulator = GlyphAccumulator::new(params);
+    }
+
+    /// "dark_on_light", "light_on_dark", or "auto" to detect it on each frame.
+    /// Codes are generated inverted if it is "light_on_dark".
+    pub fn set_polarity(&mut self, polarity: &str) -> Result<(), JsValue> {
+        self.config.params.polarity = match polarity {
+            "dark_on_light" => Some(Polarity::DarkOnLight),
+            "light_on_dark" => Some(Polarity::LightOnDark),
+            "auto" => None,
+            _ => return Err("Unknown polarity.".into()),
+        };
+        Ok(())
+    }
+
+    /// One of "circle", "concentric_rings", "bullseye" or "square_hole"
+    pub fn set_finder(&mut self, finder: &str) -> Result<(), JsValue> {
+        self.config.finder = match finder {
+            "circle" => Arc::new(CircleFinder::default()),
+            "concentric_rings" => Arc::new(ConcentricRingsFinder::default()),
+            "bullseye" => Arc::new(BullseyeFinder::default()),
+            "square_hole" => Arc::new(SquareHoleFinder::default()),
+            _ => return Err("Unknown finder.".into()),
+        };
+        Ok(())
+    }
+
+    /// The payload is a number, a short text or GS1 element strings such as (01)09501101530003, see `Payload::from_str`
+    pub fn generate_symcode_to_canvas(&self, canvas_id: &str, payload: &str) -> Result<String, JsValue> {
+        let payload = payload.parse::<Payload>()?.encode(Self::PAYLOAD_BITS)?;
 
         let canvas = if let Some(canvas) = Canvas::new_from_id(canvas_id) {
             canvas
@@ -127,11 +241,37 @@ impl Acute32SymcodeMain {
         Ok(ground_truth_code)
     }
 
-    fn generate_symcode_with_payload(&self, payload: usize) -> Result<(BinaryImage, String), &str> {
-        let payload = into_bitvec(payload, Self::PAYLOAD_BITS);
+    /// Stage 1 and 2 of scanning
+    fn locate(&self, image: &ColorImage) -> Result<PerspectiveTransform, JsValue> {
+        let acute32 = Acute32::new(&self.config).debugger(self.debugger.as_ref());
+
+        // Stage 1: Locate finder candidates
+        let finder_positions = match acute32.get_finder().find(
+            image
+        ) {
+            Ok(finder_positions) => finder_positions,
+            Err(e) => {
+                return Err(("Failed at Stage 1: ".to_owned() + e).into());
+            }
+        };
+
+        // Stage 2: Fit a perspective transform from the image space to the object space
+        match acute32.get_fitter().fit(
+            finder_positions,
+            image.width,
+            image.height
+        ) {
+            Ok(image_to_object) => Ok(image_to_object),
+            Err(e) => {
+                Err(("Failed at Stage 2: ".to_owned() + e).into())
+            }
+        }
+    }
+
+    fn generate_symcode_with_payload(&self, payload: BitVec) -> Result<(BinaryImage, String), &str> {
         let payload_bit_string = format!("{:?}", payload);
 
-        let num_symbols = self.config.num_glyphs_in_code();
+        let num_symbols = self.config.params.num_glyphs_in_code();
 
         let acute32 = Acute32::new(&self.config);
         let symcode_representation = acute32.get_encoder().encode(payload, num_symbols)?;
@@ -150,7 +290,7 @@ impl Acute32SymcodeMain {
     /// Then encode the 25-bit bit string into a symcode and generate the code image
     fn generate_symcode_random(&mut self) -> Result<(BinaryImage, String), &str> {
         let symbol_num_bits = num_bits_to_store(GlyphLabel::num_variants());
-        let num_symbols = self.config.num_glyphs_in_code();
+        let num_symbols = self.config.params.num_glyphs_in_code();
 
         // Dummy data
         let payload = BitVec::from_fn(
@@ -179,29 +319,8 @@ impl SymcodeScanner for Acute32SymcodeMain {
     type Err = JsValue;
 
     fn scan(&self, image: ColorImage) -> Result<Self::SymcodeRepresentation, Self::Err> {
-        let acute32 = Acute32::new(&self.config);
-
-        // Stage 1: Locate finder candidates
-        let finder_positions = match acute32.get_finder().find(
-            &image
-        ) {
-            Ok(finder_positions) => finder_positions,
-            Err(e) => {
-                return Err(("Failed at Stage 1: ".to_owned() + e).into());
-            }
-        };
-
-        // Stage 2: Fit a perspective transform from the image space to the object space
-        let image_to_object = match acute32.get_fitter().fit(
-            finder_positions,
-            image.width,
-            image.height
-        ) {
-            Ok(image_to_object) => image_to_object,
-            Err(e) => {
-                return Err(("Failed at Stage 2: ".to_owned() + e).into());
-            }
-        };
+        let image_to_object = self.locate(&image)?;
+        let acute32 = Acute32::new(&self.config).debugger(self.debugger.as_ref());
 
         // Stage 3: Recognize the glyphs
         let symcode_instance = match acute32.get_reader().read(
@@ -238,25 +357,6 @@ impl SymcodeGenerator for Acute32SymcodeMain {
     type SymcodeRepresentation = Vec<GlyphLabel>;
 
     fn generate(&self, symcode: Self::SymcodeRepresentation) -> BinaryImage {
-        let mut symcode_image = BinaryImage::new_w_h(self.config.code_width, self.config.code_height);
-
-        // Put in the finders
-        let finder_image = self.config.finder.to_image(self.config.symbol_width, self.config.symbol_height);
-        self.config.finder_positions.iter().for_each(|finder_center| {
-            let top_left = finder_center.to_point_i32() - PointI32::new((self.config.symbol_width >> 1) as i32, (self.config.symbol_height >> 1) as i32);
-            symcode_image.paste_from(&finder_image, top_left);
-        });
-
-        // Put in the glyphs
-        symcode.iter().enumerate().for_each(|(i, &glyph_label)| {
-            if glyph_label != GlyphLabel::Invalid {
-                let glyph_top_left = self.config.glyph_anchors[i];
-                if let Some(glyph) = self.config.symbol_library.get_glyph_with_label(glyph_label) {
-                    symcode_image.paste_from(&glyph.image, glyph_top_left.to_point_i32());
-                }
-            }
-        });
-
-        symcode_image
+        Acute32::new(&self.config).get_generator().generate(symcode)
     }
 }
\ No newline at end of file